@@ -0,0 +1,437 @@
+//! Local HTTP API for scripts and editor plugins, served while the app runs (Settings →
+//! Integrations). It listens on 127.0.0.1 only, and every request needs the token from Settings
+//! as `Authorization: Bearer <token>`.
+//!
+//! The server thread only reads requests. They're answered on the UI side (`handle`, called from
+//! `App`) against the live projects, so open screens update right away and changes are saved like
+//! any other edit. Bodies are the JSON of the `models` types, without the merge bookkeeping.
+use crate::deps;
+use crate::events::{SubtaskInfo, TodoInfo};
+use crate::id::Id;
+use crate::models::{edit_project_of, edit_todo, find_todo, Project, Todo};
+use chrono::NaiveDate;
+use dioxus::logger::tracing::{debug, info, warn};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{json, Value};
+use std::io::{self, Read};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+use tiny_http::{Header, Response, Server as Http};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use uuid::Uuid;
+
+pub const DEFAULT_PORT: u16 = 7419;
+const PREFIX: &str = "/api";
+// The UI is stuck or gone if it hasn't answered by then
+const REPLY_TIMEOUT: Duration = Duration::from_secs(10);
+// Largest request body read
+const MAX_BODY: u64 = 1 << 20;
+
+/// A fresh API token.
+pub fn new_token() -> String {
+    Uuid::new_v4().simple().to_string()
+}
+
+/// A request as read by the server thread. `path` is split on '/', without the `/api` prefix.
+pub struct Request {
+    pub method: String,
+    pub path: Vec<String>,
+    pub token: Option<String>,
+    pub body: Value,
+}
+
+pub struct Reply {
+    pub status: u16,
+    // `Null` sends an empty body
+    pub body: Value,
+}
+
+/// A request waiting for the UI to answer it.
+pub struct Call {
+    pub request: Request,
+    reply: mpsc::Sender<Reply>,
+}
+
+impl Call {
+    pub fn answer(self, reply: Reply) {
+        let _ = self.reply.send(reply);
+    }
+}
+
+/// The running server; dropping it stops listening.
+pub struct Server {
+    http: Arc<Http>,
+}
+
+impl Server {
+    /// Listen on `port`; requests arrive on the returned channel.
+    pub fn start(port: u16) -> io::Result<(Self, UnboundedReceiver<Call>)> {
+        let http = Arc::new(Http::http(("127.0.0.1", port)).map_err(io::Error::other)?);
+        let (calls, rx) = unbounded_channel();
+        let listener = http.clone();
+        thread::Builder::new().name("api".into()).spawn(move || serve(&listener, &calls))?;
+        info!("[Api] Listening on http://127.0.0.1:{}{}", port, PREFIX);
+        Ok((Self { http }, rx))
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.http.unblock();
+    }
+}
+
+fn serve(http: &Http, calls: &UnboundedSender<Call>) {
+    for mut req in http.incoming_requests() {
+        let reply = match read(&mut req) {
+            Ok(request) => ask(calls, request),
+            Err(reply) => reply,
+        };
+        debug!("[Api] {} {} -> {}", req.method(), req.url(), reply.status);
+        let response = match reply.body {
+            Value::Null => Response::from_string(String::new()),
+            body => Response::from_string(body.to_string())
+                .with_header(Header::from_bytes("Content-Type", "application/json").expect("valid header")),
+        };
+        if let Err(e) = req.respond(response.with_status_code(reply.status)) {
+            warn!("[Api] Failed to send a response: {}", e);
+        }
+    }
+}
+
+fn read(req: &mut tiny_http::Request) -> Result<Request, Reply> {
+    let url = req.url().split('?').next().unwrap_or_default();
+    let Some(rest) = url.strip_prefix(PREFIX).filter(|r| r.is_empty() || r.starts_with('/')) else {
+        return Err(failure(404, "Not found; the API is under /api".into()));
+    };
+    let path = rest.split('/').filter(|s| !s.is_empty()).map(str::to_string).collect();
+    let token = req.headers().iter()
+        .find(|h| h.field.equiv("Authorization"))
+        .and_then(|h| h.value.as_str().strip_prefix("Bearer ").map(|t| t.trim().to_string()));
+    let mut text = String::new();
+    req.as_reader().take(MAX_BODY).read_to_string(&mut text).map_err(|e| failure(400, format!("Couldn't read the body: {e}")))?;
+    let body = match text.trim() {
+        "" => Value::Null,
+        text => serde_json::from_str(text).map_err(|e| failure(400, format!("The body isn't JSON: {e}")))?,
+    };
+    Ok(Request { method: req.method().as_str().to_uppercase(), path, token, body })
+}
+
+fn ask(calls: &UnboundedSender<Call>, request: Request) -> Reply {
+    let (reply, answer) = mpsc::channel();
+    if calls.send(Call { request, reply }).is_err() {
+        return failure(503, "The app is shutting down".into());
+    }
+    answer.recv_timeout(REPLY_TIMEOUT).unwrap_or_else(|_| failure(503, "The app didn't answer in time".into()))
+}
+
+fn failure(status: u16, message: String) -> Reply {
+    Reply { status, body: json!({ "error": message }) }
+}
+
+/// Answer `request` against `projects`; also returns whether `projects` changed.
+/// On failure nothing is meant to be kept, so callers pass a copy.
+pub fn handle(projects: &mut Vec<Project>, request: &Request, token: &str, read_only: bool) -> (Reply, bool) {
+    if token.is_empty() || !request.token.as_deref().is_some_and(|given| same_token(given, token)) {
+        return (failure(401, "Missing or wrong token; see Settings → Integrations".into()), false);
+    }
+    let reading = request.method == "GET";
+    if !reading && read_only {
+        return (failure(403, "This workspace is open read-only in this instance".into()), false);
+    }
+    let path: Vec<&str> = request.path.iter().map(String::as_str).collect();
+    match route(projects, &request.method, &path, &request.body) {
+        Ok((status, body)) => (Reply { status, body }, !reading),
+        Err(Error(status, message)) => (failure(status, message), false),
+    }
+}
+
+// Compared in constant time, so how long a wrong guess takes doesn't tell how much of it was right
+fn same_token(given: &str, token: &str) -> bool {
+    given.len() == token.len() && given.bytes().zip(token.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+struct Error(u16, String);
+
+type Answer = Result<(u16, Value), Error>;
+
+/// A project as replies carry it. Like tasks and subtasks (the `events` shapes), it leaves out
+/// the merge bookkeeping (see `crdt`), which changes with every sync.
+#[derive(Serialize)]
+struct ProjectBody {
+    id: Id,
+    name: String,
+    todos: Vec<TodoInfo>,
+    columns: Vec<String>,
+    inbox: bool,
+}
+
+impl ProjectBody {
+    fn of(p: &Project) -> Self {
+        Self { id: p.id, name: p.name.clone(), todos: todos(&p.todos), columns: p.columns.clone(), inbox: p.inbox }
+    }
+}
+
+fn todos(list: &[Todo]) -> Vec<TodoInfo> {
+    list.iter().map(TodoInfo::of).collect()
+}
+
+#[derive(Deserialize)]
+struct NewProject {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct ProjectPatch {
+    name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct NewTodo {
+    title: String,
+    #[serde(default)]
+    description: String,
+    due: Option<NaiveDate>,
+}
+
+#[derive(Deserialize)]
+struct TodoPatch {
+    title: Option<String>,
+    description: Option<String>,
+    completed: Option<bool>,
+    // `null` clears the date; leaving the field out keeps it
+    #[serde(default, deserialize_with = "present")]
+    due: Option<Option<NaiveDate>>,
+    // Board column name
+    status: Option<String>,
+    blocked_by: Option<Vec<Id>>,
+    // Moves the task to the end of that project
+    project: Option<Id>,
+}
+
+#[derive(Deserialize)]
+struct NewSubtask {
+    title: String,
+    #[serde(default)]
+    description: String,
+    // Nest under this subtask instead of the top level
+    parent: Option<Id>,
+}
+
+#[derive(Deserialize)]
+struct SubtaskPatch {
+    title: Option<String>,
+    description: Option<String>,
+    completed: Option<bool>,
+}
+
+fn present<'de, D: Deserializer<'de>, T: Deserialize<'de>>(deserializer: D) -> Result<Option<T>, D::Error> {
+    T::deserialize(deserializer).map(Some)
+}
+
+fn route(projects: &mut Vec<Project>, method: &str, path: &[&str], body: &Value) -> Answer {
+    match (method, path) {
+        ("GET", ["projects"]) => ok(200, &projects.iter().map(ProjectBody::of).collect::<Vec<_>>()),
+        ("POST", ["projects"]) => {
+            let new: NewProject = parse(body)?;
+            let project = Project::new(Id::new(), required(new.name, "name")?);
+            let body = ProjectBody::of(&project);
+            projects.push(project);
+            ok(201, &body)
+        }
+        ("GET", ["projects", pid]) => ok(200, &ProjectBody::of(project_mut(projects, pid)?)),
+        ("PATCH", ["projects", pid]) => {
+            let patch: ProjectPatch = parse(body)?;
+            let p = project_mut(projects, pid)?;
+            if let Some(name) = patch.name {
+                p.name = required(name, "name")?;
+            }
+            ok(200, &ProjectBody::of(p))
+        }
+        // Leaves a tombstone on save (see `crdt`), so other copies of the workspace drop it too
+        ("DELETE", ["projects", pid]) => {
+            let p = project_mut(projects, pid)?;
+            if p.inbox {
+                return Err(Error(409, "The Inbox can't be removed".into()));
+            }
+            let (id, gone): (Id, Vec<Id>) = (p.id, p.todos.iter().map(|t| t.id).collect());
+            projects.retain(|p| p.id != id);
+            deps::forget(projects, &gone);
+            Ok((204, Value::Null))
+        }
+        ("GET", ["projects", pid, "todos"]) => ok(200, &todos(&project_mut(projects, pid)?.todos)),
+        ("POST", ["projects", pid, "todos"]) => {
+            let new: NewTodo = parse(body)?;
+            let mut todo = Todo::new(Id::new(), required(new.title, "title")?);
+            todo.description = new.description;
+            todo.due = new.due;
+            let body = TodoInfo::of(&todo);
+            project_mut(projects, pid)?.todos.push(todo);
+            ok(201, &body)
+        }
+        ("GET", ["todos", id]) => ok(200, &TodoInfo::of(todo_mut(projects, id)?)),
+        ("PATCH", ["todos", id]) => patch_todo(projects, todo_id(id)?, parse(body)?),
+        ("DELETE", ["todos", id]) => {
+            let id = todo_id(id)?;
+            let p = projects.iter_mut().find(|p| p.todos.iter().any(|t| t.id == id)).ok_or_else(|| not_found("task", id))?;
+            p.todos.retain(|t| t.id != id);
+            deps::forget(projects, &[id]);
+            Ok((204, Value::Null))
+        }
+        ("GET", ["todos", id, "subtasks"]) => ok(200, &todo_mut(projects, id)?.subtasks.iter().map(SubtaskInfo::of).collect::<Vec<_>>()),
+        ("POST", ["todos", id, "subtasks"]) => {
+            let new: NewSubtask = parse(body)?;
+            let title = required(new.title, "title")?;
+            let t = todo_mut(projects, id)?;
+            let sid = match new.parent.map(|parent| parent.upgrade_subtask(t.id)) {
+                Some(parent) => t.add_subtask(Some(parent), title).ok_or_else(|| not_found("subtask", parent))?,
+                None => t.add_subtask(None, title).ok_or_else(|| Error(500, "Couldn't add the subtask".into()))?,
+            };
+            let s = t.find_subtask_mut(sid).ok_or_else(|| not_found("subtask", sid))?;
+            s.description = new.description;
+            ok(201, &SubtaskInfo::of(s))
+        }
+        ("GET", ["todos", id, "subtasks", sid]) => {
+            let t = todo_mut(projects, id)?;
+            let sid = subtask_id(sid, t.id)?;
+            ok(200, &SubtaskInfo::of(t.find_subtask_mut(sid).ok_or_else(|| not_found("subtask", sid))?))
+        }
+        ("PATCH", ["todos", id, "subtasks", sid]) => {
+            let patch: SubtaskPatch = parse(body)?;
+            let id = todo_mut(projects, id)?.id;
+            let sid = subtask_id(sid, id)?;
+            // Finishing the last open subtask completes the task, so this can be refused as blocked
+            edit_todo(projects, id, |t| {
+                let s = t.find_subtask_mut(sid).ok_or_else(|| not_found("subtask", sid))?;
+                if let Some(title) = patch.title {
+                    s.title = required(title, "title")?;
+                }
+                if let Some(description) = patch.description {
+                    s.description = description;
+                }
+                if let Some(done) = patch.completed {
+                    // Descendants follow, ancestors and the task are re-derived, as with the checkbox
+                    s.set_completed_deep(done);
+                    t.sync_completion();
+                }
+                ok(200, &SubtaskInfo::of(t.find_subtask_mut(sid).ok_or_else(|| not_found("subtask", sid))?))
+            }).map_err(blocked)?
+        }
+        ("DELETE", ["todos", id, "subtasks", sid]) => {
+            let id = todo_mut(projects, id)?.id;
+            let sid = subtask_id(sid, id)?;
+            edit_todo(projects, id, |t| t.remove_subtask(sid)).map_err(blocked)?.ok_or_else(|| not_found("subtask", sid))?;
+            Ok((204, Value::Null))
+        }
+        _ => Err(Error(404, format!("No endpoint {method} {PREFIX}/{}", path.join("/")))),
+    }
+}
+
+fn patch_todo(projects: &mut [Project], id: Id, mut patch: TodoPatch) -> Answer {
+    patch.project = patch.project.map(Id::upgrade_project);
+    if let Some(blocked_by) = &mut patch.blocked_by {
+        blocked_by.iter_mut().for_each(|b| *b = b.upgrade_todo());
+    }
+    let Some((from, _)) = find_todo(projects, id) else { return Err(not_found("task", id)) };
+    let from = from.id;
+    if let Some(pid) = patch.project && pid != from {
+        let to = projects.iter().position(|p| p.id == pid).ok_or_else(|| not_found("project", pid))?;
+        let Some(p) = projects.iter_mut().find(|p| p.id == from) else { return Err(not_found("project", from)) };
+        let Some(i) = p.todos.iter().position(|t| t.id == id) else { return Err(not_found("task", id)) };
+        let todo = p.todos.remove(i);
+        projects[to].todos.push(todo);
+    }
+    for blocker in patch.blocked_by.iter().flatten() {
+        if find_todo(projects, *blocker).is_none() {
+            return Err(not_found("task", *blocker));
+        }
+        if deps::would_cycle(projects, id, *blocker) {
+            return Err(Error(409, format!("Waiting on {blocker} would make a dependency loop")));
+        }
+    }
+
+    // Both a status of the last column and `completed` can complete the task, so a blocked one is refused
+    edit_project_of(projects, id, |p| update_todo(p, id, patch)).map_err(blocked)?
+}
+
+fn update_todo(p: &mut Project, id: Id, patch: TodoPatch) -> Answer {
+    if let Some(status) = patch.status {
+        let col = p.columns.iter().position(|c| *c == status).ok_or_else(|| Error(422, format!("No column named \"{status}\"")))?;
+        let current = p.todos.iter().find(|t| t.id == id).map(|t| p.column_of(t));
+        if current != Some(col) {
+            p.move_to_column(id, col, None);
+        }
+    }
+    let Some(t) = p.todos.iter_mut().find(|t| t.id == id) else { return Err(not_found("task", id)) };
+    if let Some(title) = patch.title {
+        t.title = required(title, "title")?;
+    }
+    if let Some(description) = patch.description {
+        t.description = description;
+    }
+    if let Some(due) = patch.due {
+        t.due = due;
+    }
+    if let Some(blocked_by) = patch.blocked_by {
+        t.blocked_by = blocked_by;
+    }
+    if let Some(done) = patch.completed && done != t.completed {
+        t.set_completed_deep(done);
+    }
+    ok(200, &TodoInfo::of(t))
+}
+
+fn ok<T: Serialize + ?Sized>(status: u16, value: &T) -> Answer {
+    serde_json::to_value(value).map(|v| (status, v)).map_err(|e| Error(500, e.to_string()))
+}
+
+// An absent body reads as `{}`, so requests whose fields are all optional can leave it out
+fn parse<T: DeserializeOwned>(body: &Value) -> Result<T, Error> {
+    let body = if body.is_null() { json!({}) } else { body.clone() };
+    serde_json::from_value(body).map_err(|e| Error(422, format!("Invalid body: {e}")))
+}
+
+fn parse_id(s: &str) -> Result<Id, Error> {
+    s.parse().map_err(|e| Error(400, e))
+}
+
+// Numeric ids from before UUIDs (in older scripts, say) lead to the upgraded items, as links do
+fn project_id(s: &str) -> Result<Id, Error> {
+    parse_id(s).map(Id::upgrade_project)
+}
+
+fn todo_id(s: &str) -> Result<Id, Error> {
+    parse_id(s).map(Id::upgrade_todo)
+}
+
+fn subtask_id(s: &str, todo: Id) -> Result<Id, Error> {
+    parse_id(s).map(|id| id.upgrade_subtask(todo))
+}
+
+fn required(value: String, field: &str) -> Result<String, Error> {
+    let value = value.trim().to_string();
+    if value.is_empty() {
+        return Err(Error(422, format!("\"{field}\" can't be empty")));
+    }
+    Ok(value)
+}
+
+fn blocked(reason: String) -> Error {
+    Error(409, reason)
+}
+
+fn not_found(what: &str, id: Id) -> Error {
+    Error(404, format!("No {what} with id {id}"))
+}
+
+fn project_mut<'a>(projects: &'a mut [Project], pid: &str) -> Result<&'a mut Project, Error> {
+    let pid = project_id(pid)?;
+    projects.iter_mut().find(|p| p.id == pid).ok_or_else(|| not_found("project", pid))
+}
+
+fn todo_mut<'a>(projects: &'a mut [Project], id: &str) -> Result<&'a mut Todo, Error> {
+    let id = todo_id(id)?;
+    projects.iter_mut().flat_map(|p| p.todos.iter_mut()).find(|t| t.id == id).ok_or_else(|| not_found("task", id))
+}
@@ -0,0 +1,51 @@
+// Command-line options. Arguments we don't know (such as a dx-todo:// link) are left alone.
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+pub const USAGE: &str = "\
+Usage: dx_todo_app [OPTIONS] [dx-todo://LINK]
+
+Options:
+  --workspace <NAME>   Open the named workspace (created if it doesn't exist)
+  --data-dir <PATH>    Keep data in PATH for this run, outside any saved workspace
+  -h, --help           Print this help
+";
+
+#[derive(Default, Debug)]
+pub struct Args {
+    pub workspace: Option<String>,
+    pub data_dir: Option<PathBuf>,
+    pub help: bool,
+}
+
+static ARGS: OnceLock<Args> = OnceLock::new();
+
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Args, String> {
+    let mut out = Args::default();
+    let mut it = args.into_iter();
+    while let Some(arg) = it.next() {
+        // Both `--flag value` and `--flag=value`
+        let (flag, inline) = match arg.split_once('=') {
+            Some((f, v)) if f.starts_with("--") => (f.to_string(), Some(v.to_string())),
+            _ => (arg.clone(), None),
+        };
+        let mut value = |name: &str| inline.clone().or_else(|| it.next()).ok_or_else(|| format!("{name} needs a value"));
+        match flag.as_str() {
+            "--workspace" => out.workspace = Some(value("--workspace")?),
+            "--data-dir" => out.data_dir = Some(PathBuf::from(value("--data-dir")?)),
+            "-h" | "--help" => out.help = true,
+            _ => {}
+        }
+    }
+    Ok(out)
+}
+
+/// Parse the process arguments once; later calls to `args` see the result.
+pub fn init() -> Result<&'static Args, String> {
+    let parsed = parse(std::env::args().skip(1))?;
+    Ok(ARGS.get_or_init(|| parsed))
+}
+
+pub fn args() -> &'static Args {
+    ARGS.get_or_init(Args::default)
+}
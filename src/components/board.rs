@@ -0,0 +1,190 @@
+use chrono::Local;
+use dioxus::prelude::*;
+use dioxus::events::Key;
+use dioxus_router::prelude::use_navigator;
+
+use crate::components::progress::ProgressBar;
+use crate::components::projects::ProjectsState;
+use crate::components::toast::ToastState;
+use crate::deps;
+use crate::id::Id;
+use crate::models::{edit_project_of, Project, Todo};
+use crate::storage::save_projects;
+use crate::Route;
+
+// A task and whether it's waiting on open blockers
+type Card = (Todo, bool);
+
+// Kanban view of one project; columns are stored on the project
+#[component]
+pub fn Board(pid: Id) -> Element {
+    let state = use_context::<ProjectsState>();
+    let mut projects = state.projects;
+    let mut toasts = use_context::<ToastState>();
+    let nav = use_navigator();
+
+    // Card drag state, same protocol as the list: a dragged card id and what it hovers
+    let mut dragging = use_signal(|| Option::<Id>::None);
+    let mut over_card = use_signal(|| Option::<Id>::None);
+    let mut over_col = use_signal(|| Option::<usize>::None);
+    let mut editing_col = use_signal(|| Option::<usize>::None);
+    let mut edit_text = use_signal(String::new);
+    let mut new_col = use_signal(String::new);
+
+    let Some(project) = projects.read().iter().find(|p| p.id == pid).cloned() else {
+        return rsx! { div { class: "app", div { class: "card", "Project not found" } } };
+    };
+
+    let mut drop_on = move |col: usize, before: Option<Id>| {
+        let src = *dragging.read();
+        dragging.set(None);
+        over_card.set(None);
+        over_col.set(None);
+        let Some(src) = src else { return };
+        if before == Some(src) { return; }
+        // Moving into the last column completes the task, which a blocked task can't be
+        let res = edit_project_of(&mut projects.write(), src, |p| p.move_to_column(src, col, before));
+        match res {
+            Ok(_) => save_projects(&projects.read()),
+            Err(msg) => { toasts.error(msg); }
+        }
+    };
+    let mut save_rename = move || {
+        // Enter and blur both land here; Escape clears `editing_col` first
+        let Some(col) = *editing_col.read() else { return };
+        editing_col.set(None);
+        let name = edit_text.read().trim().to_string();
+        if name.is_empty() { return; }
+        // The column may be gone by now, e.g. removed by a sync while editing
+        let renamed = projects.write().iter_mut().find(|p| p.id == pid).and_then(|p| match p.columns.get(col) {
+            None => None,
+            Some(old) if *old == name => Some(true),
+            Some(_) => Some(p.rename_column(col, name.clone())),
+        });
+        match renamed {
+            Some(true) => save_projects(&projects.read()),
+            Some(false) => { toasts.error(format!("A column named \"{name}\" already exists.")); }
+            None => {}
+        }
+    };
+    let mut add_column = move || {
+        let name = new_col.read().trim().to_string();
+        if name.is_empty() { return; }
+        let added = projects.write().iter_mut().find(|p| p.id == pid).is_some_and(|p| p.add_column(name.clone()));
+        if added {
+            new_col.set(String::new());
+            save_projects(&projects.read());
+        } else {
+            toasts.error(format!("A column named \"{name}\" already exists."));
+        }
+    };
+    let mut edit_columns = move |f: &dyn Fn(&mut Project) -> bool| {
+        let changed = projects.write().iter_mut().find(|p| p.id == pid).is_some_and(f);
+        if changed { save_projects(&projects.read()); }
+    };
+
+    let last = project.columns.len() - 1;
+    let removable = project.columns.len() > 2;
+    let today = Local::now().date_naive();
+    // (column index, name, cards) in board order
+    let columns: Vec<(usize, String, Vec<Card>)> = {
+        let list = projects.read();
+        project.columns.iter().enumerate().map(|(ci, name)| {
+            let cards = project.todos.iter()
+                .filter(|t| project.column_of(t) == ci)
+                .map(|t| (t.clone(), !t.completed && !deps::open_blockers(&list, t).is_empty()))
+                .collect();
+            (ci, name.clone(), cards)
+        }).collect()
+    };
+
+    rsx! {
+        div { class: "app board-app",
+            div { class: "card",
+                div { class: "header",
+                    h1 { class: "title", "Board" }
+                    span { class: "meta", "Project: {project.name}" }
+                    div { class: "actions", style: "margin-left:auto; display:flex; gap:8px;",
+                        button { class: "btn btn-ghost", onclick: move |_| { nav.push(Route::List { pid }); }, "← List" }
+                    }
+                }
+                div { class: "board",
+                    for (ci, name, cards) in columns.into_iter() {
+                        div { key: "col-{ci}-{name}",
+                            class: if *over_col.read() == Some(ci) && over_card.read().is_none() { "board-col drag-over" } else { "board-col" },
+                            ondragover: move |e: DragEvent| { e.prevent_default(); over_col.set(Some(ci)); },
+                            ondrop: move |_| drop_on(ci, None),
+                            div { class: "board-col-head",
+                                if *editing_col.read() == Some(ci) {
+                                    input { class: "text sub-input", r#type: "text", autofocus: "true", value: "{edit_text.read()}",
+                                        oninput: move |e| edit_text.set(e.value()),
+                                        onkeydown: move |e| {
+                                            if e.key() == Key::Enter { save_rename(); }
+                                            if e.key() == Key::Escape { editing_col.set(None); }
+                                        },
+                                        onblur: move |_| save_rename(),
+                                    }
+                                } else {
+                                    span { class: "board-col-title", title: "Double-click to rename",
+                                        ondoubleclick: { let n = name.clone(); move |_| { edit_text.set(n.clone()); editing_col.set(Some(ci)); } },
+                                        "{name}"
+                                    }
+                                    if ci == last { span { class: "meta", title: "Tasks here count as completed", "✓" } }
+                                    span { class: "meta", "{cards.len()}" }
+                                }
+                                div { class: "sub-actions",
+                                    button { class: "btn btn-ghost sub-remove", title: "Move left", disabled: ci == 0, onclick: move |_| edit_columns(&|p| p.shift_column(ci, true)), "◂" }
+                                    button { class: "btn btn-ghost sub-remove", title: "Move right", disabled: ci == last, onclick: move |_| edit_columns(&|p| p.shift_column(ci, false)), "▸" }
+                                    button { class: "btn btn-ghost sub-remove", title: "Remove column (tasks move left)", disabled: !removable, onclick: move |_| edit_columns(&|p| p.remove_column(ci)), "✕" }
+                                }
+                            }
+                            ul { class: "board-cards",
+                                for (t, blocked) in cards.into_iter() {
+                                    li { key: "card-{t.id}",
+                                        class: {
+                                            let mut cls = "board-card".to_string();
+                                            if *dragging.read() == Some(t.id) { cls.push_str(" dragging"); }
+                                            if *over_card.read() == Some(t.id) { cls.push_str(" drag-over"); }
+                                            if blocked { cls.push_str(" blocked"); }
+                                            cls
+                                        },
+                                        draggable: "true",
+                                        ondragstart: move |_| dragging.set(Some(t.id)),
+                                        ondragend: move |_| { dragging.set(None); over_card.set(None); over_col.set(None); },
+                                        ondragover: move |e: DragEvent| { e.prevent_default(); e.stop_propagation(); over_card.set(Some(t.id)); over_col.set(Some(ci)); },
+                                        ondragleave: move |_| if *over_card.read() == Some(t.id) { over_card.set(None) },
+                                        ondrop: move |e: DragEvent| { e.stop_propagation(); drop_on(ci, Some(t.id)); },
+                                        onclick: move |_| { nav.push(Route::Details { pid, id: t.id }); },
+                                        span { class: if t.completed { "item-title completed" } else { "item-title" }, "{t.title}" }
+                                        div { class: "board-card-meta",
+                                            if !t.subtasks.is_empty() {
+                                                {
+                                                    let (done, total) = t.subtask_progress();
+                                                    rsx! {
+                                                        ProgressBar { done, total, class: "mini" }
+                                                        span { class: "meta", "{done}/{total}" }
+                                                    }
+                                                }
+                                            }
+                                            if blocked { span { class: "blocked-badge", "Blocked" } }
+                                            if let Some(due) = t.due {
+                                                span { class: if !t.completed && due < today { "due overdue" } else { "due" }, {format!("Due {}", due.format("%b %-d"))} }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    div { class: "board-col board-col-new",
+                        input { class: "text sub-input", r#type: "text", placeholder: "New column…", value: "{new_col.read()}",
+                            oninput: move |e| new_col.set(e.value()),
+                            onkeydown: move |e| if e.key() == Key::Enter { add_column() },
+                        }
+                        button { class: "btn btn-primary sub-add-btn", onclick: move |_| add_column(), "Add column" }
+                    }
+                }
+            }
+        }
+    }
+}
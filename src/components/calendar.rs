@@ -0,0 +1,189 @@
+use chrono::{Datelike, Duration, Local, Months, NaiveDate};
+use dioxus::prelude::*;
+use dioxus_router::prelude::use_navigator;
+
+use crate::components::projects::ProjectsState;
+use crate::id::Id;
+use crate::storage::save_projects;
+use crate::Route;
+
+// Fixed palette; a project keeps its color as long as its id doesn't change
+const PROJECT_COLORS: [&str; 8] = ["#6366f1", "#10b981", "#f59e0b", "#ef4444", "#0ea5e9", "#a855f7", "#14b8a6", "#f97316"];
+
+pub fn project_color(project_id: Id) -> &'static str {
+    PROJECT_COLORS[project_id.bucket(PROJECT_COLORS.len())]
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Span {
+    Month,
+    Week,
+}
+
+// One dated (or undated) task as shown on the calendar
+#[derive(Clone, PartialEq)]
+struct Entry {
+    project_id: Id,
+    project: String,
+    todo_id: Id,
+    title: String,
+    completed: bool,
+    due: Option<NaiveDate>,
+}
+
+fn monday_of(d: NaiveDate) -> NaiveDate {
+    d - Duration::days(d.weekday().num_days_from_monday() as i64)
+}
+
+// Days shown for the span around `anchor`, Monday-based and padded to whole weeks
+fn visible_days(span: Span, anchor: NaiveDate) -> Vec<NaiveDate> {
+    let (start, weeks) = match span {
+        Span::Week => (monday_of(anchor), 1),
+        Span::Month => {
+            let first = anchor.with_day(1).unwrap_or(anchor);
+            let last = first + Months::new(1) - Duration::days(1);
+            let start = monday_of(first);
+            (start, ((last - start).num_days() / 7 + 1) as usize)
+        }
+    };
+    (0..weeks * 7).map(|i| start + Duration::days(i as i64)).collect()
+}
+
+// Calendar of tasks from every project by due date; drag a task onto a day to reschedule
+#[component]
+pub fn Calendar() -> Element {
+    let state = use_context::<ProjectsState>();
+    let mut projects = state.projects;
+    let nav = use_navigator();
+
+    let today = Local::now().date_naive();
+    let mut span = use_signal(|| Span::Month);
+    let mut anchor = use_signal(move || today);
+    let mut dragging = use_signal(|| Option::<Id>::None);
+    let mut drag_over = use_signal(|| Option::<Option<NaiveDate>>::None);
+
+    // `None` clears the due date (dropped on the unscheduled list)
+    let mut reschedule = move |due: Option<NaiveDate>| {
+        let src = *dragging.read();
+        dragging.set(None);
+        drag_over.set(None);
+        let Some(id) = src else { return };
+        if let Some(t) = projects.write().iter_mut().flat_map(|p| p.todos.iter_mut()).find(|t| t.id == id) {
+            t.due = due;
+        }
+        save_projects(&projects.read());
+    };
+    let mut step = move |forward: bool| {
+        let a = *anchor.read();
+        let next = match (*span.read(), forward) {
+            (Span::Month, true) => a + Months::new(1),
+            (Span::Month, false) => a - Months::new(1),
+            (Span::Week, true) => a + Duration::weeks(1),
+            (Span::Week, false) => a - Duration::weeks(1),
+        };
+        anchor.set(next);
+    };
+
+    let entries: Vec<Entry> = projects.read().iter()
+        .flat_map(|p| p.todos.iter().map(move |t| Entry {
+            project_id: p.id, project: p.name.clone(), todo_id: t.id, title: t.title.clone(), completed: t.completed, due: t.due,
+        }))
+        .collect();
+    let legend: Vec<(Id, String)> = projects.read().iter().map(|p| (p.id, p.name.clone())).collect();
+    let days = visible_days(*span.read(), *anchor.read());
+    let month = anchor.read().month();
+    let heading = match *span.read() {
+        Span::Month => anchor.read().format("%B %Y").to_string(),
+        Span::Week => {
+            let (first, last) = (days[0], days[6]);
+            format!("{} – {}", first.format("%b %-d"), last.format("%b %-d, %Y"))
+        }
+    };
+    let overdue: Vec<Entry> = entries.iter().filter(|e| !e.completed && e.due.is_some_and(|d| d < today)).cloned().collect();
+    let unscheduled: Vec<Entry> = entries.iter().filter(|e| !e.completed && e.due.is_none()).cloned().collect();
+
+    // A draggable task chip; clicking opens Details in the owning project
+    let chip = move |e: Entry| {
+        let color = project_color(e.project_id);
+        let mut cls = "cal-task".to_string();
+        if e.completed { cls.push_str(" completed"); }
+        if !e.completed && e.due.is_some_and(|d| d < today) { cls.push_str(" overdue"); }
+        if *dragging.read() == Some(e.todo_id) { cls.push_str(" dragging"); }
+        rsx! {
+            div { key: "cal-{e.todo_id}", class: "{cls}", style: "border-left-color: {color};", title: "{e.project}",
+                draggable: "true",
+                ondragstart: move |_| dragging.set(Some(e.todo_id)),
+                ondragend: move |_| { dragging.set(None); drag_over.set(None); },
+                onclick: move |_| { nav.push(Route::Details { pid: e.project_id, id: e.todo_id }); },
+                "{e.title}"
+            }
+        }
+    };
+
+    rsx! {
+        div { class: "app",
+            div { class: "card",
+                div { class: "header",
+                    h1 { class: "title", "Calendar" }
+                    div { class: "actions",
+                        button { class: "btn btn-ghost", onclick: move |_| { nav.go_back(); }, "← Back" }
+                    }
+                }
+                div { class: "row cal-toolbar",
+                    button { class: "btn btn-ghost", title: "Previous", onclick: move |_| step(false), "‹" }
+                    button { class: "btn btn-ghost", onclick: move |_| anchor.set(today), "Today" }
+                    button { class: "btn btn-ghost", title: "Next", onclick: move |_| step(true), "›" }
+                    h3 { class: "cal-heading", "{heading}" }
+                    button { class: if *span.read() == Span::Month { "tab active" } else { "tab" }, onclick: move |_| span.set(Span::Month), "Month" }
+                    button { class: if *span.read() == Span::Week { "tab active" } else { "tab" }, onclick: move |_| span.set(Span::Week), "Week" }
+                }
+                div { class: "cal-legend",
+                    for (pid, name) in legend.into_iter() {
+                        span { key: "legend-{pid}", class: "meta cal-legend-item", span { class: "cal-swatch", style: "background: {project_color(pid)};" } "{name}" }
+                    }
+                }
+                div { class: "cal-layout",
+                    div { class: if *span.read() == Span::Week { "cal-grid week" } else { "cal-grid" },
+                        for wd in ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"] {
+                            div { class: "cal-weekday meta", "{wd}" }
+                        }
+                        for day in days.into_iter() {
+                            div { key: "{day}",
+                                class: {
+                                    let mut cls = "cal-day".to_string();
+                                    if *span.read() == Span::Month && day.month() != month { cls.push_str(" other-month"); }
+                                    if day == today { cls.push_str(" today"); }
+                                    if *drag_over.read() == Some(Some(day)) { cls.push_str(" drag-over"); }
+                                    cls
+                                },
+                                ondragover: move |e: DragEvent| { e.prevent_default(); drag_over.set(Some(Some(day))); },
+                                ondrop: move |_| reschedule(Some(day)),
+                                span { class: "cal-date", "{day.day()}" }
+                                for e in entries.iter().filter(|e| e.due == Some(day)).cloned() {
+                                    {chip(e)}
+                                }
+                            }
+                        }
+                    }
+                    div { class: "cal-side",
+                        h4 { class: "report-heading", "Overdue ({overdue.len()})" }
+                        for e in overdue.into_iter() {
+                            {chip(e)}
+                        }
+                        div { class: if *drag_over.read() == Some(None) { "cal-unscheduled drag-over" } else { "cal-unscheduled" },
+                            ondragover: move |e: DragEvent| { e.prevent_default(); drag_over.set(Some(None)); },
+                            ondrop: move |_| reschedule(None),
+                            h4 { class: "report-heading", "Unscheduled ({unscheduled.len()})" }
+                            if unscheduled.is_empty() {
+                                p { class: "meta", "Drop a task here to clear its date." }
+                            }
+                            for e in unscheduled.into_iter() {
+                                {chip(e)}
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
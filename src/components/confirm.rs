@@ -0,0 +1,19 @@
+use dioxus::prelude::*;
+use dioxus::events::Key;
+
+// Modal yes/no prompt; the overlay captures keys so Escape cancels
+#[component]
+pub fn ConfirmDialog(title: String, message: String, on_confirm: EventHandler<()>, on_cancel: EventHandler<()>) -> Element {
+    rsx! {
+        div { class: "modal-overlay", tabindex: 0, onkeydown: move |e: KeyboardEvent| if e.key() == Key::Escape { on_cancel.call(()) },
+            div { class: "modal",
+                h3 { class: "title", "{title}" }
+                p { class: "meta", "{message}" }
+                div { class: "actions",
+                    button { class: "btn btn-danger", autofocus: "true", onclick: move |_| on_confirm.call(()), "Confirm" }
+                    button { class: "btn btn-ghost", onclick: move |_| on_cancel.call(()), "Cancel" }
+                }
+            }
+        }
+    }
+}
@@ -8,6 +8,8 @@ pub fn FilterBar(
     on_all: EventHandler<MouseEvent>,
     on_active: EventHandler<MouseEvent>,
     on_completed: EventHandler<MouseEvent>,
+    on_blocked: EventHandler<MouseEvent>,
+    on_actionable: EventHandler<MouseEvent>,
     on_clear_completed: EventHandler<MouseEvent>,
 ) -> Element {
     rsx! {
@@ -16,6 +18,8 @@ pub fn FilterBar(
                 button { class: if matches!(active, Filter::All) { "tab active" } else { "tab" }, onclick: move |e| on_all.call(e), "All" }
                 button { class: if matches!(active, Filter::Active) { "tab active" } else { "tab" }, onclick: move |e| on_active.call(e), "Active" }
                 button { class: if matches!(active, Filter::Completed) { "tab active" } else { "tab" }, onclick: move |e| on_completed.call(e), "Completed" }
+                button { class: if matches!(active, Filter::Actionable) { "tab active" } else { "tab" }, onclick: move |e| on_actionable.call(e), "Actionable" }
+                button { class: if matches!(active, Filter::Blocked) { "tab active" } else { "tab" }, onclick: move |e| on_blocked.call(e), "Blocked" }
             }
             button { class: "btn btn-link danger", onclick: move |e| on_clear_completed.call(e), "Clear completed" }
         }
@@ -1,13 +1,16 @@
 use dioxus::prelude::*;
 use crate::models::Project;
+use crate::export::ExportFormat;
+use crate::storage::SaveStatus;
 
 #[derive(Clone, Copy)]
 pub struct HeaderState {
     pub active_project: Signal<Option<Project>>, // a snapshot for display
+    pub save_status: Signal<SaveStatus>,
 }
 
 #[component]
-pub fn Header(count: usize, on_switch: EventHandler<()>, on_export: EventHandler<()>) -> Element {
+pub fn Header(count: usize, on_stats: EventHandler<()>, on_board: EventHandler<()>, on_calendar: EventHandler<()>, on_export: EventHandler<ExportFormat>) -> Element {
     let state = use_context::<HeaderState>();
     let name = state
         .active_project
@@ -15,14 +18,23 @@ pub fn Header(count: usize, on_switch: EventHandler<()>, on_export: EventHandler
         .as_ref()
         .map(|p| p.name.clone())
         .unwrap_or_else(|| "Select a project".to_string());
+    let (save_class, save_text, save_title) = match &*state.save_status.read() {
+        SaveStatus::Saved => ("save-status", "Saved", String::new()),
+        SaveStatus::Pending => ("save-status pending", "Saving…", String::new()),
+        SaveStatus::Failed(e) => ("save-status failed", "Save failed", e.clone()),
+    };
     rsx! {
         div { class: "header",
             h1 { class: "title", "To-Do" }
-            span { class: "meta", "{count} items" }
+            button { class: "btn btn-link meta", title: "Show statistics", onclick: move |_| on_stats.call(()), "{count} items" }
             div { class: "actions", style: "margin-left:auto; display:flex; gap:8px;",
                 span { class: "meta", "Project: {name}" }
-                button { class: "btn btn-ghost", onclick: move |_| on_switch.call(()), "Switch" }
-                button { class: "btn btn-primary", onclick: move |_| on_export.call(()), "Export to PDF" }
+                span { class: "{save_class}", title: "{save_title}", role: "status", "{save_text}" }
+                button { class: "btn btn-ghost", onclick: move |_| on_board.call(()), "Board" }
+                button { class: "btn btn-ghost", onclick: move |_| on_calendar.call(()), "Calendar" }
+                button { class: "btn btn-ghost", onclick: move |_| on_stats.call(()), "Stats" }
+                button { class: "btn btn-ghost", onclick: move |_| on_export.call(ExportFormat::Html), "Export HTML" }
+                button { class: "btn btn-primary", onclick: move |_| on_export.call(ExportFormat::Pdf), "Export to PDF" }
             }
         }
     }
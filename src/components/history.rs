@@ -0,0 +1,194 @@
+use dioxus::prelude::*;
+use dioxus::events::Key;
+use dioxus_router::prelude::use_navigator;
+
+use crate::components::confirm::ConfirmDialog;
+use crate::components::projects::ProjectsState;
+use crate::components::settings::WorkspaceState;
+use crate::components::toast::ToastState;
+use crate::crdt;
+use crate::git::{self, Commit, Repo};
+use crate::models::Project;
+use crate::storage;
+
+// How many commits the list shows
+const LOG_LIMIT: usize = 200;
+
+// A project in the preview: name, tasks, completed tasks, titles of the open ones
+type PreviewRow = (String, usize, usize, Vec<String>);
+
+// Past states of a git-mode workspace: browse commits, preview and restore one, push and pull
+#[component]
+pub fn History() -> Element {
+    let mut state = use_context::<ProjectsState>();
+    let ws = use_context::<WorkspaceState>();
+    let mut toasts = use_context::<ToastState>();
+    let nav = use_navigator();
+
+    let mut selected = use_signal(|| Option::<Commit>::None);
+    let mut confirming_restore = use_signal(|| false);
+    let mut busy = use_signal(|| Option::<&'static str>::None);
+    let mut remote_input = use_signal(String::new);
+    // Bumped to reload the log and remote after pushes, pulls and restores
+    let mut refresh = use_signal(|| 0u32);
+
+    let log = use_resource(move || async move {
+        let _ = refresh.read();
+        let dir = ws.dir.read().clone();
+        git::background(move || {
+            storage::flush();
+            let repo = Repo::new(dir);
+            Ok((repo.log(LOG_LIMIT)?, repo.remote_url()))
+        }).await
+    });
+    let preview = use_resource(move || async move {
+        let commit = selected.read().clone()?;
+        let dir = ws.dir.read().clone();
+        Some(git::background(move || Repo::new(dir).projects_at(&commit.id)).await)
+    });
+    use_effect(move || {
+        if let Some(Ok((_, Some(url)))) = &*log.read() && remote_input.peek().is_empty() {
+            remote_input.set(url.clone());
+        }
+    });
+
+    // Reload the projects after files changed underneath (pull). Edits made meanwhile (since `base`)
+    // aren't in the files yet, so they're merged in like any other concurrent change
+    let mut reload = move |base: Vec<Project>, pulled: Vec<Project>| {
+        storage::set_baseline(&pulled);
+        let mut ours = state.projects.peek().clone();
+        if ours == base {
+            state.projects.set(pulled);
+            return;
+        }
+        crdt::stamp(&base, &mut ours);
+        let merged = crdt::merge(&pulled, &ours);
+        storage::save_merged(&merged);
+        state.projects.set(merged);
+    };
+    let mut restore = move |commit: Commit, projects: Vec<Project>| {
+        storage::set_commit_message(format!("Restore state from {} ({})", commit.short, commit.message));
+        storage::save_merged(&projects);
+        state.projects.set(projects);
+        toasts.success(format!("Restored the state from {}.", commit.when.format("%b %-d, %H:%M")), Vec::new());
+        selected.set(None);
+        refresh += 1;
+    };
+    let mut remote_op = move |label: &'static str| {
+        if busy.read().is_some() || !storage::is_git_mode() { return; }
+        busy.set(Some(label));
+        let dir = ws.dir.peek().clone();
+        let url = remote_input.peek().trim().to_string();
+        let base = state.projects.peek().clone();
+        spawn(async move {
+            let res = git::background(move || {
+                storage::flush();
+                git::exclusive(|| {
+                    let repo = Repo::new(dir.clone());
+                    if !url.is_empty() && repo.remote_url().as_deref() != Some(url.as_str()) {
+                        repo.set_remote_url(&url)?;
+                    }
+                    match label {
+                        "Push" => repo.push().map(|_| None),
+                        _ => repo.pull().and_then(|_| git::read_tree(&dir)).map(Some),
+                    }
+                })
+            }).await;
+            busy.set(None);
+            match res {
+                Ok(pulled) => {
+                    if let Some(projects) = pulled { reload(base, projects); }
+                    toasts.success(format!("{label} finished."), Vec::new());
+                }
+                Err(e) => { toasts.error(format!("{label} failed: {e}")); }
+            }
+            refresh += 1;
+        });
+    };
+
+    let (commits, log_error) = match &*log.read() {
+        Some(Ok((commits, _))) => (commits.clone(), None),
+        Some(Err(e)) => (Vec::new(), Some(e.to_string())),
+        None => (Vec::new(), None),
+    };
+    let selected_id = selected.read().as_ref().map(|c| c.id.clone());
+    let git_mode = storage::is_git_mode();
+    let preview_rows: Option<Result<Vec<PreviewRow>, String>> = preview.read().as_ref().and_then(|p| p.as_ref()).map(|res| match res {
+        Ok(projects) => Ok(projects.iter().map(|p| {
+            let open: Vec<String> = p.todos.iter().filter(|t| !t.completed).map(|t| t.title.clone()).collect();
+            (p.name.clone(), p.todos.len(), p.todos.len() - open.len(), open)
+        }).collect()),
+        Err(e) => Err(e.to_string()),
+    });
+
+    rsx! {
+        div { class: "app",
+            div { class: "card",
+                div { class: "header",
+                    h1 { class: "title", "History" }
+                    span { class: "meta", "{commits.len()} commits" }
+                    div { class: "actions",
+                        button { class: "btn btn-ghost", onclick: move |_| { nav.go_back(); }, "← Back" }
+                    }
+                }
+                if !git_mode {
+                    p { class: "meta", "This workspace isn't kept in git. Turn it on in Settings → Storage." }
+                }
+                div { class: "row setting-row",
+                    input { class: "text", r#type: "text", placeholder: "Remote (URL or path to a bare repository)", value: "{remote_input.read()}",
+                        oninput: move |e| remote_input.set(e.value()),
+                        onkeydown: move |e| if e.key() == Key::Enter { remote_op("Pull") },
+                    }
+                    button { class: "btn btn-ghost", disabled: busy.read().is_some() || !git_mode, onclick: move |_| remote_op("Pull"), "Pull" }
+                    button { class: "btn btn-primary", disabled: busy.read().is_some() || !git_mode, onclick: move |_| remote_op("Push"), "Push" }
+                    if let Some(label) = *busy.read() {
+                        span { class: "meta", "{label}…" }
+                    }
+                }
+                if let Some(e) = log_error {
+                    p { class: "meta", "Couldn't read the history: {e}" }
+                }
+                div { class: "history-layout",
+                    ul { class: "list history-list",
+                        for (commit, when) in commits.into_iter().map(|c| { let when = c.when.format("%Y-%m-%d %H:%M").to_string(); (c, when) }) {
+                            li { key: "{commit.id}",
+                                class: if selected_id.as_deref() == Some(commit.id.as_str()) { "history-item active" } else { "history-item" },
+                                onclick: { let commit = commit.clone(); move |_| selected.set(Some(commit.clone())) },
+                                span { class: "history-message", "{commit.message}" }
+                                span { class: "meta", "{when} · {commit.author} · {commit.short}" }
+                            }
+                        }
+                    }
+                    div { class: "history-preview",
+                        match preview_rows {
+                            None => rsx! { p { class: "meta", "Select a commit to see the workspace as it was." } },
+                            Some(Err(e)) => rsx! { p { class: "meta", "Couldn't read this commit: {e}" } },
+                            Some(Ok(rows)) => rsx! {
+                                button { class: "btn btn-primary", onclick: move |_| confirming_restore.set(true), "Restore this state" }
+                                for (name, total, done, open) in rows.into_iter() {
+                                    h4 { class: "report-heading", "{name} · {done}/{total} done" }
+                                    ul { class: "history-tasks",
+                                        for (i, title) in open.into_iter().enumerate() {
+                                            li { key: "{name}-{i}", "{title}" }
+                                        }
+                                    }
+                                }
+                            },
+                        }
+                    }
+                }
+                if *confirming_restore.read() {
+                    ConfirmDialog { title: "Restore this state?", message: "Your tasks are replaced by the ones from this commit. The current state stays in the history, so this can be undone.",
+                        on_confirm: move |_| {
+                            confirming_restore.set(false);
+                            let commit = selected.peek().clone();
+                            let projects = preview.peek().as_ref().and_then(|p| p.as_ref()).and_then(|r| r.as_ref().ok()).cloned();
+                            if let (Some(commit), Some(projects)) = (commit, projects) { restore(commit, projects); }
+                        },
+                        on_cancel: move |_| confirming_restore.set(false),
+                    }
+                }
+            }
+        }
+    }
+}
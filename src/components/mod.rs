@@ -3,3 +3,16 @@ pub mod add_form;
 pub mod filter_bar;
 pub mod todo_item;
 pub mod projects;
+pub mod toast;
+pub mod report;
+pub mod stats;
+pub mod progress;
+pub mod subtask_tree;
+pub mod board;
+pub mod calendar;
+pub mod smart_view;
+pub mod sidebar;
+pub mod confirm;
+pub mod settings;
+pub mod sync;
+pub mod history;
@@ -0,0 +1,13 @@
+use dioxus::prelude::*;
+
+// Thin completion bar; `total == 0` renders empty
+#[component]
+pub fn ProgressBar(done: usize, total: usize, #[props(default)] class: String) -> Element {
+    let pct = (done * 100).checked_div(total).unwrap_or(0);
+    rsx! {
+        div { class: "progress {class}", title: "{done}/{total} ({pct}%)", role: "progressbar",
+            aria_valuemin: "0", aria_valuemax: "100", aria_valuenow: "{pct}",
+            div { class: "progress-fill", style: "width: {pct}%" }
+        }
+    }
+}
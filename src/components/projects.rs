@@ -1,54 +1,110 @@
 use dioxus::prelude::*;
 use dioxus_router::prelude::use_navigator;
+use dioxus::logger::tracing::{debug, warn};
+use crate::id::Id;
 use crate::models::Project;
 use crate::storage::save_projects;
 use crate::Route;
+use crate::components::toast::{ToastAction, ToastState};
+use crate::components::progress::ProgressBar;
+use crate::components::settings::SettingsState;
+use crate::logging;
+use crate::export;
+use crate::views::SmartView;
 
 #[derive(Clone, Copy)]
 pub struct ProjectsState {
     pub projects: Signal<Vec<Project>>,
-    pub active_project_id: Signal<Option<u64>>,
+    pub active_project_id: Signal<Option<Id>>,
+}
+
+impl ProjectsState {
+    /// Create a project and save; returns its id (None for a blank name).
+    pub fn add_project(&mut self, name: &str) -> Option<Id> {
+        let name = name.trim().to_string();
+        if name.is_empty() { return None; }
+        let id = Id::new();
+        self.projects.write().push(Project::new(id, name));
+        save_projects(&self.projects.read());
+        Some(id)
+    }
 }
 
 #[component]
 pub fn Projects() -> Element {
-    let state = use_context::<ProjectsState>();
-    let mut projects = state.projects;
-    let mut active = state.active_project_id;
+    let mut state = use_context::<ProjectsState>();
+    let projects = state.projects;
+    let mut toasts = use_context::<ToastState>();
+    let settings = use_context::<SettingsState>().settings;
     let nav = use_navigator();
 
     let mut new_name = use_signal(String::new);
 
     let mut add_project = move |name: String| {
-        let name = name.trim().to_string();
-        if name.is_empty() { return; }
-        let next_id = projects.read().iter().map(|p| p.id).max().unwrap_or(0) + 1;
-        projects.write().push(Project { id: next_id, name: name.clone(), todos: Vec::new() });
-        active.set(Some(next_id));
-        save_projects(&projects.read());
-        println!("[Projects] Navigating to List after add");
-        nav.push(Route::List {});
+        if let Some(pid) = state.add_project(&name) {
+            debug!("[Projects] Navigating to List after add");
+            nav.push(Route::List { pid });
+        }
     };
 
+    let today = chrono::Local::now().date_naive();
+    let views: Vec<(SmartView, usize)> = SmartView::ALL.iter().map(|v| (*v, v.badge_count(&projects.read(), today))).collect();
+
     rsx! {
         div { class: "app",
             div { class: "card",
+                div { class: "smart-views",
+                    for (view, count) in views.into_iter() {
+                        button { key: "view-{view}", class: "tab", onclick: move |_| { nav.push(Route::View { view }); },
+                            "{view.label()}"
+                            span { class: "view-count", "{count}" }
+                        }
+                    }
+                }
                 h2 { class: "title", "Projects" }
                 ul { class: "list",
                     for p in projects.read().iter().cloned() {
                         li { class: "list-item",
                             div { class: "content",
                                 div { class: "item-title", "{p.name}" }
+                                {
+                                    let total = p.todos.len();
+                                    let done = p.todos.iter().filter(|t| t.completed).count();
+                                    rsx! {
+                                        div { class: "project-progress",
+                                            ProgressBar { done, total }
+                                            span { class: "meta", "{total - done} open · {done}/{total} done" }
+                                        }
+                                    }
+                                }
                             }
                             div { class: "actions",
-                                button { class: "btn btn-primary", onclick: move |_| { println!("[Projects] Open clicked for id={} name={}", p.id, p.name); active.set(Some(p.id)); println!("[Projects] Navigating to List after open"); nav.push(Route::List {}); }, "Open" }
+                                button { class: "btn btn-primary", onclick: move |_| { debug!("[Projects] Open clicked for id={} name={}", p.id, p.name); debug!("[Projects] Navigating to List after open"); nav.push(Route::List { pid: p.id }); }, "Open" }
                             }
                         }
                     }
                 }
                 div { class: "row", style: "gap:8px; margin-top: 12px;",
                     input { class: "text", placeholder: "New project name", value: "{new_name.read()}", oninput: move |e| new_name.set(e.value()) }
-                    button { class: "btn btn-primary", onclick: move |_| { let n = new_name.read().clone(); if !n.trim().is_empty() { println!("[Projects] Add clicked with name={}", n); add_project(n); new_name.set(String::new()); } }, "Add" }
+                    button { class: "btn btn-primary", onclick: move |_| { let n = new_name.read().clone(); if !n.trim().is_empty() { debug!("[Projects] Add clicked with name={}", n); add_project(n); new_name.set(String::new()); } }, "Add" }
+                }
+                div { class: "row", style: "margin-top: 12px; gap: 16px;",
+                    button { class: "btn btn-link", onclick: move |_| { nav.push(Route::Report {}); }, "Progress report" }
+                    button { class: "btn btn-link", onclick: move |_| { nav.push(Route::Stats {}); }, "Statistics" }
+                    button { class: "btn btn-link", onclick: move |_| { nav.push(Route::Calendar {}); }, "Calendar" }
+                    button { class: "btn btn-link", onclick: move |_| {
+                        let prefs = settings.read().clone();
+                        let res = export::export_all_projects_html(&projects.read(), prefs.export_dir.as_deref());
+                        export::notify(&mut toasts, res, prefs.open_after_export);
+                    }, "Export all to HTML" }
+                    button { class: "btn btn-link", onclick: move |_| { nav.push(Route::Settings {}); }, "Settings" }
+                    button { class: "btn btn-link", title: "Copy app version, platform and recent logs for a bug report",
+                        onclick: move |_| match logging::copy_diagnostics_to_clipboard() {
+                            Ok(()) => { toasts.success("Diagnostics copied to clipboard", logging::current_log_file().map(|f| vec![ToastAction::OpenFile(f.clone()), ToastAction::ShowInFolder(f)]).unwrap_or_default()); }
+                            Err(e) => { warn!("[Projects] Copy diagnostics failed: {}", e); toasts.error(format!("Couldn't copy diagnostics: {e}")); }
+                        },
+                        "Copy diagnostics"
+                    }
                 }
             }
         }
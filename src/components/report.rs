@@ -0,0 +1,102 @@
+use chrono::{Datelike, Duration, Local, NaiveDate};
+use dioxus::prelude::*;
+use dioxus_router::prelude::use_navigator;
+
+use crate::components::progress::ProgressBar;
+use crate::components::projects::ProjectsState;
+use crate::components::settings::SettingsState;
+use crate::components::toast::ToastState;
+use crate::export::{self, ExportFormat};
+use crate::report;
+use crate::Route;
+
+fn parse_date(v: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(v, "%Y-%m-%d").ok()
+}
+
+#[component]
+pub fn Report() -> Element {
+    let state = use_context::<ProjectsState>();
+    let projects = state.projects;
+    let mut toasts = use_context::<ToastState>();
+    let settings = use_context::<SettingsState>().settings;
+    let nav = use_navigator();
+
+    let today = Local::now().date_naive();
+    // Default to the last seven days including today
+    let mut from = use_signal(move || today - Duration::days(6));
+    let mut to = use_signal(move || today);
+
+    // Monday-based weeks; 0 = this week, 1 = last week
+    let mut select_week = move |weeks_back: i64| {
+        let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64) - Duration::weeks(weeks_back);
+        from.set(monday);
+        to.set(monday + Duration::days(6));
+    };
+
+    let rep = report::build(&projects.read(), *from.read(), *to.read(), today);
+    let mut export_as = move |format: ExportFormat| {
+        let rep = report::build(&projects.read(), *from.read(), *to.read(), today);
+        let prefs = settings.read().clone();
+        let res = export::export_report(&rep, format, prefs.export_dir.as_deref());
+        export::notify(&mut toasts, res, prefs.open_after_export);
+    };
+
+    rsx! {
+        div { class: "app",
+            div { class: "card",
+                div { class: "header",
+                    h1 { class: "title", "Progress report" }
+                    div { class: "actions",
+                        button { class: "btn btn-ghost", onclick: move |_| { nav.push(Route::Projects {}); }, "← Back" }
+                        button { class: "btn btn-ghost", onclick: move |_| export_as(ExportFormat::Markdown), "Markdown" }
+                        button { class: "btn btn-ghost", onclick: move |_| export_as(ExportFormat::Html), "HTML" }
+                        button { class: "btn btn-primary", onclick: move |_| export_as(ExportFormat::Pdf), "PDF" }
+                    }
+                }
+                div { class: "row report-range",
+                    label { class: "meta", r#for: "report-from", "From" }
+                    input { id: "report-from", class: "text due-input", r#type: "date", value: "{from.read().format(\"%Y-%m-%d\")}",
+                        onchange: move |e| if let Some(d) = parse_date(&e.value()) { from.set(d) } }
+                    label { class: "meta", r#for: "report-to", "To" }
+                    input { id: "report-to", class: "text due-input", r#type: "date", value: "{to.read().format(\"%Y-%m-%d\")}",
+                        onchange: move |e| if let Some(d) = parse_date(&e.value()) { to.set(d) } }
+                    button { class: "tab", onclick: move |_| select_week(0), "This week" }
+                    button { class: "tab", onclick: move |_| select_week(1), "Last week" }
+                }
+                if rep.from > rep.to {
+                    p { class: "meta", "The start date is after the end date." }
+                }
+                ul { class: "list",
+                    for pr in rep.projects.iter().cloned() {
+                        li { class: "list-item report-item",
+                            div { class: "content",
+                                div { class: "item-title", "{pr.name}" }
+                                div { class: "report-summary",
+                                    ProgressBar { done: pr.completed.len(), total: pr.completed.len() + pr.open_at_end }
+                                    span { class: "meta", "{pr.summary()}" }
+                                }
+                                if pr.is_empty() {
+                                    p { class: "meta", "No activity in this period." }
+                                }
+                                for (heading, items, date_prefix) in pr.sections() {
+                                    if !items.is_empty() {
+                                        h4 { class: "report-heading", "{heading} ({items.len()})" }
+                                        ul { class: "subtasks",
+                                            for i in items.iter() {
+                                                li { class: "sub-item",
+                                                    span { class: "sub-title", "{i.label()}" }
+                                                    span { class: "meta", "{date_prefix}{i.date}" }
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
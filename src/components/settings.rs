@@ -0,0 +1,412 @@
+use dioxus::prelude::*;
+use dioxus::events::Key;
+use dioxus_router::prelude::use_navigator;
+use rfd::FileDialog;
+use std::io;
+use std::path::PathBuf;
+
+use crate::api;
+use crate::events::{self, Hook, HookKind, Kind};
+use crate::components::projects::ProjectsState;
+use crate::components::sync::SyncState;
+use crate::components::toast::ToastState;
+use crate::folder_sync::display_value;
+use crate::logging;
+use crate::storage;
+use crate::models::Filter;
+use crate::settings::{self, Settings as Prefs, Theme};
+use crate::workspace::Workspace;
+use crate::Route;
+
+#[derive(Clone, Copy)]
+pub struct SettingsState {
+    pub settings: Signal<Prefs>,
+}
+
+impl SettingsState {
+    /// Change settings and persist them right away.
+    pub fn update(&mut self, f: impl FnOnce(&mut Prefs)) {
+        f(&mut self.settings.write());
+        settings::save(&self.settings.read());
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct WorkspaceState {
+    // Label of the open workspace: its name, or the folder given with `--data-dir`
+    pub name: Signal<String>,
+    // Folder the projects are loaded from; changing it reloads them
+    pub dir: Signal<PathBuf>,
+}
+
+impl WorkspaceState {
+    /// Open a saved workspace and make it the one to start with next time.
+    pub fn open(&mut self, settings: &mut SettingsState, ws: &Workspace) -> io::Result<()> {
+        let dir = ws.data_dir()?;
+        settings.update(|s| s.workspace = ws.name.clone());
+        self.name.set(ws.name.clone());
+        self.dir.set(dir);
+        Ok(())
+    }
+}
+
+const FILTERS: [(Filter, &str); 5] = [
+    (Filter::All, "All"),
+    (Filter::Active, "Active"),
+    (Filter::Completed, "Completed"),
+    (Filter::Actionable, "Actionable"),
+    (Filter::Blocked, "Blocked"),
+];
+
+#[component]
+pub fn Settings() -> Element {
+    let mut state = use_context::<SettingsState>();
+    let mut ws_state = use_context::<WorkspaceState>();
+    let mut toasts = use_context::<ToastState>();
+    let nav = use_navigator();
+    let current = state.settings.read().clone();
+
+    let mut new_ws = use_signal(String::new);
+    let mut new_ws_dir = use_signal(|| Option::<PathBuf>::None);
+    let mut add_workspace = move || {
+        let name = new_ws.read().trim().to_string();
+        if name.is_empty() { return; }
+        if state.settings.read().find_workspace(&name).is_some() {
+            toasts.error(format!("A workspace named \"{name}\" already exists."));
+            return;
+        }
+        let ws = Workspace::new(name, new_ws_dir.read().clone());
+        if let Some(other) = state.settings.read().workspace_sharing_dir(&ws) {
+            toasts.error(format!("\"{}\" would share its folder with \"{}\"; pick another name or folder.", ws.name, other.name));
+            return;
+        }
+        state.update(|s| s.workspaces.push(ws));
+        new_ws.set(String::new());
+        new_ws_dir.set(None);
+    };
+    let mut open_workspace = move |ws: Workspace| match ws_state.open(&mut state, &ws) {
+        Ok(()) => { nav.push(Route::Projects {}); }
+        Err(e) => { toasts.error(format!("Couldn't open workspace \"{}\": {e}", ws.name)); }
+    };
+    let open_name = ws_state.name.read().clone();
+    let workspaces: Vec<(Workspace, String)> = current.workspaces.iter()
+        .map(|w| (w.clone(), w.data_dir().map(|d| d.display().to_string()).unwrap_or_default()))
+        .collect();
+    let removable = workspaces.len() > 1;
+
+    let mut sync = use_context::<SyncState>();
+    let projects_state = use_context::<ProjectsState>();
+    let open_saved = current.find_workspace(&open_name).cloned();
+    let mut set_sync_dir = move |dir: Option<PathBuf>| {
+        let name = ws_state.name.peek().clone();
+        state.update(|s| if let Some(w) = s.workspaces.iter_mut().find(|w| w.name == name) { w.sync_dir = dir; });
+    };
+    let sync_status = sync.status.read().clone();
+    let conflicts: Vec<(usize, String, String, String, String, String)> = sync.engine.read().as_ref()
+        .map(|e| e.conflicts().iter().enumerate()
+            .map(|(i, c)| (i, c.label.clone(), c.field_label(), display_value(&c.kept), display_value(&c.other), c.device.clone()))
+            .collect())
+        .unwrap_or_default();
+
+    let export_dir = current.export_dir.as_ref().map(|d| d.display().to_string());
+
+    let mut api_port = use_signal(move || state.settings.peek().api_port.to_string());
+    let mut set_api_port = move || match api_port.read().trim().parse::<u16>() {
+        Ok(port) if port > 0 => state.update(|s| s.api_port = port),
+        _ => { toasts.error(format!("\"{}\" isn't a port number (1–65535).", api_port.read().trim())); }
+    };
+    let api_url = format!("http://127.0.0.1:{}/api", current.api_port);
+
+    let mut hook_kind = use_signal(|| HookKind::Webhook);
+    let mut hook_target = use_signal(String::new);
+    let mut hook_events = use_signal(Vec::<Kind>::new);
+    let mut add_hook = move || {
+        let target = hook_target.read().trim().to_string();
+        let kind = *hook_kind.read();
+        if target.is_empty() { return; }
+        if kind == HookKind::Webhook && !(target.starts_with("http://") || target.starts_with("https://")) {
+            toasts.error("A webhook needs an http:// or https:// URL.");
+            return;
+        }
+        let events = Kind::ALL.into_iter().filter(|k| hook_events.read().contains(k)).collect();
+        state.update(|s| s.hooks.push(Hook { kind, target, events, enabled: true }));
+        hook_target.set(String::new());
+        hook_events.set(Vec::new());
+    };
+    let test_hook = move |hook: Hook| {
+        spawn(async move {
+            match events::test(hook).await {
+                Ok(()) => { toasts.info("Test event delivered."); }
+                Err(e) => { toasts.error(format!("The hook failed: {e}")); }
+            }
+        });
+    };
+    let hooks: Vec<(usize, Hook, String)> = current.hooks.iter().cloned().enumerate()
+        .map(|(i, h)| {
+            let wants = if h.events.is_empty() { "All events".to_string() } else { h.events.iter().map(|k| k.label()).collect::<Vec<_>>().join(", ") };
+            (i, h, wants)
+        })
+        .collect();
+
+    rsx! {
+        div { class: "app",
+            div { class: "card",
+                div { class: "header",
+                    h1 { class: "title", "Settings" }
+                    div { class: "actions",
+                        button { class: "btn btn-ghost", onclick: move |_| { nav.go_back(); }, "← Back" }
+                    }
+                }
+                div { class: "list settings",
+                    h3 { class: "stats-heading", "Workspaces" }
+                    p { class: "meta", "Each workspace keeps its projects in its own folder. Start with one directly using --workspace NAME or --data-dir PATH." }
+                    ul { class: "subtasks workspaces",
+                        for (ws, dir) in workspaces.into_iter() {
+                            li { key: "ws-{ws.name}", class: "sub-item",
+                                span { class: "sub-title setting-label", "{ws.name}" }
+                                span { class: "meta setting-value", title: "{dir}", "{dir}" }
+                                if ws.name == open_name {
+                                    span { class: "meta", "Open" }
+                                } else {
+                                    button { class: "btn btn-ghost", onclick: { let ws = ws.clone(); move |_| open_workspace(ws.clone()) }, "Open" }
+                                    button { class: "btn btn-ghost sub-remove", title: "Remove from the list (files stay on disk)", disabled: !removable,
+                                        onclick: { let name = ws.name.clone(); move |_| { let name = name.clone(); state.update(|s| s.workspaces.retain(|w| w.name != name)); } },
+                                        "✕"
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    div { class: "row setting-row",
+                        input { class: "text", r#type: "text", placeholder: "New workspace name", value: "{new_ws.read()}",
+                            oninput: move |e| new_ws.set(e.value()),
+                            onkeydown: move |e| if e.key() == Key::Enter { add_workspace() },
+                        }
+                        button { class: "btn btn-ghost", title: "Keep this workspace's data in a folder of your choice, e.g. a synced one",
+                            onclick: move |_| {
+                                if let Some(dir) = FileDialog::new().set_title("Workspace folder").pick_folder() { new_ws_dir.set(Some(dir)); }
+                            },
+                            {new_ws_dir.read().as_ref().map(|d| d.display().to_string()).unwrap_or_else(|| "Folder…".into())}
+                        }
+                        button { class: "btn btn-primary", onclick: move |_| add_workspace(), "Add" }
+                    }
+
+                    h3 { class: "stats-heading", "Sync" }
+                    match open_saved {
+                        None => rsx! { p { class: "meta", "Sync is available for saved workspaces, not for folders opened with --data-dir." } },
+                        Some(ws) => rsx! {
+                            p { class: "meta", "Share a folder between your machines (Syncthing, Dropbox, a network drive) to sync the \"{ws.name}\" workspace through it. Each device writes its own change file there." }
+                            div { class: "row setting-row",
+                                span { class: "setting-label", "Sync folder" }
+                                span { class: "meta setting-value", {ws.sync_dir.as_ref().map(|d| d.display().to_string()).unwrap_or_else(|| "Off".into())} }
+                                button { class: "btn btn-ghost",
+                                    onclick: move |_| {
+                                        if let Some(dir) = FileDialog::new().set_title("Sync folder").pick_folder() { set_sync_dir(Some(dir)); }
+                                    },
+                                    "Choose…"
+                                }
+                                if ws.sync_dir.is_some() {
+                                    button { class: "btn btn-ghost", onclick: move |_| sync.sync_folder(projects_state, toasts), "Sync now" }
+                                    button { class: "btn btn-ghost", onclick: move |_| set_sync_dir(None), "Stop syncing" }
+                                }
+                            }
+                            if let Some(status) = sync_status {
+                                p { class: "meta", "{status} · this device is {current.device_id}" }
+                            }
+                        },
+                    }
+                    if !conflicts.is_empty() {
+                        h4 { class: "report-heading", "Conflicts ({conflicts.len()})" }
+                        ul { class: "subtasks conflict-list",
+                            for (i, label, field, kept, other, device) in conflicts.into_iter() {
+                                li { key: "sync-conflict-{i}", class: "sub-item sync-conflict",
+                                    div { class: "sub-title",
+                                        strong { "{label}" }
+                                        span { class: "meta", " · {field}: kept “{kept}”, {device} had “{other}”" }
+                                    }
+                                    button { class: "btn btn-ghost", onclick: move |_| sync.settle(i, true, projects_state), "Use “{other}”" }
+                                    button { class: "btn btn-ghost", onclick: move |_| sync.settle(i, false, projects_state), "Keep" }
+                                }
+                            }
+                        }
+                    }
+
+                    h3 { class: "stats-heading", "Appearance" }
+                    div { class: "row setting-row",
+                        label { class: "setting-label", r#for: "setting-theme", "Theme" }
+                        select { id: "setting-theme", class: "text",
+                            onchange: move |e| {
+                                let pick = Theme::ALL.into_iter().find(|t| t.attr() == e.value());
+                                if let Some(t) = pick { state.update(|s| s.theme = t); }
+                            },
+                            for t in Theme::ALL {
+                                option { value: t.attr(), selected: current.theme == t, "{t.label()}" }
+                            }
+                        }
+                    }
+
+                    h3 { class: "stats-heading", "Tasks" }
+                    div { class: "row setting-row",
+                        label { class: "setting-label", r#for: "setting-filter", "Default filter" }
+                        select { id: "setting-filter", class: "text",
+                            onchange: move |e| {
+                                let pick = FILTERS.iter().find(|(_, l)| *l == e.value()).map(|(f, _)| *f);
+                                if let Some(f) = pick { state.update(|s| s.default_filter = f); }
+                            },
+                            for (f, l) in FILTERS {
+                                option { value: l, selected: current.default_filter == f, "{l}" }
+                            }
+                        }
+                    }
+                    label { class: "row setting-row setting-check",
+                        input { r#type: "checkbox", checked: current.confirm_delete,
+                            onchange: move |e| state.update(|s| s.confirm_delete = e.checked()) }
+                        "Confirm before removing tasks"
+                    }
+
+                    h3 { class: "stats-heading", "Storage" }
+                    if let Some(ws) = current.find_workspace(&open_name).cloned() {
+                        div { class: "row setting-row",
+                            label { class: "setting-check",
+                                input { r#type: "checkbox", checked: ws.git,
+                                    onchange: move |e| {
+                                        let on = e.checked();
+                                        let name = ws.name.clone();
+                                        state.update(|s| if let Some(w) = s.workspaces.iter_mut().find(|w| w.name == name) { w.git = on; });
+                                        // Write the current state in the new format right away
+                                        storage::set_git_mode(on);
+                                        if on { storage::set_commit_message("Start history".into()); }
+                                        storage::save_projects(&projects_state.projects.peek());
+                                    },
+                                }
+                                "Keep this workspace in git (a commit per save)"
+                            }
+                            if ws.git {
+                                button { class: "btn btn-ghost", onclick: move |_| { nav.push(Route::History {}); }, "History…" }
+                            }
+                        }
+                    }
+                    label { class: "row setting-row setting-check",
+                        input { r#type: "checkbox", checked: current.journal, disabled: storage::is_git_mode(),
+                            onchange: move |e| state.update(|s| s.journal = e.checked()) }
+                        "Keep a change journal"
+                    }
+                    p { class: "meta", "Each change is appended to projects.log as soon as it's made, and folded into projects.json from time to time. Nothing is lost if the app stops unexpectedly." }
+
+                    h3 { class: "stats-heading", "Export" }
+                    div { class: "row setting-row",
+                        span { class: "setting-label", "Save exports in" }
+                        span { class: "meta setting-value", {export_dir.unwrap_or_else(|| "Ask each time (system default)".into())} }
+                        button { class: "btn btn-ghost",
+                            onclick: move |_| {
+                                if let Some(dir) = FileDialog::new().set_title("Default export folder").pick_folder() {
+                                    state.update(|s| s.export_dir = Some(dir));
+                                }
+                            },
+                            "Choose…"
+                        }
+                        if current.export_dir.is_some() {
+                            button { class: "btn btn-ghost", onclick: move |_| state.update(|s| s.export_dir = None), "Reset" }
+                        }
+                    }
+                    label { class: "row setting-row setting-check",
+                        input { r#type: "checkbox", checked: current.open_after_export,
+                            onchange: move |e| state.update(|s| s.open_after_export = e.checked()) }
+                        "Open files after exporting"
+                    }
+
+                    h3 { class: "stats-heading", "Integrations" }
+                    label { class: "row setting-row setting-check",
+                        input { r#type: "checkbox", checked: current.api_enabled,
+                            onchange: move |e| {
+                                let on = e.checked();
+                                state.update(|s| {
+                                    s.api_enabled = on;
+                                    if s.api_token.is_empty() { s.api_token = api::new_token(); }
+                                });
+                            }
+                        }
+                        "Local API for scripts and plugins"
+                    }
+                    p { class: "meta", "While the app runs, other programs on this computer can read and change projects, tasks and subtasks at {api_url}, sending the token as \"Authorization: Bearer <token>\". See the README for the endpoints." }
+                    if current.api_enabled {
+                        div { class: "row setting-row",
+                            label { class: "setting-label", r#for: "setting-api-port", "Port" }
+                            input { id: "setting-api-port", class: "text", r#type: "number", min: "1", max: "65535", value: "{api_port.read()}",
+                                oninput: move |e| api_port.set(e.value()),
+                                onchange: move |_| set_api_port(),
+                            }
+                        }
+                        div { class: "row setting-row",
+                            span { class: "setting-label", "Token" }
+                            code { class: "meta setting-value", "{current.api_token}" }
+                            button { class: "btn btn-ghost",
+                                onclick: {
+                                    let token = current.api_token.clone();
+                                    move |_| match logging::copy_to_clipboard(token.clone()) {
+                                        Ok(()) => { toasts.info("Token copied."); }
+                                        Err(e) => { toasts.error(format!("Couldn't copy the token: {e}")); }
+                                    }
+                                },
+                                "Copy"
+                            }
+                            button { class: "btn btn-ghost", title: "Programs using the old token stop working",
+                                onclick: move |_| state.update(|s| s.api_token = api::new_token()),
+                                "New token"
+                            }
+                        }
+                    }
+
+                    h4 { class: "report-heading", "Hooks" }
+                    p { class: "meta", "Run something when tasks change: a webhook gets each event POSTed as JSON; a command gets it as JSON on stdin, with the event name in DX_TODO_EVENT. Pick the events below, or none for all of them." }
+                    ul { class: "subtasks hooks",
+                        for (i, hook, wants) in hooks.into_iter() {
+                            li { key: "hook-{i}", class: "sub-item",
+                                input { r#type: "checkbox", checked: hook.enabled, title: "Enabled",
+                                    onchange: move |e| { let on = e.checked(); state.update(|s| if let Some(h) = s.hooks.get_mut(i) { h.enabled = on; }); }
+                                }
+                                span { class: "sub-title setting-label", "{hook.kind.label()}" }
+                                span { class: "meta setting-value", title: "{hook.target}", "{hook.target} · {wants}" }
+                                button { class: "btn btn-ghost", onclick: { let hook = hook.clone(); move |_| test_hook(hook.clone()) }, "Test" }
+                                button { class: "btn btn-ghost sub-remove", title: "Remove hook",
+                                    onclick: move |_| state.update(|s| { if i < s.hooks.len() { s.hooks.remove(i); } }),
+                                    "✕"
+                                }
+                            }
+                        }
+                    }
+                    div { class: "row setting-row",
+                        select { class: "text",
+                            onchange: move |e| {
+                                let pick = HookKind::ALL.into_iter().find(|k| k.label() == e.value());
+                                if let Some(k) = pick { hook_kind.set(k); }
+                            },
+                            for k in HookKind::ALL {
+                                option { value: k.label(), selected: *hook_kind.read() == k, "{k.label()}" }
+                            }
+                        }
+                        input { class: "text", r#type: "text", value: "{hook_target.read()}",
+                            placeholder: if *hook_kind.read() == HookKind::Webhook { "https://example.com/hook" } else { "Command, e.g. notify-send \"Task changed\"" },
+                            oninput: move |e| hook_target.set(e.value()),
+                            onkeydown: move |e| if e.key() == Key::Enter { add_hook() },
+                        }
+                        button { class: "btn btn-primary", onclick: move |_| add_hook(), "Add" }
+                    }
+                    div { class: "row setting-row hook-events",
+                        for k in Kind::ALL {
+                            label { class: "setting-check",
+                                input { r#type: "checkbox", checked: hook_events.read().contains(&k),
+                                    onchange: move |e| {
+                                        let on = e.checked();
+                                        hook_events.with_mut(|list| if on { list.push(k) } else { list.retain(|x| *x != k) });
+                                    }
+                                }
+                                "{k.label()}"
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use chrono::Local;
+use dioxus::prelude::*;
+use dioxus::events::Key;
+use dioxus_router::prelude::{use_navigator, use_route};
+
+use crate::components::projects::ProjectsState;
+use crate::components::settings::{SettingsState, WorkspaceState};
+use crate::components::sync::SyncState;
+use crate::id::Id;
+use crate::storage;
+use crate::components::toast::ToastState;
+use crate::views::SmartView;
+use crate::Route;
+
+// Persistent navigation: smart views, projects with open counts and a quick "New project" field
+#[component]
+pub fn Sidebar() -> Element {
+    let mut state = use_context::<ProjectsState>();
+    let projects = state.projects;
+    let nav = use_navigator();
+    let route = use_route::<Route>();
+
+    let mut settings = use_context::<SettingsState>();
+    let mut ws_state = use_context::<WorkspaceState>();
+    let mut toasts = use_context::<ToastState>();
+    let mut collapsed = use_signal(|| false);
+    let mut new_name = use_signal(String::new);
+
+    let mut add_project = move || {
+        let name = new_name.read().clone();
+        if let Some(pid) = state.add_project(&name) {
+            new_name.set(String::new());
+            nav.push(Route::List { pid });
+        }
+    };
+
+    let workspaces: Vec<String> = settings.settings.read().workspaces.iter().map(|w| w.name.clone()).collect();
+    let open_ws = ws_state.name.read().clone();
+    let mut switch_workspace = move |name: String| {
+        let Some(ws) = settings.settings.read().find_workspace(&name).cloned() else { return };
+        match ws_state.open(&mut settings, &ws) {
+            Ok(()) => { nav.push(Route::Projects {}); }
+            Err(e) => { toasts.error(format!("Couldn't open workspace \"{name}\": {e}")); }
+        }
+    };
+
+    let sync_conflicts = use_context::<SyncState>().engine.read().as_ref().map_or(0, |e| e.conflicts().len());
+
+    let current_view = match route { Route::View { view } => Some(view), _ => None };
+    let current_project = route.project_id();
+    let today = Local::now().date_naive();
+    let views: Vec<(SmartView, usize)> = SmartView::ALL.iter().map(|v| (*v, v.badge_count(&projects.read(), today))).collect();
+    let rows: Vec<(Id, String, usize)> = projects.read().iter()
+        .filter(|p| !p.inbox)
+        .map(|p| (p.id, p.name.clone(), p.todos.iter().filter(|t| !t.completed).count()))
+        .collect();
+
+    if *collapsed.read() {
+        return rsx! {
+            nav { class: "sidebar collapsed",
+                button { class: "btn btn-ghost sidebar-toggle", title: "Show sidebar", onclick: move |_| collapsed.set(false), "»" }
+            }
+        };
+    }
+
+    rsx! {
+        nav { class: "sidebar",
+            div { class: "sidebar-head",
+                button { class: "btn btn-link sidebar-title", onclick: move |_| { nav.push(Route::Projects {}); }, "To-Do" }
+                button { class: "btn btn-ghost sidebar-toggle", title: "Hide sidebar", onclick: move |_| collapsed.set(true), "«" }
+            }
+            if workspaces.len() > 1 || !workspaces.contains(&open_ws) {
+                select { class: "text sidebar-workspace", aria_label: "Workspace",
+                    onchange: move |e| switch_workspace(e.value()),
+                    if !workspaces.contains(&open_ws) {
+                        // Opened with --data-dir: not a saved workspace
+                        option { value: "", selected: true, "{open_ws}" }
+                    }
+                    for name in workspaces.iter().cloned() {
+                        option { value: "{name}", selected: name == open_ws, "{name}" }
+                    }
+                }
+            }
+            ul { class: "sidebar-list",
+                for (view, count) in views.into_iter() {
+                    li { key: "side-{view}",
+                        button { class: if current_view == Some(view) { "sidebar-item active" } else { "sidebar-item" },
+                            onclick: move |_| { nav.push(Route::View { view }); },
+                            span { class: "sidebar-name", "{view.label()}" }
+                            if count > 0 { span { class: "view-count", "{count}" } }
+                        }
+                    }
+                }
+            }
+            h4 { class: "sidebar-heading meta", "Projects" }
+            ul { class: "sidebar-list",
+                for (pid, name, open) in rows.into_iter() {
+                    li { key: "side-p-{pid}",
+                        button { class: if current_project == Some(pid) { "sidebar-item active" } else { "sidebar-item" },
+                            onclick: move |_| { nav.push(Route::List { pid }); },
+                            span { class: "sidebar-name", "{name}" }
+                            if open > 0 { span { class: "view-count", "{open}" } }
+                        }
+                    }
+                }
+            }
+            if storage::is_git_mode() {
+                button { class: "btn btn-link sidebar-settings", onclick: move |_| { nav.push(Route::History {}); }, "History" }
+            }
+            if sync_conflicts > 0 {
+                button { class: "btn btn-link sidebar-settings sidebar-alert", onclick: move |_| { nav.push(Route::Settings {}); }, "Sync conflicts ({sync_conflicts})" }
+            }
+            button { class: "btn btn-link sidebar-settings", onclick: move |_| { nav.push(Route::Settings {}); }, "Settings" }
+            input { class: "text sidebar-new", r#type: "text", placeholder: "New project…", value: "{new_name.read()}",
+                oninput: move |e| new_name.set(e.value()),
+                onkeydown: move |e| if e.key() == Key::Enter { add_project() },
+            }
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use chrono::Local;
+use dioxus::prelude::*;
+use dioxus::events::Key;
+use dioxus_router::prelude::use_navigator;
+
+use crate::components::add_form::AddForm;
+use crate::components::confirm::ConfirmDialog;
+use crate::components::settings::SettingsState;
+use crate::components::projects::ProjectsState;
+use crate::components::todo_item::TodoItem;
+use crate::components::toast::ToastState;
+use crate::deps;
+use crate::id::Id;
+use crate::models::{edit_todo, find_todo_mut, Todo};
+use crate::storage::save_projects;
+use crate::views::SmartView;
+use crate::Route;
+
+// A smart view: tasks from every project, edited in place in their owning project
+#[component]
+pub fn SmartList(view: SmartView) -> Element {
+    let state = use_context::<ProjectsState>();
+    let mut projects = state.projects;
+    let mut toasts = use_context::<ToastState>();
+    let nav = use_navigator();
+
+    let mut new_title = use_signal(String::new);
+    let mut editing_id = use_signal(|| Option::<Id>::None);
+    let mut editing_text = use_signal(String::new);
+    let mut confirming_remove = use_signal(|| Option::<Id>::None);
+    let confirm_delete = use_context::<SettingsState>().settings.read().confirm_delete;
+
+    // Quick capture always lands in the Inbox project
+    let mut capture = move || {
+        let title = new_title.read().trim().to_string();
+        if title.is_empty() { return; }
+        if let Some(p) = projects.write().iter_mut().find(|p| p.inbox) {
+            p.todos.push(Todo::new(Id::new(), title));
+        }
+        new_title.set(String::new());
+        save_projects(&projects.read());
+    };
+    let mut toggle = move |id: Id| {
+        let res = edit_todo(&mut projects.write(), id, |t| t.set_completed_deep(!t.completed));
+        match res {
+            Ok(()) => save_projects(&projects.read()),
+            Err(msg) => { toasts.error(msg); }
+        }
+    };
+    let mut save_edit = move |id: Id| {
+        let text = editing_text.read().trim().to_string();
+        if !text.is_empty() && let Some(t) = find_todo_mut(&mut projects.write(), id) {
+            t.title = text;
+        }
+        save_projects(&projects.read());
+        editing_id.set(None);
+    };
+    let mut remove_item = move |id: Id| {
+        for p in projects.write().iter_mut() {
+            p.todos.retain(|t| t.id != id);
+        }
+        deps::forget(&mut projects.write(), &[id]);
+        save_projects(&projects.read());
+    };
+
+    let today = Local::now().date_naive();
+    let items: Vec<(Id, String, Todo, Vec<String>)> = {
+        let list = projects.read();
+        view.collect(&list, today).into_iter()
+            .map(|(pid, pname, t)| {
+                let blockers = deps::open_blockers(&list, &t).iter().map(|b| b.title.clone()).collect();
+                (pid, pname, t, blockers)
+            })
+            .collect()
+    };
+
+    rsx! {
+        div { class: "app",
+            div { class: "card",
+                div { class: "header",
+                    h1 { class: "title", "{view.label()}" }
+                    span { class: "meta", "{items.len()} items" }
+                    div { class: "actions",
+                        button { class: "btn btn-ghost", onclick: move |_| { nav.push(Route::Projects {}); }, "← Projects" }
+                    }
+                }
+                if view == SmartView::Inbox {
+                    AddForm { value: new_title.read().clone(),
+                        on_input: move |e: FormEvent| new_title.set(e.value()),
+                        on_enter: move |e: KeyboardEvent| if e.key() == Key::Enter { capture() },
+                        on_add: move |_| capture(),
+                    }
+                }
+                if items.is_empty() {
+                    p { class: "meta", "{view.empty_text()}" }
+                }
+                ul { class: "list",
+                    for (pid, pname, t, blockers) in items.into_iter() {
+                        TodoItem {
+                            key: "view-{t.id}",
+                            todo: t.clone(),
+                            pid,
+                            blockers,
+                            reorderable: false,
+                            // The Inbox view only holds Inbox tasks, so the label would be noise
+                            project: (view != SmartView::Inbox).then_some(pname),
+                            is_editing: *editing_id.read() == Some(t.id),
+                            editing_text: editing_text.read().clone(),
+                            on_toggle: move |_| toggle(t.id),
+                            on_start_edit: move |_| { editing_id.set(Some(t.id)); editing_text.set(t.title.clone()); },
+                            on_remove: move |_| if confirm_delete { confirming_remove.set(Some(t.id)) } else { remove_item(t.id) },
+                            on_save_click: move |_| save_edit(t.id),
+                            on_save_key: move |e: KeyboardEvent| if e.key() == Key::Enter { save_edit(t.id) },
+                            on_edit_input: move |e: FormEvent| editing_text.set(e.value()),
+                            on_cancel: move |_| editing_id.set(None),
+                            on_drag_start: |_| {},
+                            on_drag_over: |_| {},
+                            on_drag_leave: |_| {},
+                            on_drag_end: |_| {},
+                            on_drop: |_| {},
+                            is_dragging: false,
+                            is_drag_over: false,
+                        }
+                    }
+                }
+                if let Some(rid) = *confirming_remove.read() {
+                    ConfirmDialog { title: "Remove this task?", message: "Its subtasks are removed too. This cannot be undone.",
+                        on_confirm: move |_| { remove_item(rid); confirming_remove.set(None); },
+                        on_cancel: move |_| confirming_remove.set(None),
+                    }
+                }
+            }
+        }
+    }
+}
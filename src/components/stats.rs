@@ -0,0 +1,97 @@
+use chrono::Local;
+use dioxus::prelude::*;
+use dioxus_router::prelude::use_navigator;
+
+use crate::components::progress::ProgressBar;
+use crate::components::projects::ProjectsState;
+use crate::stats;
+use crate::Route;
+
+// Chart geometry in SVG user units
+const CHART_W: f64 = 600.0;
+const CHART_H: f64 = 140.0;
+const BAR_GAP: f64 = 4.0;
+
+#[component]
+pub fn Stats() -> Element {
+    let state = use_context::<ProjectsState>();
+    let projects = state.projects;
+    let nav = use_navigator();
+
+    let today = Local::now().date_naive();
+    let st = stats::compute(&projects.read(), today);
+
+    let open: usize = st.projects.iter().map(|p| p.open_todos).sum();
+    let done: usize = st.projects.iter().map(|p| p.completed_todos).sum();
+    let sub_open: usize = st.projects.iter().map(|p| p.open_subtasks).sum();
+    let sub_done: usize = st.projects.iter().map(|p| p.completed_subtasks).sum();
+    let last_30: usize = st.completed_per_day.iter().map(|(_, n)| n).sum();
+
+    let max_per_day = st.completed_per_day.iter().map(|(_, n)| *n).max().unwrap_or(0).max(1) as f64;
+    let bar_w = CHART_W / st.completed_per_day.len() as f64 - BAR_GAP;
+    let first_day = st.completed_per_day.first().map(|(d, _)| d.format("%b %-d").to_string()).unwrap_or_default();
+
+    rsx! {
+        div { class: "app",
+            div { class: "card",
+                div { class: "header",
+                    h1 { class: "title", "Statistics" }
+                    div { class: "actions",
+                        button { class: "btn btn-ghost", onclick: move |_| { nav.go_back(); }, "← Back" }
+                    }
+                }
+                div { class: "list stats",
+                    div { class: "stat-tiles",
+                        div { class: "stat-tile", span { class: "stat-value", "{open}" } span { class: "meta", "open tasks" } }
+                        div { class: "stat-tile", span { class: "stat-value", "{done}" } span { class: "meta", "completed tasks" } }
+                        div { class: "stat-tile", span { class: "stat-value", "{sub_done}/{sub_open + sub_done}" } span { class: "meta", "subtasks done" } }
+                        div { class: "stat-tile", span { class: "stat-value", "{last_30}" } span { class: "meta", "completed in 30 days" } }
+                        div { class: "stat-tile", span { class: "stat-value", "🔥 {st.streak}" } span { class: "meta", if st.streak == 1 { "day streak" } else { "days streak" } } }
+                    }
+
+                    h3 { class: "stats-heading", "Completed per day" }
+                    svg { class: "stats-chart", view_box: "0 0 {CHART_W} {CHART_H + 18.0}", preserve_aspect_ratio: "none",
+                        for (i, (day, n)) in st.completed_per_day.iter().cloned().enumerate() {
+                            {
+                                let h = if n == 0 { 2.0 } else { (n as f64 / max_per_day) * CHART_H };
+                                let x = i as f64 * (bar_w + BAR_GAP);
+                                rsx! {
+                                    rect { key: "{day}", class: if n == 0 { "bar empty" } else { "bar" }, x: "{x}", y: "{CHART_H - h}", width: "{bar_w}", height: "{h}", rx: "2",
+                                        title { "{day.format(\"%a %b %-d\")}: {n}" }
+                                    }
+                                }
+                            }
+                        }
+                        text { class: "axis", x: "0", y: "{CHART_H + 14.0}", "{first_day}" }
+                        text { class: "axis", x: "{CHART_W}", y: "{CHART_H + 14.0}", text_anchor: "end", "Today" }
+                    }
+
+                    h3 { class: "stats-heading", "Projects" }
+                    ul { class: "subtasks stats-projects",
+                        for p in st.projects.iter().cloned() {
+                            li { key: "stat-{p.id}", class: "sub-item stats-row",
+                                span { class: "sub-title stats-name", "{p.name}" }
+                                ProgressBar { done: p.completed_todos, total: p.todo_total() }
+                                span { class: "meta", "{p.percent()}%" }
+                                span { class: "meta", "{p.open_todos} open · {p.completed_todos}/{p.todo_total()} tasks · {p.completed_subtasks}/{p.subtask_total()} subtasks" }
+                            }
+                        }
+                    }
+
+                    h3 { class: "stats-heading", "Oldest open tasks" }
+                    if st.oldest_open.is_empty() {
+                        p { class: "meta", "Nothing open with a known creation date." }
+                    }
+                    ul { class: "subtasks",
+                        for o in st.oldest_open.iter().cloned() {
+                            li { key: "old-{o.todo_id}", class: "sub-item stats-row",
+                                button { class: "btn btn-link", onclick: move |_| { nav.push(Route::Details { pid: o.project_id, id: o.todo_id }); }, "{o.title}" }
+                                span { class: "meta", "{o.project} · open {(today - o.created).num_days()} day(s)" }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use dioxus::prelude::*;
+use dioxus::events::Key;
+
+use crate::components::todo_item::DragHandle;
+use crate::id::Id;
+use crate::models::Subtask;
+
+/// Callbacks shared by every node of the tree; bundled so recursion passes one prop.
+#[derive(Clone, Copy, PartialEq)]
+pub struct SubtaskActions {
+    pub on_toggle: EventHandler<Id>,
+    pub on_remove: EventHandler<Id>,
+    pub on_add_child: EventHandler<(Id, String)>,
+    pub on_update_desc: EventHandler<(Id, String)>,
+    pub on_rename: EventHandler<(Id, String)>,
+    pub on_promote: EventHandler<Id>,
+    // Drag & drop reordering, same protocol as `TodoItem`
+    pub on_drag_start: EventHandler<Id>,
+    pub on_drag_over: EventHandler<Id>,
+    pub on_drag_leave: EventHandler<Id>,
+    pub on_drag_end: EventHandler<Id>,
+    pub on_drop: EventHandler<Id>,
+}
+
+// One node of the subtask tree in Details; renders its children recursively
+#[component]
+pub fn SubtaskNode(
+    node: Subtask,
+    actions: SubtaskActions,
+    dragging: Option<Id>,
+    drag_over: Option<Id>,
+) -> Element {
+    let mut expanded = use_signal(|| true);
+    let mut adding = use_signal(|| false);
+    let mut show_notes = use_signal(|| false);
+    let mut child_input = use_signal(String::new);
+    let mut editing = use_signal(|| false);
+    let mut edit_text = use_signal(String::new);
+
+    let id = node.id;
+    let has_children = !node.children.is_empty();
+    // Progress of the descendants only
+    let (done, total) = node.progress();
+    let (done, total) = (done - node.completed as usize, total - 1);
+    let notes_open = *show_notes.read() || !node.description.is_empty();
+
+    let mut submit_child = move || {
+        let v = child_input.read().trim().to_string();
+        if !v.is_empty() {
+            actions.on_add_child.call((id, v));
+            child_input.set(String::new());
+            expanded.set(true);
+        }
+    };
+    let mut start_edit = move |current: String| { edit_text.set(current); editing.set(true); };
+    let mut save_edit = move || {
+        // Enter and blur both land here; Escape clears `editing` first so blur doesn't save
+        if !*editing.read() { return; }
+        let v = edit_text.read().trim().to_string();
+        if !v.is_empty() { actions.on_rename.call((id, v)); }
+        editing.set(false);
+    };
+
+    let row_class = {
+        let mut cls = "sub-item".to_string();
+        if dragging == Some(id) { cls.push_str(" dragging"); }
+        if drag_over == Some(id) { cls.push_str(" drag-over"); }
+        cls
+    };
+
+    rsx! {
+        li { class: "sub-node",
+            div { class: "{row_class}",
+                ondragover: move |e: DragEvent| { e.prevent_default(); e.stop_propagation(); actions.on_drag_over.call(id); },
+                ondragleave: move |_| actions.on_drag_leave.call(id),
+                ondrop: move |e: DragEvent| { e.stop_propagation(); actions.on_drop.call(id); },
+                DragHandle { id, on_drag_start: actions.on_drag_start, on_drag_end: actions.on_drag_end }
+                if has_children {
+                    button { class: "btn btn-ghost sub-caret", title: if *expanded.read() { "Collapse" } else { "Expand" },
+                        onclick: move |_| { let v = *expanded.read(); expanded.set(!v); },
+                        if *expanded.read() { "▾" } else { "▸" }
+                    }
+                } else {
+                    span { class: "sub-caret-spacer" }
+                }
+                input { r#type: "checkbox", checked: node.completed, onclick: move |_| actions.on_toggle.call(id) }
+                if *editing.read() {
+                    input { class: "text sub-input sub-edit", r#type: "text", autofocus: "true", value: "{edit_text.read()}",
+                        oninput: move |e| edit_text.set(e.value()),
+                        onkeydown: move |e| {
+                            if e.key() == Key::Enter { save_edit(); }
+                            if e.key() == Key::Escape { editing.set(false); }
+                        },
+                        onblur: move |_| save_edit(),
+                    }
+                } else {
+                    span { class: if node.completed { "sub-title completed" } else { "sub-title" }, title: "Double-click to rename",
+                        ondoubleclick: { let t = node.title.clone(); move |_| start_edit(t.clone()) }, "{node.title}" }
+                }
+                if has_children {
+                    span { class: "meta", "{done}/{total}" }
+                }
+                div { class: "sub-actions",
+                    button { class: "btn btn-ghost sub-remove", title: "Rename", onclick: { let t = node.title.clone(); move |_| start_edit(t.clone()) }, "✎" }
+                    button { class: "btn btn-ghost sub-remove", title: "Notes", onclick: move |_| { let v = *show_notes.read(); show_notes.set(!v); }, "¶" }
+                    button { class: "btn btn-ghost sub-remove", title: "Add a child subtask", onclick: move |_| { adding.set(true); expanded.set(true); }, "+" }
+                    button { class: "btn btn-ghost sub-remove", title: "Promote to task", onclick: move |_| actions.on_promote.call(id), "⤴" }
+                    button { class: "btn btn-ghost sub-remove", title: "Remove", onclick: move |_| actions.on_remove.call(id), "✕" }
+                }
+            }
+            if notes_open {
+                textarea { class: "text desc sub-desc", rows: "2", placeholder: "Notes…", value: "{node.description}",
+                    oninput: move |e| actions.on_update_desc.call((id, e.value())) }
+            }
+            if *expanded.read() && (has_children || *adding.read()) {
+                ul { class: "subtasks nested",
+                    for child in node.children.iter().cloned() {
+                        SubtaskNode { key: "sub-{child.id}", node: child, actions, dragging, drag_over }
+                    }
+                    if *adding.read() {
+                        li { class: "sub-add",
+                            input { class: "text sub-input", r#type: "text", placeholder: "Add a child subtask…", autofocus: "true", value: "{child_input.read()}",
+                                oninput: move |e| child_input.set(e.value()),
+                                onkeydown: move |e| {
+                                    if e.key() == Key::Enter { submit_child(); }
+                                    if e.key() == Key::Escape { adding.set(false); child_input.set(String::new()); }
+                                }
+                            }
+                            button { class: "btn btn-primary sub-add-btn", onclick: move |_| submit_child(), "Add" }
+                            button { class: "btn btn-ghost sub-add-btn", onclick: move |_| { adding.set(false); child_input.set(String::new()); }, "Cancel" }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
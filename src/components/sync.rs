@@ -0,0 +1,193 @@
+use dioxus::prelude::*;
+use dioxus::events::Key;
+use dioxus::logger::tracing::{info, warn};
+use chrono::Local;
+
+use crate::components::projects::ProjectsState;
+use crate::components::toast::ToastState;
+use crate::crdt;
+use crate::folder_sync::{self, Engine};
+use crate::models::{ensure_inbox, Project};
+use crate::storage::{self, ExternalChange};
+
+/// Which side wins when this window and the data file changed the same field.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Side {
+    Ours,
+    Theirs,
+}
+
+/// An external change to the data files that conflicts with this window's edits.
+pub struct PendingChange {
+    change: ExternalChange,
+    conflicts: Vec<String>,
+}
+
+/// Keeps the in-memory projects in step with changes made to the data file by others.
+#[derive(Clone, Copy)]
+pub struct SyncState {
+    // Another instance holds the data dir's lock; nothing is saved from this window
+    pub read_only: Signal<bool>,
+    pub pending: Signal<Option<PendingChange>>,
+    // Set while the open workspace syncs through a shared folder
+    pub engine: Signal<Option<Engine>>,
+    // Outcome of the last folder sync, for display
+    pub status: Signal<Option<String>>,
+}
+
+impl SyncState {
+    /// Pick up the data files if someone else changed them: merge them with this window's state,
+    /// or hold them for the user to decide when both changed the same items.
+    pub fn check_file(&mut self, state: ProjectsState, toasts: ToastState) {
+        let Some(change) = storage::external_change() else { return };
+        let (ours, theirs) = sides(&change, state);
+        let merged = crdt::merge(&ours, &theirs);
+        let clashes = crdt::clashes(&change.base, &ours, &theirs, &merged);
+        if clashes.is_empty() {
+            apply(change, merged, state, toasts);
+            return;
+        }
+        let mut conflicts: Vec<String> = Vec::new();
+        for c in clashes {
+            if !conflicts.contains(&c.label) {
+                conflicts.push(c.label);
+            }
+        }
+        info!("[Sync] External change conflicts with {} item(s)", conflicts.len());
+        self.pending.set(Some(PendingChange { change, conflicts }));
+    }
+
+    /// Exchange changes with other devices through the sync folder, if one is set.
+    pub fn sync_folder(&mut self, mut state: ProjectsState, mut toasts: ToastState) {
+        if *self.read_only.peek() {
+            return;
+        }
+        let (result, before) = match self.engine.write().as_mut() {
+            Some(engine) => {
+                let before = engine.conflicts().len();
+                (engine.sync(&state.projects.peek()), before)
+            }
+            None => return,
+        };
+        match result {
+            Ok(merged) => {
+                self.status.set(Some(format!("Synced at {}", Local::now().format("%H:%M"))));
+                let Some(mut merged) = merged else { return };
+                ensure_inbox(&mut merged);
+                info!("[Sync] Applied changes from other devices");
+                // Their events were sent on the devices that made them
+                storage::save_merged(&merged);
+                state.projects.set(merged);
+                let found = self.engine.peek().as_ref().map_or(0, |e| e.conflicts().len()).saturating_sub(before);
+                if found > 0 {
+                    toasts.info(format!("Sync found {found} conflicting change(s); review them in Settings."));
+                }
+            }
+            Err(e) => {
+                warn!("[Sync] Folder sync failed: {}", e);
+                self.status.set(Some(format!("Sync failed: {e}")));
+            }
+        }
+    }
+
+    /// Settle a sync conflict: `use_other` applies the value that lost, otherwise it's dismissed.
+    pub fn settle(&mut self, index: usize, use_other: bool, mut state: ProjectsState) {
+        let mut engine = self.engine.write();
+        let Some(engine) = engine.as_mut() else { return };
+        if use_other && let Some(conflict) = engine.conflicts().get(index) {
+            let updated = folder_sync::choose_other(&state.projects.peek(), conflict);
+            storage::save_projects(&updated);
+            state.projects.set(updated);
+        }
+        if let Err(e) = engine.dismiss(index) {
+            warn!("[Sync] Couldn't save sync state: {}", e);
+        }
+    }
+
+    pub fn resolve(&mut self, prefer: Side, state: ProjectsState, toasts: ToastState) {
+        let Some(PendingChange { change, .. }) = self.pending.take() else { return };
+        let (ours, theirs) = sides(&change, state);
+        let mut merged = crdt::merge(&ours, &theirs);
+        // The merge kept the later edit of each clash; where that isn't the preferred side,
+        // its value goes back in as a new edit
+        for c in crdt::clashes(&change.base, &ours, &theirs, &merged) {
+            let (value, lost) = match prefer {
+                Side::Ours => (&c.ours, c.theirs_kept),
+                Side::Theirs => (&c.theirs, !c.theirs_kept),
+            };
+            if lost {
+                crdt::put(&mut merged, c.target, &c.field, value);
+            }
+        }
+        apply(change, merged, state, toasts);
+    }
+}
+
+// Both sides stamped against the file as this window last saw it: this window's edits, and
+// edits to the file made by something that doesn't stamp them (a text editor, an older version)
+fn sides(change: &ExternalChange, state: ProjectsState) -> (Vec<Project>, Vec<Project>) {
+    let mut ours = state.projects.peek().clone();
+    crdt::stamp(&change.base, &mut ours);
+    let mut theirs = change.theirs.clone();
+    crdt::stamp(&change.base, &mut theirs);
+    (ours, theirs)
+}
+
+fn apply(change: ExternalChange, mut merged: Vec<Project>, mut state: ProjectsState, mut toasts: ToastState) {
+    change.accept();
+    ensure_inbox(&mut merged);
+    // Write back only what the file is missing: our unsaved edits or the chosen side of a conflict
+    if merged != change.theirs {
+        storage::save_merged(&merged);
+    } else {
+        storage::observe(&merged);
+    }
+    if merged == *state.projects.peek() {
+        return;
+    }
+    info!("[Sync] Reloaded projects changed outside this window");
+    state.projects.set(merged);
+    toasts.info("Loaded changes made outside this window.");
+}
+
+// Asks which side wins when this window and the data file changed the same fields
+#[component]
+pub fn ConflictDialog() -> Element {
+    let mut sync = use_context::<SyncState>();
+    let state = use_context::<ProjectsState>();
+    let toasts = use_context::<ToastState>();
+
+    let Some(conflicts) = sync.pending.read().as_ref().map(|p| p.conflicts.clone()) else { return rsx! {} };
+
+    rsx! {
+        div { class: "modal-overlay", tabindex: 0,
+            onkeydown: move |e: KeyboardEvent| if e.key() == Key::Escape { sync.resolve(Side::Ours, state, toasts) },
+            div { class: "modal",
+                h3 { class: "title", "The data file changed" }
+                p { class: "meta", "Another window or a sync tool changed items you also edited here. Other changes from both sides are kept either way." }
+                ul { class: "conflict-list",
+                    for (i, c) in conflicts.into_iter().enumerate() {
+                        li { key: "conflict-{i}", "{c}" }
+                    }
+                }
+                div { class: "actions",
+                    button { class: "btn btn-primary", autofocus: "true", onclick: move |_| sync.resolve(Side::Ours, state, toasts), "Keep mine" }
+                    button { class: "btn btn-ghost", onclick: move |_| sync.resolve(Side::Theirs, state, toasts), "Use theirs" }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+pub fn ReadOnlyBanner() -> Element {
+    let sync = use_context::<SyncState>();
+    if !*sync.read_only.read() {
+        return rsx! {};
+    }
+    rsx! {
+        div { class: "read-only-banner", role: "status",
+            "Another window is using this workspace, so changes made here are not saved. Close it, or open a different workspace."
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use dioxus::prelude::*;
+use dioxus::logger::tracing::warn;
+use std::path::PathBuf;
+use std::time::Duration;
+
+// How long non-error toasts stay on screen; errors stay until dismissed
+const AUTO_DISMISS: Duration = Duration::from_secs(6);
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ToastKind {
+    Info,
+    Success,
+    Error,
+}
+
+#[derive(Clone, PartialEq)]
+pub enum ToastAction {
+    OpenFile(PathBuf),
+    ShowInFolder(PathBuf),
+}
+
+impl ToastAction {
+    fn label(&self) -> &'static str {
+        match self {
+            ToastAction::OpenFile(_) => "Open file",
+            ToastAction::ShowInFolder(_) => "Show in folder",
+        }
+    }
+
+    fn run(&self) -> Result<(), String> {
+        match self {
+            ToastAction::OpenFile(path) => opener::open(path).map_err(|e| e.to_string()),
+            ToastAction::ShowInFolder(path) => opener::reveal(path).map_err(|e| e.to_string()),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub struct Toast {
+    pub id: u64,
+    pub kind: ToastKind,
+    pub message: String,
+    pub actions: Vec<ToastAction>,
+}
+
+/// Notification queue shared through context; any component can push toasts.
+#[derive(Clone, Copy)]
+pub struct ToastState {
+    pub toasts: Signal<Vec<Toast>>,
+    pub next_id: Signal<u64>,
+}
+
+impl ToastState {
+    pub fn push(&mut self, kind: ToastKind, message: impl Into<String>, actions: Vec<ToastAction>) -> u64 {
+        let id = *self.next_id.read();
+        self.next_id.set(id + 1);
+        self.toasts.write().push(Toast { id, kind, message: message.into(), actions });
+        if kind != ToastKind::Error {
+            // Owned by the root scope so the timer survives the pushing component unmounting
+            let mut this = *self;
+            spawn_forever(async move {
+                tokio::time::sleep(AUTO_DISMISS).await;
+                this.dismiss(id);
+            });
+        }
+        id
+    }
+
+    pub fn info(&mut self, message: impl Into<String>) -> u64 { self.push(ToastKind::Info, message, Vec::new()) }
+    pub fn success(&mut self, message: impl Into<String>, actions: Vec<ToastAction>) -> u64 { self.push(ToastKind::Success, message, actions) }
+    pub fn error(&mut self, message: impl Into<String>) -> u64 { self.push(ToastKind::Error, message, Vec::new()) }
+
+    pub fn dismiss(&mut self, id: u64) {
+        self.toasts.write().retain(|t| t.id != id);
+    }
+}
+
+// Renders the toast stack; mount once at the app root
+#[component]
+pub fn ToastHost() -> Element {
+    let mut state = use_context::<ToastState>();
+    let toasts = state.toasts.read().clone();
+
+    rsx! {
+        div { class: "toasts", role: "status", aria_live: "polite",
+            for t in toasts.into_iter() {
+                div { key: "toast-{t.id}",
+                    class: match t.kind { ToastKind::Info => "toast", ToastKind::Success => "toast success", ToastKind::Error => "toast error" },
+                    span { class: "toast-message", "{t.message}" }
+                    div { class: "actions",
+                        for action in t.actions.clone().into_iter() {
+                            button { class: "btn btn-link", onclick: move |_| {
+                                    if let Err(e) = action.run() {
+                                        warn!("[Toast] Action failed: {e}");
+                                        state.error(format!("Couldn't {}: {e}", action.label().to_lowercase()));
+                                    }
+                                },
+                                {action.label()}
+                            }
+                        }
+                        button { class: "btn btn-ghost toast-close", title: "Dismiss", onclick: move |_| state.dismiss(t.id), "✕" }
+                    }
+                }
+            }
+        }
+    }
+}
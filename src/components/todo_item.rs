@@ -1,12 +1,41 @@
 use dioxus::prelude::*;
 use dioxus_router::prelude::use_navigator;
 use crate::Route;
+use chrono::Local;
 
+use crate::id::Id;
 use crate::models::Todo;
+use crate::components::progress::ProgressBar;
+
+// Grip that starts a drag for the row identified by `id`; shared by tasks and subtasks
+#[component]
+pub fn DragHandle(id: Id, on_drag_start: EventHandler<Id>, on_drag_end: EventHandler<Id>) -> Element {
+    rsx! {
+        span { 
+            class: "drag-handle", 
+            title: "Drag to reorder", 
+            draggable: "true", 
+            ondragstart: move |_| on_drag_start.call(id),
+            ondragend: move |_| on_drag_end.call(id),
+            svg { 
+                view_box: "0 0 24 24", 
+                fill: "currentColor",
+                circle { cx: "7", cy: "7", r: "1.5" }
+                circle { cx: "7", cy: "12", r: "1.5" }
+                circle { cx: "7", cy: "17", r: "1.5" }
+                circle { cx: "12", cy: "7", r: "1.5" }
+                circle { cx: "12", cy: "12", r: "1.5" }
+                circle { cx: "12", cy: "17", r: "1.5" }
+            }
+        }
+    }
+}
 
 #[component]
 pub fn TodoItem(
     todo: Todo,
+    // Owning project, for the Details link
+    pid: Id,
     is_editing: bool,
     editing_text: String,
     on_toggle: EventHandler<MouseEvent>,
@@ -17,15 +46,24 @@ pub fn TodoItem(
     on_edit_input: EventHandler<FormEvent>,
     on_cancel: EventHandler<MouseEvent>,
     // Drag & drop reordering
-    on_drag_start: EventHandler<u64>,
-    on_drag_over: EventHandler<u64>,
-    on_drag_leave: EventHandler<u64>,
-    on_drag_end: EventHandler<u64>,
-    on_drop: EventHandler<u64>,
+    on_drag_start: EventHandler<Id>,
+    on_drag_over: EventHandler<Id>,
+    on_drag_leave: EventHandler<Id>,
+    on_drag_end: EventHandler<Id>,
+    on_drop: EventHandler<Id>,
     // Visual flags
     is_dragging: bool,
     is_drag_over: bool,
+    // Titles of unfinished tasks this one waits on
+    #[props(default)]
+    blockers: Vec<String>,
+    // Cross-project lists: no drag handle, and a label naming the project
+    #[props(default = true)]
+    reorderable: bool,
+    #[props(default)]
+    project: Option<String>,
 ) -> Element {
+    let blocked = !blockers.is_empty();
     let nav = use_navigator();
     rsx! {
         li {
@@ -36,28 +74,15 @@ pub fn TodoItem(
                 let mut cls = if is_editing { "list-item editing".to_string() } else { "list-item".to_string() };
                 if is_dragging { cls.push_str(" dragging"); }
                 if is_drag_over { cls.push_str(" drag-over"); }
+                if blocked { cls.push_str(" blocked"); }
                 cls
             },
             // complete toggle
             if !is_editing {
                 div { class: "row between",
                     div { class: "left",
-                        span { 
-                            class: "drag-handle", 
-                            title: "Drag to reorder", 
-                            draggable: "true", 
-                            ondragstart: move |_| on_drag_start.call(todo.id),
-                            ondragend: move |_| on_drag_end.call(todo.id),
-                            svg { 
-                                view_box: "0 0 24 24", 
-                                fill: "currentColor",
-                                circle { cx: "7", cy: "7", r: "1.5" }
-                                circle { cx: "7", cy: "12", r: "1.5" }
-                                circle { cx: "7", cy: "17", r: "1.5" }
-                                circle { cx: "12", cy: "7", r: "1.5" }
-                                circle { cx: "12", cy: "12", r: "1.5" }
-                                circle { cx: "12", cy: "17", r: "1.5" }
-                            }
+                        if reorderable {
+                            DragHandle { id: todo.id, on_drag_start, on_drag_end }
                         }
                         input {
                             r#type: "checkbox",
@@ -74,6 +99,26 @@ pub fn TodoItem(
             div { class: "content",
                 if !is_editing {
                     span { class: if todo.completed { "item-title completed" } else { "item-title" }, "{todo.title}" }
+                    if !todo.subtasks.is_empty() {
+                        {
+                            let (done, total) = todo.subtask_progress();
+                            rsx! {
+                                span { class: "sub-progress", title: "Subtasks completed",
+                                    ProgressBar { done, total, class: "mini" }
+                                    span { class: "meta", "{done}/{total}" }
+                                }
+                            }
+                        }
+                    }
+                    if let Some(name) = project.as_ref() {
+                        span { class: "project-tag", "{name}" }
+                    }
+                    if blocked {
+                        span { class: "blocked-badge", title: "Blocked by: {blockers.join(\", \")}", "Blocked" }
+                    }
+                    if let Some(due) = todo.due {
+                        span { class: if !todo.completed && due < Local::now().date_naive() { "due overdue" } else { "due" }, {format!("Due {}", due.format("%b %-d"))} }
+                    }
                 } else {
                     input {
                         class: "text edit",
@@ -89,7 +134,7 @@ pub fn TodoItem(
             // actions
             div { class: "actions",
                 if !is_editing {
-                    button { class: "btn btn-primary", onclick: move |_| { nav.push(Route::Details { id: todo.id }); }, "Details" }
+                    button { class: "btn btn-primary", onclick: move |_| { nav.push(Route::Details { pid, id: todo.id }); }, "Details" }
                     button { class: "btn btn-ghost", onclick: move |e| on_start_edit.call(e), "Edit" }
                     button { class: "btn btn-danger", onclick: move |e| on_remove.call(e), "Remove" }
                 } else {
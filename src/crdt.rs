@@ -0,0 +1,960 @@
+//! Merging copies of a workspace edited on different devices, without conflicts.
+//!
+//! Every field of a project, todo or subtask is a last-writer-wins register: `stamps` records
+//! when, and on which device, each field last changed, and a merge keeps the newer value field
+//! by field. Where an item sits is a register too: its `rank` (a fractional index, so moving
+//! one item never renumbers the others), a todo's project and a subtask's parent. Removed
+//! projects, todos and subtasks leave tombstones so a merge doesn't bring them back, unless the
+//! other side edited them after the removal.
+//!
+//! Edits don't stamp anything themselves. `stamp` compares the state being saved with the one
+//! saved before and stamps what changed, so the rest of the app keeps working with plain lists.
+use crate::id::Id;
+use crate::models::{Project, Subtask, Todo};
+use chrono::Utc;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Mutex;
+
+// Registers kept for where an item sits rather than for one of its fields
+const RANK: &str = "rank";
+const PROJECT: &str = "project";
+const PARENT: &str = "parent";
+
+// Rank digits, in ASCII order
+const DIGITS: &[u8; 62] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// When a field last changed: milliseconds on a hybrid clock, and the device that changed it.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Stamp(pub u64, pub String);
+
+/// Field name -> when it last changed.
+pub type Stamps = BTreeMap<String, Stamp>;
+/// Id of a removed item -> when it was removed.
+pub type Tombstones = BTreeMap<Id, Stamp>;
+
+static CLOCK: Mutex<u64> = Mutex::new(0);
+static DEVICE: Mutex<String> = Mutex::new(String::new());
+
+/// Name this device in new stamps.
+pub fn set_device(device: &str) {
+    *DEVICE.lock().unwrap_or_else(|e| e.into_inner()) = device.to_string();
+}
+
+// Never behind the wall clock or any stamp seen so far, so an edit made after a merge wins
+// over everything that was merged
+fn tick() -> Stamp {
+    let now = Utc::now().timestamp_millis().max(0) as u64;
+    let mut clock = CLOCK.lock().unwrap_or_else(|e| e.into_inner());
+    *clock = (*clock + 1).max(now);
+    let device = DEVICE.lock().unwrap_or_else(|e| e.into_inner());
+    Stamp(*clock, if device.is_empty() { "local".into() } else { device.clone() })
+}
+
+fn observe(projects: &[Project]) {
+    let mut seen = 0;
+    for p in projects {
+        seen = p.stamps.values().chain(p.removed.values()).fold(seen, |m, s| m.max(s.0));
+        for t in &p.todos {
+            seen = t.stamps.values().chain(t.removed.values()).fold(seen, |m, s| m.max(s.0));
+            Subtask::walk(&t.subtasks, 0, &mut |s, _| seen = s.stamps.values().fold(seen, |m, st| m.max(st.0)));
+        }
+    }
+    let mut clock = CLOCK.lock().unwrap_or_else(|e| e.into_inner());
+    *clock = (*clock).max(seen);
+}
+
+// An item whose fields are registers
+trait Registers {
+    // Plain fields; `RANK` is handled alongside them but stamped by `order`
+    const FIELDS: &'static [&'static str];
+    fn value(&self, field: &str) -> Value;
+    fn take(&mut self, other: &Self, field: &str);
+    // Set a field from a JSON `value`, as `value` shows it; false if it doesn't fit
+    fn put(&mut self, field: &str, value: &Value) -> bool;
+    fn stamps(&self) -> &Stamps;
+    fn stamps_mut(&mut self) -> &mut Stamps;
+    fn id(&self) -> Id;
+    fn rank(&self) -> &str;
+    fn rank_mut(&mut self) -> &mut String;
+}
+
+impl Registers for Project {
+    const FIELDS: &'static [&'static str] = &["name", "columns", "inbox"];
+
+    fn value(&self, field: &str) -> Value {
+        match field {
+            "name" => json!(self.name),
+            "columns" => json!(self.columns),
+            "inbox" => json!(self.inbox),
+            RANK => json!(self.rank),
+            _ => Value::Null,
+        }
+    }
+
+    fn take(&mut self, other: &Self, field: &str) {
+        match field {
+            "name" => self.name = other.name.clone(),
+            "columns" => self.columns = other.columns.clone(),
+            "inbox" => self.inbox = other.inbox,
+            RANK => self.rank = other.rank.clone(),
+            _ => {}
+        }
+    }
+
+    fn put(&mut self, field: &str, value: &Value) -> bool {
+        match field {
+            "name" => set(&mut self.name, value),
+            "columns" => set(&mut self.columns, value),
+            "inbox" => set(&mut self.inbox, value),
+            _ => false,
+        }
+    }
+
+    fn stamps(&self) -> &Stamps { &self.stamps }
+    fn stamps_mut(&mut self) -> &mut Stamps { &mut self.stamps }
+    fn id(&self) -> Id { self.id }
+    fn rank(&self) -> &str { &self.rank }
+    fn rank_mut(&mut self) -> &mut String { &mut self.rank }
+}
+
+impl Registers for Todo {
+    const FIELDS: &'static [&'static str] = &["title", "description", "completed", "due", "blocked_by", "status"];
+
+    fn value(&self, field: &str) -> Value {
+        match field {
+            "title" => json!(self.title),
+            "description" => json!(self.description),
+            "completed" => json!([self.completed, self.completed_at]),
+            "due" => json!(self.due),
+            "blocked_by" => json!(self.blocked_by),
+            "status" => json!(self.status),
+            RANK => json!(self.rank),
+            _ => Value::Null,
+        }
+    }
+
+    fn take(&mut self, other: &Self, field: &str) {
+        match field {
+            "title" => self.title = other.title.clone(),
+            "description" => self.description = other.description.clone(),
+            "completed" => {
+                self.completed = other.completed;
+                self.completed_at = other.completed_at;
+            }
+            "due" => self.due = other.due,
+            "blocked_by" => self.blocked_by = other.blocked_by.clone(),
+            "status" => self.status = other.status.clone(),
+            RANK => self.rank = other.rank.clone(),
+            _ => {}
+        }
+    }
+
+    fn put(&mut self, field: &str, value: &Value) -> bool {
+        match field {
+            "title" => set(&mut self.title, value),
+            "description" => set(&mut self.description, value),
+            "completed" => value.as_bool().map(|done| self.set_completed_deep(done)).is_some(),
+            "due" => set(&mut self.due, value),
+            "blocked_by" => set(&mut self.blocked_by, value),
+            "status" => set(&mut self.status, value),
+            _ => false,
+        }
+    }
+
+    fn stamps(&self) -> &Stamps { &self.stamps }
+    fn stamps_mut(&mut self) -> &mut Stamps { &mut self.stamps }
+    fn id(&self) -> Id { self.id }
+    fn rank(&self) -> &str { &self.rank }
+    fn rank_mut(&mut self) -> &mut String { &mut self.rank }
+}
+
+impl Registers for Subtask {
+    const FIELDS: &'static [&'static str] = &["title", "description", "completed"];
+
+    fn value(&self, field: &str) -> Value {
+        match field {
+            "title" => json!(self.title),
+            "description" => json!(self.description),
+            "completed" => json!([self.completed, self.completed_at]),
+            RANK => json!(self.rank),
+            _ => Value::Null,
+        }
+    }
+
+    fn take(&mut self, other: &Self, field: &str) {
+        match field {
+            "title" => self.title = other.title.clone(),
+            "description" => self.description = other.description.clone(),
+            "completed" => {
+                self.completed = other.completed;
+                self.completed_at = other.completed_at;
+            }
+            RANK => self.rank = other.rank.clone(),
+            _ => {}
+        }
+    }
+
+    fn put(&mut self, field: &str, value: &Value) -> bool {
+        match field {
+            "title" => set(&mut self.title, value),
+            "description" => set(&mut self.description, value),
+            "completed" => value.as_bool().map(|done| self.set_completed(done)).is_some(),
+            _ => false,
+        }
+    }
+
+    fn stamps(&self) -> &Stamps { &self.stamps }
+    fn stamps_mut(&mut self) -> &mut Stamps { &mut self.stamps }
+    fn id(&self) -> Id { self.id }
+    fn rank(&self) -> &str { &self.rank }
+    fn rank_mut(&mut self) -> &mut String { &mut self.rank }
+}
+
+fn set<V: DeserializeOwned>(slot: &mut V, value: &Value) -> bool {
+    serde_json::from_value(value.clone()).map(|v| *slot = v).is_ok()
+}
+
+// ---- Ranks ----
+
+fn digit(c: u8) -> usize {
+    DIGITS.iter().position(|d| *d == c).unwrap_or(0)
+}
+
+// Only ranks this module could have made are kept; anything else is ranked again
+fn valid_rank(rank: &str) -> bool {
+    !rank.is_empty() && !rank.ends_with('0') && rank.bytes().all(|c| DIGITS.contains(&c))
+}
+
+/// A rank that sorts strictly between `lo` and `hi`, where an empty `lo` is the start of the
+/// list and an empty `hi` its end.
+fn rank_between(lo: &str, hi: &str) -> String {
+    let mut out = Vec::new();
+    midpoint(lo.as_bytes(), (!hi.is_empty()).then_some(hi.as_bytes()), &mut out);
+    String::from_utf8(out).unwrap_or_default()
+}
+
+fn midpoint(lo: &[u8], hi: Option<&[u8]>, out: &mut Vec<u8>) {
+    if let Some(hi) = hi {
+        // Copy the common prefix, reading `lo` as padded with zeros
+        let n = hi.iter().enumerate().take_while(|(i, c)| lo.get(*i).copied().unwrap_or(b'0') == **c).count();
+        if n == hi.len() {
+            return midpoint(lo.get(n..).unwrap_or_default(), None, out);
+        }
+        if n > 0 {
+            out.extend_from_slice(&hi[..n]);
+            return midpoint(lo.get(n..).unwrap_or_default(), Some(&hi[n..]), out);
+        }
+    }
+    let a = lo.first().map_or(0, |c| digit(*c));
+    let b = hi.map_or(DIGITS.len(), |hi| digit(hi[0]));
+    if b > a + 1 {
+        out.push(DIGITS[(a + b) / 2]);
+    } else if let Some(hi) = hi && hi.len() > 1 {
+        out.push(hi[0]);
+    } else {
+        out.push(DIGITS[a]);
+        midpoint(lo.get(1..).unwrap_or_default(), None, out);
+    }
+}
+
+// `n` ranks between `lo` and `hi`, spread out so they stay short
+fn spread(lo: &str, hi: &str, n: usize, out: &mut Vec<String>) {
+    if n == 0 {
+        return;
+    }
+    let mid = rank_between(lo, hi);
+    let left = (n - 1) / 2;
+    spread(lo, &mid, left, out);
+    out.push(mid.clone());
+    spread(&mid, hi, n - 1 - left, out);
+}
+
+// Which ranks can stay: the longest run of them (not necessarily adjacent) already in order
+fn in_order(ranks: &[&str]) -> Vec<bool> {
+    let mut tails: Vec<usize> = Vec::new();
+    let mut prev = vec![None; ranks.len()];
+    for (i, rank) in ranks.iter().enumerate() {
+        if !valid_rank(rank) {
+            continue;
+        }
+        let len = tails.partition_point(|&j| ranks[j] < *rank);
+        if len > 0 {
+            prev[i] = tails.get(len - 1).copied();
+        }
+        if len == tails.len() { tails.push(i) } else { tails[len] = i }
+    }
+    let mut keep = vec![false; ranks.len()];
+    let mut at = tails.last().copied();
+    while let Some(i) = at {
+        keep[i] = true;
+        at = prev[i];
+    }
+    keep
+}
+
+fn sort_by_rank<T: Registers>(list: &mut [T]) {
+    list.sort_by(|a, b| (a.rank(), a.id()).cmp(&(b.rank(), b.id())));
+}
+
+// Give `list` ranks in its current order. `saved[i]` is item i's rank and rank stamp as last
+// saved, if it was in this same list; items whose rank still fits keep it, the rest (moved or new)
+// get a new one
+fn order<T: Registers>(list: &mut [T], saved: &[Option<(&str, Option<&Stamp>)>]) {
+    let candidates: Vec<(String, Option<Stamp>)> = list.iter().zip(saved).map(|(item, saved)| {
+        let own = item.stamps().get(RANK);
+        match saved {
+            // A newer stamp of its own came with a merge
+            Some((rank, stamp)) if own <= *stamp => (rank.to_string(), stamp.cloned()),
+            _ => (item.rank().to_string(), own.cloned()),
+        }
+    }).collect();
+    let keep = in_order(&candidates.iter().map(|(r, _)| r.as_str()).collect::<Vec<_>>());
+    let mut i = 0;
+    while i < list.len() {
+        if keep[i] {
+            let (rank, stamp) = candidates[i].clone();
+            *list[i].rank_mut() = rank;
+            set_stamp(list[i].stamps_mut(), RANK, stamp);
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < list.len() && !keep[i] {
+            i += 1;
+        }
+        let lo = if start > 0 { list[start - 1].rank().to_string() } else { String::new() };
+        let hi = candidates.get(i).map(|(r, _)| r.clone()).unwrap_or_default();
+        let mut ranks = Vec::new();
+        spread(&lo, &hi, i - start, &mut ranks);
+        for (item, rank) in list[start..i].iter_mut().zip(ranks) {
+            *item.rank_mut() = rank;
+            item.stamps_mut().insert(RANK.into(), tick());
+        }
+    }
+}
+
+// ---- Stamping saves ----
+
+fn set_stamp(stamps: &mut Stamps, field: &str, stamp: Option<Stamp>) {
+    match stamp {
+        Some(s) => { stamps.insert(field.into(), s); }
+        None => { stamps.remove(field); }
+    }
+}
+
+// A stamp newer than the saved one came in with a merge and stays; otherwise a changed value
+// gets a new stamp and an unchanged one keeps the saved stamp
+fn settle(stamps: &mut Stamps, field: &str, saved: Option<&Stamp>, changed: bool) {
+    if stamps.get(field) > saved {
+        return;
+    }
+    let stamp = if changed { Some(tick()) } else { saved.cloned() };
+    set_stamp(stamps, field, stamp);
+}
+
+fn stamp_fields<T: Registers>(prev: Option<&T>, item: &mut T) {
+    for field in T::FIELDS {
+        let changed = prev.is_none_or(|p| p.value(field) != item.value(field));
+        settle(item.stamps_mut(), field, prev.and_then(|p| p.stamps().get(*field)), changed);
+    }
+}
+
+fn newest(stamps: &Stamps) -> Option<&Stamp> {
+    stamps.values().max()
+}
+
+fn keep_newer(tombstones: &mut Tombstones, id: Id, stamp: &Stamp) {
+    let slot = tombstones.entry(id).or_insert_with(|| stamp.clone());
+    if *slot < *stamp {
+        *slot = stamp.clone();
+    }
+}
+
+// An item is back (say, a removal was undone) when it's present and its stamps are no newer
+// than its tombstone; it's then stamped as new so merges keep it
+fn revived(stamps: &Stamps, tombstone: Option<&Stamp>) -> bool {
+    tombstone.is_some_and(|t| newest(stamps) <= Some(t))
+}
+
+/// Stamp what changed from `base` (the state last saved) to `new`, rank todos and subtasks in
+/// their current order and leave tombstones for removed ones. Loading passes an empty `base`
+/// to give older data its first stamps and ranks.
+pub fn stamp(base: &[Project], new: &mut [Project]) {
+    observe(base);
+    observe(new);
+    let saved: HashMap<Id, &Project> = base.iter().map(|p| (p.id, p)).collect();
+    let saved_todos: HashMap<Id, (Id, &Todo)> = base.iter().flat_map(|p| p.todos.iter().map(move |t| (t.id, (p.id, t)))).collect();
+    let present: HashSet<Id> = new.iter().flat_map(|p| p.todos.iter().map(|t| t.id).chain([p.id])).collect();
+    let mut tombstones = Tombstones::new();
+    for p in base.iter().chain(new.iter()) {
+        for (id, s) in &p.removed {
+            keep_newer(&mut tombstones, *id, s);
+        }
+    }
+    for p in new.iter_mut() {
+        if revived(&p.stamps, tombstones.get(&p.id)) {
+            p.stamps.clear();
+        }
+    }
+
+    let ranks: Vec<_> = new.iter().map(|p| saved.get(&p.id).map(|o| (o.rank.as_str(), o.stamps.get(RANK)))).collect();
+    order(new, &ranks);
+    for p in new.iter_mut() {
+        let prev = saved.get(&p.id).copied();
+        stamp_fields(prev, p);
+        if let Some(prev) = prev {
+            for (id, s) in &prev.removed {
+                keep_newer(&mut p.removed, *id, s);
+            }
+            for t in prev.todos.iter().filter(|t| !present.contains(&t.id)) {
+                p.removed.entry(t.id).or_insert_with(tick);
+            }
+        }
+        p.removed.retain(|id, _| !present.contains(id));
+
+        let pid = p.id;
+        let ranks: Vec<_> = p.todos.iter().map(|t| {
+            saved_todos.get(&t.id).filter(|(from, _)| *from == pid).map(|(_, o)| (o.rank.as_str(), o.stamps.get(RANK)))
+        }).collect();
+        order(&mut p.todos, &ranks);
+        for t in p.todos.iter_mut() {
+            if revived(&t.stamps, tombstones.get(&t.id)) {
+                t.stamps.clear();
+            }
+            let prev = saved_todos.get(&t.id);
+            stamp_fields(prev.map(|(_, o)| *o), t);
+            settle(&mut t.stamps, PROJECT, prev.and_then(|(_, o)| o.stamps.get(PROJECT)), prev.is_none_or(|(from, _)| *from != pid));
+            stamp_subtasks(prev.map(|(_, o)| *o), t);
+        }
+    }
+
+    // A removed project's tombstone, and those of its todos, are kept by the Inbox (or the
+    // first project)
+    let Some(holder) = new.iter().position(|p| p.inbox).or((!new.is_empty()).then_some(0)) else { return };
+    for gone in base.iter().filter(|p| !present.contains(&p.id)) {
+        let removed = &mut new[holder].removed;
+        for (id, s) in gone.removed.iter().filter(|(id, _)| !present.contains(id)) {
+            keep_newer(removed, *id, s);
+        }
+        removed.entry(gone.id).or_insert_with(tick);
+        for t in gone.todos.iter().filter(|t| !present.contains(&t.id)) {
+            removed.entry(t.id).or_insert_with(tick);
+        }
+    }
+}
+
+type Flat<'a> = HashMap<Id, (Option<Id>, &'a Subtask)>;
+
+fn flatten<'a>(list: &'a [Subtask], parent: Option<Id>, out: &mut Flat<'a>) {
+    for s in list {
+        out.insert(s.id, (parent, s));
+        flatten(&s.children, Some(s.id), out);
+    }
+}
+
+fn stamp_subtasks(prev: Option<&Todo>, todo: &mut Todo) {
+    let mut saved = Flat::new();
+    let mut present = HashSet::new();
+    if let Some(prev) = prev {
+        flatten(&prev.subtasks, None, &mut saved);
+    }
+    Subtask::walk(&todo.subtasks, 0, &mut |s, _| { present.insert(s.id); });
+    let mut tombstones = todo.removed.clone();
+    if let Some(prev) = prev {
+        for (id, s) in &prev.removed {
+            keep_newer(&mut tombstones, *id, s);
+            keep_newer(&mut todo.removed, *id, s);
+        }
+        for id in saved.keys().filter(|id| !present.contains(id)) {
+            todo.removed.entry(*id).or_insert_with(tick);
+        }
+    }
+    todo.removed.retain(|id, _| !present.contains(id));
+    stamp_list(&mut todo.subtasks, None, &saved, &tombstones);
+}
+
+fn stamp_list(list: &mut [Subtask], parent: Option<Id>, saved: &Flat, tombstones: &Tombstones) {
+    let ranks: Vec<_> = list.iter().map(|s| {
+        saved.get(&s.id).filter(|(from, _)| *from == parent).map(|(_, o)| (o.rank.as_str(), o.stamps.get(RANK)))
+    }).collect();
+    order(list, &ranks);
+    for s in list.iter_mut() {
+        if revived(&s.stamps, tombstones.get(&s.id)) {
+            s.stamps.clear();
+        }
+        let prev = saved.get(&s.id);
+        stamp_fields(prev.map(|(_, o)| *o), s);
+        settle(&mut s.stamps, PARENT, prev.and_then(|(_, o)| o.stamps.get(PARENT)), prev.is_none_or(|(from, _)| *from != parent));
+        stamp_list(&mut s.children, Some(s.id), saved, tombstones);
+    }
+}
+
+// ---- Merging ----
+
+fn merge_registers<T: Registers>(into: &mut T, other: &T) {
+    for field in T::FIELDS.iter().copied().chain([RANK]) {
+        let theirs = other.stamps().get(field).cloned();
+        let take = match theirs.as_ref().cmp(&into.stamps().get(field)) {
+            Ordering::Greater => true,
+            Ordering::Less => false,
+            // Same stamp (or neither has one): settle on the larger value so both sides agree
+            Ordering::Equal => {
+                let (theirs, mine) = (other.value(field).to_string(), into.value(field).to_string());
+                theirs > mine
+            }
+        };
+        if take {
+            into.take(other, field);
+            if let Some(s) = theirs {
+                into.stamps_mut().insert(field.into(), s);
+            }
+        }
+    }
+}
+
+// Resolve a register kept outside the item (a todo's project, a subtask's parent)
+fn resolve<V: Ord>(field: &str, (x, vx): (&Stamps, V), (y, vy): (&Stamps, V), into: &mut Stamps) -> V {
+    let (sx, sy) = (x.get(field), y.get(field));
+    if let Some(s) = sx.max(sy) {
+        into.insert(field.into(), s.clone());
+    }
+    match sx.cmp(&sy) {
+        Ordering::Greater => vx,
+        Ordering::Less => vy,
+        Ordering::Equal => vx.max(vy),
+    }
+}
+
+/// Merge two copies of a workspace. The result is the same whichever copy comes first, and
+/// merging a copy with itself changes nothing, so devices that exchange their states in any
+/// order end up with the same projects.
+pub fn merge(a: &[Project], b: &[Project]) -> Vec<Project> {
+    observe(a);
+    observe(b);
+    let mut projects: Vec<Project> = Vec::new();
+    for p in a.iter().chain(b) {
+        match projects.iter_mut().find(|q| q.id == p.id) {
+            Some(q) => {
+                merge_registers(q, p);
+                for (id, s) in &p.removed {
+                    keep_newer(&mut q.removed, *id, s);
+                }
+            }
+            None => projects.push(Project { todos: Vec::new(), ..p.clone() }),
+        }
+    }
+
+    let mut tombstones = Tombstones::new();
+    for p in &projects {
+        for (id, s) in &p.removed {
+            keep_newer(&mut tombstones, *id, s);
+        }
+    }
+    // A removed project is gone unless it changed afterwards; the tombstones it held move to
+    // one that stays, and its todos that stay move to the Inbox
+    let (gone, stay): (Vec<Project>, Vec<Project>) = projects.into_iter().partition(|p| {
+        tombstones.get(&p.id).is_some_and(|s| newest(&p.stamps) <= Some(s))
+    });
+    projects = stay;
+    sort_by_rank(&mut projects);
+    if let Some(holder) = projects.iter().position(|p| p.inbox).or((!projects.is_empty()).then_some(0)) {
+        for (id, s) in gone.iter().flat_map(|p| &p.removed) {
+            keep_newer(&mut projects[holder].removed, *id, s);
+        }
+    }
+
+    let todos = |list: &[Project]| -> HashMap<Id, (Id, Todo)> {
+        list.iter().flat_map(|p| p.todos.iter().map(move |t| (t.id, (p.id, t.clone())))).collect()
+    };
+    let (ta, tb) = (todos(a), todos(b));
+    let mut ids: Vec<Id> = ta.keys().chain(tb.keys()).copied().collect();
+    ids.sort();
+    ids.dedup();
+    let mut kept: HashSet<Id> = projects.iter().map(|p| p.id).collect();
+    for id in ids {
+        let (pid, todo) = match (ta.get(&id), tb.get(&id)) {
+            (Some((pa, x)), Some((pb, y))) => {
+                let mut t = x.clone();
+                merge_registers(&mut t, y);
+                let pid = resolve(PROJECT, (&x.stamps, *pa), (&y.stamps, *pb), &mut t.stamps);
+                t.subtasks = merge_subtasks(x, y, &mut t.removed);
+                (pid, t)
+            }
+            (Some((pid, t)), None) | (None, Some((pid, t))) => (*pid, t.clone()),
+            (None, None) => continue,
+        };
+        // Removed on one side: gone, unless the other side changed it afterwards
+        if tombstones.get(&id).is_some_and(|s| newest(&todo.stamps) <= Some(s)) {
+            continue;
+        }
+        kept.insert(id);
+        let home = projects.iter().position(|p| p.id == pid).or_else(|| projects.iter().position(|p| p.inbox)).unwrap_or(0);
+        if let Some(p) = projects.get_mut(home) {
+            p.todos.push(todo);
+        }
+    }
+    for p in projects.iter_mut() {
+        p.removed.retain(|id, _| !kept.contains(id));
+        sort_by_rank(&mut p.todos);
+        for t in p.todos.iter_mut() {
+            t.sync_completion();
+        }
+    }
+    sort_by_rank(&mut projects);
+    projects
+}
+
+type Nodes = BTreeMap<Id, (Option<Id>, Subtask)>;
+
+fn merge_subtasks(x: &Todo, y: &Todo, removed: &mut Tombstones) -> Vec<Subtask> {
+    for (id, s) in &y.removed {
+        keep_newer(removed, *id, s);
+    }
+    let (mut fx, mut fy) = (Flat::new(), Flat::new());
+    flatten(&x.subtasks, None, &mut fx);
+    flatten(&y.subtasks, None, &mut fy);
+    let mut ids: Vec<Id> = fx.keys().chain(fy.keys()).copied().collect();
+    ids.sort();
+    ids.dedup();
+
+    let mut nodes = Nodes::new();
+    for id in ids {
+        let (parent, node) = match (fx.get(&id), fy.get(&id)) {
+            (Some((px, sx)), Some((py, sy))) => {
+                let mut s = Subtask { children: Vec::new(), ..(*sx).clone() };
+                merge_registers(&mut s, *sy);
+                let parent = resolve(PARENT, (&sx.stamps, *px), (&sy.stamps, *py), &mut s.stamps);
+                (parent, s)
+            }
+            (Some((parent, s)), None) | (None, Some((parent, s))) => (*parent, Subtask { children: Vec::new(), ..(*s).clone() }),
+            (None, None) => continue,
+        };
+        if removed.get(&id).is_some_and(|s| newest(&node.stamps) <= Some(s)) {
+            continue;
+        }
+        nodes.insert(id, (parent, node));
+    }
+    removed.retain(|id, _| !nodes.contains_key(id));
+
+    // A subtask whose parent is gone moves to the top level, and so does one that two moves
+    // put inside its own subtree
+    let orphans: Vec<Id> = nodes.iter().filter(|(_, (p, _))| p.is_some_and(|p| !nodes.contains_key(&p))).map(|(id, _)| *id).collect();
+    for id in orphans {
+        if let Some(node) = nodes.get_mut(&id) { node.0 = None; }
+    }
+    let ids: Vec<Id> = nodes.keys().copied().collect();
+    for id in ids {
+        let mut seen = HashSet::new();
+        let mut at = nodes.get(&id).and_then(|n| n.0);
+        while let Some(p) = at {
+            if p == id {
+                if let Some(node) = nodes.get_mut(&id) { node.0 = None; }
+                break;
+            }
+            if !seen.insert(p) {
+                break;
+            }
+            at = nodes.get(&p).and_then(|n| n.0);
+        }
+    }
+    build(None, &mut nodes)
+}
+
+fn build(parent: Option<Id>, nodes: &mut Nodes) -> Vec<Subtask> {
+    let ids: Vec<Id> = nodes.iter().filter(|(_, (p, _))| *p == parent).map(|(id, _)| *id).collect();
+    let mut list: Vec<Subtask> = ids.into_iter().filter_map(|id| nodes.remove(&id)).map(|(_, s)| s).collect();
+    for s in list.iter_mut() {
+        s.children = build(Some(s.id), nodes);
+    }
+    sort_by_rank(&mut list);
+    list
+}
+
+// ---- Clashes ----
+
+/// Field name of a `Clash` over a task that one copy removed while the other changed it.
+pub const REMOVED: &str = "*";
+
+/// A project or task, as named in a `Clash`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "id", rename_all = "snake_case")]
+pub enum Target {
+    Project(Id),
+    Todo(Id),
+}
+
+impl Target {
+    /// The same target with a numeric id from an older version upgraded.
+    pub fn upgrade(self) -> Self {
+        match self {
+            Target::Project(id) => Target::Project(id.upgrade_project()),
+            Target::Todo(id) => Target::Todo(id.upgrade_todo()),
+        }
+    }
+}
+
+/// A field both copies changed from their common base, to different values. `merge` has already
+/// kept one of them (the later edit); this is only a report, so the other can still be chosen.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Clash {
+    pub target: Target,
+    /// The project or task as people know it, e.g. `Task "Write report"`.
+    pub label: String,
+    /// A field name, or `REMOVED`.
+    pub field: String,
+    /// Each copy's value as `put` takes it; a whole task (with its `project`) or null for `REMOVED`.
+    pub ours: Value,
+    pub theirs: Value,
+    pub theirs_kept: bool,
+}
+
+impl Clash {
+    pub fn kept(&self) -> &Value {
+        if self.theirs_kept { &self.theirs } else { &self.ours }
+    }
+
+    pub fn other(&self) -> &Value {
+        if self.theirs_kept { &self.ours } else { &self.theirs }
+    }
+}
+
+// A field's value as people see it: whether a task is done, not when
+fn shown<T: Registers>(item: &T, field: &str) -> Value {
+    match item.value(field) {
+        Value::Array(mut v) if field == "completed" && !v.is_empty() => v.swap_remove(0),
+        v => v,
+    }
+}
+
+fn clash_fields<T: Registers>(target: Target, label: &str, [base, ours, theirs]: [&T; 3], merged: Option<&T>, out: &mut Vec<Clash>) {
+    for field in T::FIELDS {
+        let (b, o, t) = (shown(base, field), shown(ours, field), shown(theirs, field));
+        if o == b || t == b || o == t {
+            continue;
+        }
+        let theirs_kept = merged.is_some_and(|m| shown(m, field) == t);
+        out.push(Clash { target, label: label.to_string(), field: field.to_string(), ours: o, theirs: t, theirs_kept });
+    }
+}
+
+fn changed<T: Registers>(base: &T, item: &T) -> bool {
+    T::FIELDS.iter().any(|f| shown(base, f) != shown(item, f))
+}
+
+// A removed task as a `REMOVED` clash carries it: the whole task and the project it was in
+fn whole(pid: Id, todo: &Todo) -> Value {
+    let mut value = json!(todo);
+    value[PROJECT] = json!(pid);
+    value
+}
+
+/// What `merge(ours, theirs)` (given as `merged`) settled that people may want to see: fields
+/// both copies changed from `base` to different values, and tasks one removed while the other
+/// changed them.
+pub fn clashes(base: &[Project], ours: &[Project], theirs: &[Project], merged: &[Project]) -> Vec<Clash> {
+    let project = |list: &[Project], id: Id| list.iter().find(|p| p.id == id).cloned();
+    let mut out = Vec::new();
+    for b in base {
+        if let (Some(o), Some(t)) = (project(ours, b.id), project(theirs, b.id)) {
+            let label = format!("Project \"{}\"", o.name);
+            clash_fields(Target::Project(b.id), &label, [b, &o, &t], project(merged, b.id).as_ref(), &mut out);
+        }
+    }
+
+    let todos = |list: &[Project]| -> BTreeMap<Id, (Id, Todo)> {
+        list.iter().flat_map(|p| p.todos.iter().map(move |t| (t.id, (p.id, t.clone())))).collect()
+    };
+    let (tm, to, tt) = (todos(merged), todos(ours), todos(theirs));
+    for (id, (_, b)) in todos(base) {
+        let target = Target::Todo(id);
+        let label = format!("Task \"{}\"", b.title);
+        let kept = tm.get(&id);
+        match (to.get(&id), tt.get(&id)) {
+            (Some((_, o)), Some((_, t))) => clash_fields(target, &label, [&b, o, t], kept.map(|(_, m)| m), &mut out),
+            (Some((pid, o)), None) if changed(&b, o) => {
+                out.push(Clash { target, label, field: REMOVED.into(), ours: whole(*pid, o), theirs: Value::Null, theirs_kept: kept.is_none() });
+            }
+            (None, Some((pid, t))) if changed(&b, t) => {
+                out.push(Clash { target, label, field: REMOVED.into(), ours: Value::Null, theirs: whole(*pid, t), theirs_kept: kept.is_some() });
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+/// Set `field` of `target` to `value`, as a `Clash` gives it, stamped as a new edit so it wins
+/// the next merge. For `REMOVED`, a task puts the task back and null removes it. Returns whether
+/// anything changed.
+pub fn put(projects: &mut [Project], target: Target, field: &str, value: &Value) -> bool {
+    match target {
+        Target::Project(id) => projects.iter_mut().find(|p| p.id == id).is_some_and(|p| put_field(p, field, value)),
+        Target::Todo(id) if field == REMOVED => {
+            let present = projects.iter().any(|p| p.todos.iter().any(|t| t.id == id));
+            match serde_json::from_value::<Todo>(value.clone()) {
+                Ok(todo) if !present => {
+                    let pid = value.get(PROJECT).and_then(|v| serde_json::from_value::<Id>(v.clone()).ok());
+                    let home = projects.iter().position(|p| Some(p.id) == pid).or_else(|| projects.iter().position(|p| p.inbox)).unwrap_or(0);
+                    let Some(p) = projects.get_mut(home) else { return false };
+                    p.removed.remove(&id);
+                    p.todos.push(todo);
+                    true
+                }
+                Err(_) if value.is_null() => {
+                    let mut found = false;
+                    for p in projects.iter_mut() {
+                        let before = p.todos.len();
+                        p.todos.retain(|t| t.id != id);
+                        found |= p.todos.len() != before;
+                    }
+                    found
+                }
+                _ => false,
+            }
+        }
+        Target::Todo(id) => projects.iter_mut().flat_map(|p| p.todos.iter_mut()).find(|t| t.id == id).is_some_and(|t| put_field(t, field, value)),
+    }
+}
+
+fn put_field<T: Registers>(item: &mut T, field: &str, value: &Value) -> bool {
+    if !item.put(field, value) {
+        return false;
+    }
+    item.stamps_mut().insert(field.into(), tick());
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo(title: &str) -> Todo {
+        Todo::new(Id::new(), title.into())
+    }
+
+    // A saved workspace with one project holding `titles`
+    fn saved(titles: &[&str]) -> Vec<Project> {
+        let mut p = Project::new(Id::new(), "Work".into());
+        p.todos = titles.iter().map(|t| todo(t)).collect();
+        let mut projects = vec![p];
+        stamp(&[], &mut projects);
+        projects
+    }
+
+    // A copy of `base` edited by `edit`, then saved
+    fn edited(base: &[Project], edit: impl FnOnce(&mut Vec<Todo>)) -> Vec<Project> {
+        let mut copy = base.to_vec();
+        edit(&mut copy[0].todos);
+        stamp(base, &mut copy);
+        copy
+    }
+
+    fn titles(projects: &[Project]) -> Vec<&str> {
+        projects[0].todos.iter().map(|t| t.title.as_str()).collect()
+    }
+
+    #[test]
+    fn merge_is_commutative_and_idempotent() {
+        let base = saved(&["a", "b", "c"]);
+        let x = edited(&base, |todos| {
+            todos[0].title = "a2".into();
+            todos.swap(1, 2);
+        });
+        let y = edited(&base, |todos| {
+            todos[1].description = "notes".into();
+            todos.remove(2);
+            todos.push(todo("d"));
+        });
+
+        let merged = merge(&x, &y);
+        assert!(merged == merge(&y, &x));
+        assert!(merge(&merged, &merged) == merged);
+        assert!(merge(&merged, &x) == merged);
+        assert!(merge(&y, &merged) == merged);
+        let mut kept = titles(&merged);
+        kept.sort();
+        assert_eq!(kept, ["a2", "b", "d"]);
+        assert!(merged[0].todos.iter().any(|t| t.title == "b" && t.description == "notes"));
+    }
+
+    #[test]
+    fn tombstone_beats_an_older_edit() {
+        let base = saved(&["a", "b"]);
+        let edit = edited(&base, |todos| todos[0].title = "a2".into());
+        let removal = edited(&base, |todos| { todos.remove(0); });
+
+        let merged = merge(&edit, &removal);
+        assert_eq!(titles(&merged), ["b"]);
+        assert!(merged == merge(&removal, &edit));
+    }
+
+    #[test]
+    fn tombstone_loses_to_a_later_edit() {
+        let base = saved(&["a", "b"]);
+        let removal = edited(&base, |todos| { todos.remove(0); });
+        let edit = edited(&base, |todos| todos[0].title = "a2".into());
+
+        let merged = merge(&removal, &edit);
+        assert_eq!(titles(&merged), ["a2", "b"]);
+        assert!(merged == merge(&edit, &removal));
+        assert!(merged[0].removed.is_empty());
+    }
+
+    #[test]
+    fn concurrent_inserts_between_the_same_ranks_keep_a_stable_order() {
+        let base = saved(&["a", "b"]);
+        let x = edited(&base, |todos| todos.insert(1, todo("x")));
+        let y = edited(&base, |todos| todos.insert(1, todo("y")));
+
+        let merged = merge(&x, &y);
+        let order = titles(&merged);
+        assert_eq!(order.first(), Some(&"a"));
+        assert_eq!(order.last(), Some(&"b"));
+        assert_eq!(order.len(), 4);
+        assert_eq!(titles(&merge(&y, &x)), order);
+
+        // Saving the merged copy on either side keeps that order
+        let mut again = merged.clone();
+        stamp(&merged, &mut again);
+        assert_eq!(titles(&again), order);
+        assert_eq!(titles(&merge(&again, &x)), order);
+    }
+
+    #[test]
+    fn removed_project_stays_removed_and_its_new_tasks_move_to_the_inbox() {
+        let mut inbox = Project::new(Id::new(), "Inbox".into());
+        inbox.inbox = true;
+        let mut base = saved(&["a"]);
+        base.push(inbox);
+        stamp(&[], &mut base);
+
+        let removal = {
+            let mut copy = base.clone();
+            copy.retain(|p| p.inbox);
+            stamp(&base, &mut copy);
+            copy
+        };
+        let added = edited(&base, |todos| todos.push(todo("b")));
+
+        let merged = merge(&removal, &added);
+        assert!(merged == merge(&added, &removal));
+        assert_eq!(merged.len(), 1);
+        assert!(merged[0].inbox);
+        assert_eq!(titles(&merged), ["b"]);
+
+        // Renamed after the removal: it comes back
+        let renamed = {
+            let mut copy = base.clone();
+            copy[0].name = "Job".into();
+            stamp(&base, &mut copy);
+            copy
+        };
+        let merged = merge(&removal, &renamed);
+        assert!(merged.iter().any(|p| p.name == "Job" && p.todos.is_empty()));
+    }
+
+}
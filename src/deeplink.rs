@@ -0,0 +1,188 @@
+// `dx-todo://` links: `dx-todo://project/<pid>/todo/<id>` opens a task of a project.
+// A link is the route path behind the scheme, so every route can be linked to.
+// Links opened while the app runs are passed to it over a local socket by the process the OS
+// starts for them, which then exits.
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use dioxus::logger::tracing::{debug, warn};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+
+use crate::Route;
+
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener as Listener, UnixStream as Stream};
+#[cfg(not(unix))]
+use std::net::{TcpListener as Listener, TcpStream as Stream};
+
+pub const SCHEME: &str = "dx-todo";
+// Longest link read from another process
+const MAX_LINK: u64 = 4096;
+// A sender that hasn't written its link by then is given up on
+const FORWARD_TIMEOUT: Duration = Duration::from_secs(2);
+
+// Links from later launches, until `App` takes them
+static INCOMING: Mutex<Option<UnboundedReceiver<Route>>> = Mutex::new(None);
+
+pub fn link(route: &Route) -> String {
+    // Route paths start with '/', giving `dx-todo://project/...`
+    format!("{SCHEME}:/{route}")
+}
+
+pub fn parse(url: &str) -> Option<Route> {
+    let rest = url.strip_prefix(SCHEME)?.strip_prefix("://")?;
+    let route = format!("/{}", rest.trim_end_matches('/')).parse().ok()?;
+    // Links copied before UUIDs carry numbers; they lead to the same items after the upgrade
+    Some(match route {
+        Route::List { pid } => Route::List { pid: pid.upgrade_project() },
+        Route::Board { pid } => Route::Board { pid: pid.upgrade_project() },
+        Route::Details { pid, id } => Route::Details { pid: pid.upgrade_project(), id: id.upgrade_todo() },
+        other => other,
+    })
+}
+
+/// The OS passes the clicked link as a command-line argument.
+pub fn from_args() -> Option<Route> {
+    std::env::args().skip(1).find_map(|a| parse(&a))
+}
+
+/// Hand the link this process was started with to an instance that's already running.
+/// `true` when one took it, and this process has nothing left to do.
+pub fn forward() -> bool {
+    let Some(url) = std::env::args().skip(1).find(|a| parse(a).is_some()) else { return false };
+    let Ok(mut stream) = connect() else { return false };
+    writeln!(stream, "{url}").is_ok()
+}
+
+/// Take links from later launches (see `forward`); they arrive through `incoming`.
+/// Does nothing when another instance already listens.
+pub fn listen() -> io::Result<()> {
+    let Some(listener) = bind()? else { return Ok(()) };
+    let (links, rx) = unbounded_channel();
+    *INCOMING.lock().unwrap_or_else(|e| e.into_inner()) = Some(rx);
+    thread::Builder::new().name("links".into()).spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let _ = stream.set_read_timeout(Some(FORWARD_TIMEOUT));
+            let mut line = String::new();
+            if BufReader::new(stream.take(MAX_LINK)).read_line(&mut line).is_err() { continue; }
+            match parse(line.trim()) {
+                Some(route) => if links.send(route).is_err() { break },
+                None => warn!("[Links] Ignoring a forwarded link that isn't a {SCHEME}:// route"),
+            }
+        }
+    })?;
+    Ok(())
+}
+
+/// Routes of links opened while the app runs; `None` after the first call or if `listen` failed.
+pub fn incoming() -> Option<UnboundedReceiver<Route>> {
+    INCOMING.lock().unwrap_or_else(|e| e.into_inner()).take()
+}
+
+fn socket_dir() -> io::Result<PathBuf> {
+    let proj = directories::ProjectDirs::from("com", "dx", "dx_todo_app").ok_or_else(|| io::Error::other("unable to get project dirs"))?;
+    Ok(proj.runtime_dir().unwrap_or(proj.cache_dir()).to_path_buf())
+}
+
+#[cfg(unix)]
+fn connect() -> io::Result<Stream> {
+    Stream::connect(socket_dir()?.join("links.sock"))
+}
+
+#[cfg(unix)]
+fn bind() -> io::Result<Option<Listener>> {
+    if connect().is_ok() { return Ok(None); }
+    let dir = socket_dir()?;
+    fs::create_dir_all(&dir)?;
+    let path = dir.join("links.sock");
+    // Nobody answered, so a socket left here belongs to an instance that's gone
+    if fs::remove_file(&path).is_ok() { debug!("[Links] Removed stale socket {}", path.display()); }
+    Listener::bind(path).map(Some)
+}
+
+// No Unix sockets in std here: a loopback port, written where later launches look for it
+#[cfg(not(unix))]
+fn connect() -> io::Result<Stream> {
+    let port: u16 = fs::read_to_string(socket_dir()?.join("links.port"))?.trim().parse().map_err(io::Error::other)?;
+    Stream::connect_timeout(&(std::net::Ipv4Addr::LOCALHOST, port).into(), FORWARD_TIMEOUT)
+}
+
+#[cfg(not(unix))]
+fn bind() -> io::Result<Option<Listener>> {
+    if connect().is_ok() { return Ok(None); }
+    let dir = socket_dir()?;
+    fs::create_dir_all(&dir)?;
+    let listener = Listener::bind((std::net::Ipv4Addr::LOCALHOST, 0))?;
+    fs::write(dir.join("links.port"), listener.local_addr()?.port().to_string())?;
+    debug!("[Links] Listening on port {}", listener.local_addr()?.port());
+    Ok(Some(listener))
+}
+
+/// Tell the OS to open `dx-todo://` links with this executable. Runs on every start, but only
+/// touches the OS settings when the handler is missing or points at another executable.
+#[cfg(target_os = "linux")]
+pub fn register() -> io::Result<()> {
+    use std::process::Command;
+
+    let exe = std::env::current_exe()?;
+    let dirs = directories::BaseDirs::new().ok_or_else(|| io::Error::other("unable to get base dirs"))?;
+    let apps = dirs.data_dir().join("applications");
+    fs::create_dir_all(&apps)?;
+    let file_name = "dx-todo-app-url.desktop";
+    let entry = format!(
+        "[Desktop Entry]\nType=Application\nName=DX Todo\nExec=\"{}\" %u\nNoDisplay=true\nMimeType=x-scheme-handler/{SCHEME};\n",
+        exe.display()
+    );
+    let path = apps.join(file_name);
+    if !fs::read_to_string(&path).is_ok_and(|s| s == entry) {
+        fs::write(&path, entry)?;
+    }
+    let mime = format!("x-scheme-handler/{SCHEME}");
+    let current = Command::new("xdg-mime").args(["query", "default", &mime]).output()?;
+    if String::from_utf8_lossy(&current.stdout).trim() == file_name {
+        return Ok(());
+    }
+    let status = Command::new("xdg-mime").args(["default", file_name, &mime]).status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!("xdg-mime exited with {status}")));
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+pub fn register() -> io::Result<()> {
+    use std::process::Command;
+
+    let exe = std::env::current_exe()?;
+    let key = format!(r"HKCU\Software\Classes\{SCHEME}");
+    let command = format!("\"{}\" \"%1\"", exe.display());
+    let current = Command::new("reg").args(["query", &format!(r"{key}\shell\open\command"), "/ve"]).output()?;
+    if current.status.success() && String::from_utf8_lossy(&current.stdout).contains(&command) {
+        return Ok(());
+    }
+    let entries: [(String, &str, String); 3] = [
+        (key.clone(), "/ve", format!("URL:{SCHEME}")),
+        (key.clone(), "URL Protocol", String::new()),
+        (format!(r"{key}\shell\open\command"), "/ve", command),
+    ];
+    for (k, value, data) in entries {
+        let mut cmd = Command::new("reg");
+        cmd.args(["add", &k]);
+        if value == "/ve" { cmd.arg("/ve"); } else { cmd.args(["/v", value]); }
+        let status = cmd.args(["/d", &data, "/f"]).status()?;
+        if !status.success() {
+            return Err(io::Error::other(format!("reg add {k} exited with {status}")));
+        }
+    }
+    Ok(())
+}
+
+// macOS reads URL schemes from the bundle's Info.plist (CFBundleURLTypes) at install time
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+pub fn register() -> io::Result<()> {
+    Ok(())
+}
@@ -0,0 +1,44 @@
+// Task dependencies: `Todo::blocked_by` holds ids of todos (in any project) that must be done first.
+use std::collections::HashSet;
+
+use crate::id::Id;
+use crate::models::{find_todo, Project, Todo};
+
+/// Blockers of `todo` that are still open; ids of deleted todos are ignored.
+pub fn open_blockers<'a>(projects: &'a [Project], todo: &Todo) -> Vec<&'a Todo> {
+    todo.blocked_by
+        .iter()
+        .filter_map(|bid| find_todo(projects, *bid).map(|(_, t)| t))
+        .filter(|t| !t.completed)
+        .collect()
+}
+
+/// Why `id` can't be completed yet, if it's open and waits on open blockers.
+pub fn completion_blocked(projects: &[Project], id: Id) -> Option<String> {
+    let (_, todo) = find_todo(projects, id).filter(|(_, t)| !t.completed)?;
+    let titles: Vec<&str> = open_blockers(projects, todo).iter().map(|b| b.title.as_str()).collect();
+    (!titles.is_empty()).then(|| format!("Blocked by: {}. Finish those first or remove the dependency in Details.", titles.join(", ")))
+}
+
+/// Would making `todo_id` wait on `blocker_id` close a loop (including waiting on itself)?
+pub fn would_cycle(projects: &[Project], todo_id: Id, blocker_id: Id) -> bool {
+    // The new edge todo -> blocker cycles iff `todo_id` is already reachable from the blocker
+    let mut stack = vec![blocker_id];
+    let mut seen = HashSet::new();
+    while let Some(id) = stack.pop() {
+        if id == todo_id { return true; }
+        if !seen.insert(id) { continue; }
+        if let Some((_, t)) = find_todo(projects, id) {
+            stack.extend(t.blocked_by.iter().copied());
+        }
+    }
+    false
+}
+
+/// Drop references to todos that no longer exist as tasks (deleted or demoted).
+pub fn forget(projects: &mut [Project], removed: &[Id]) {
+    if removed.is_empty() { return; }
+    for t in projects.iter_mut().flat_map(|p| p.todos.iter_mut()) {
+        t.blocked_by.retain(|id| !removed.contains(id));
+    }
+}
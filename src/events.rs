@@ -0,0 +1,484 @@
+//! Events for integrations: a task created, completed, reopened, moved or removed, a subtask
+//! toggled, a project created. They're sent to the hooks set up in Settings → Integrations, either
+//! a webhook (the event is POSTed as JSON) or a local command (the event arrives as JSON on stdin,
+//! its name in `DX_TODO_EVENT`).
+//!
+//! Events are derived on the save thread by comparing each save with the one before (`saved`),
+//! so every screen and the API produce them without code of their own. Every save is compared,
+//! even when several are written together. Being a comparison, it only sees where each save
+//! ended up: a task created and removed again before a save, or moved and moved back, sends
+//! nothing. Changes brought in from elsewhere (other devices, another instance, restoring
+//! history) only move the baseline (`observe`): they were events where they were made.
+use crate::id::Id;
+use crate::models::{Project, Subtask, Todo};
+use chrono::{DateTime, NaiveDate, Utc};
+use dioxus::logger::tracing::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+// A webhook that takes longer than this counts as failed
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Kind {
+    #[serde(rename = "todo.created")]
+    TodoCreated,
+    #[serde(rename = "todo.completed")]
+    TodoCompleted,
+    #[serde(rename = "todo.reopened")]
+    TodoReopened,
+    #[serde(rename = "todo.moved")]
+    TodoMoved,
+    #[serde(rename = "todo.deleted")]
+    TodoDeleted,
+    #[serde(rename = "subtask.toggled")]
+    SubtaskToggled,
+    #[serde(rename = "project.created")]
+    ProjectCreated,
+}
+
+impl Kind {
+    pub const ALL: [Kind; 7] = [
+        Kind::TodoCreated,
+        Kind::TodoCompleted,
+        Kind::TodoReopened,
+        Kind::TodoMoved,
+        Kind::TodoDeleted,
+        Kind::SubtaskToggled,
+        Kind::ProjectCreated,
+    ];
+
+    // As sent in payloads and `DX_TODO_EVENT`
+    pub fn name(self) -> &'static str {
+        match self {
+            Kind::TodoCreated => "todo.created",
+            Kind::TodoCompleted => "todo.completed",
+            Kind::TodoReopened => "todo.reopened",
+            Kind::TodoMoved => "todo.moved",
+            Kind::TodoDeleted => "todo.deleted",
+            Kind::SubtaskToggled => "subtask.toggled",
+            Kind::ProjectCreated => "project.created",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Kind::TodoCreated => "Task created",
+            Kind::TodoCompleted => "Task completed",
+            Kind::TodoReopened => "Task reopened",
+            Kind::TodoMoved => "Task moved",
+            Kind::TodoDeleted => "Task removed",
+            Kind::SubtaskToggled => "Subtask toggled",
+            Kind::ProjectCreated => "Project created",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum HookKind {
+    Webhook,
+    Command,
+}
+
+impl HookKind {
+    pub const ALL: [HookKind; 2] = [HookKind::Webhook, HookKind::Command];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            HookKind::Webhook => "Webhook",
+            HookKind::Command => "Command",
+        }
+    }
+}
+
+/// Where events go: a URL to POST them to, or a command line to run for each.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Hook {
+    pub kind: HookKind,
+    pub target: String,
+    // Events that trigger the hook; empty means all of them
+    #[serde(default)]
+    pub events: Vec<Kind>,
+    #[serde(default = "enabled")]
+    pub enabled: bool,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+impl Hook {
+    pub fn wants(&self, kind: Kind) -> bool {
+        self.enabled && (self.events.is_empty() || self.events.contains(&kind))
+    }
+}
+
+/// The project an event happened in, with its task counts after the change (e.g. to notice
+/// when everything is done).
+#[derive(Clone, Serialize)]
+pub struct ProjectInfo {
+    pub id: Id,
+    pub name: String,
+    pub completed: usize,
+    pub total: usize,
+}
+
+impl ProjectInfo {
+    fn of(p: &Project) -> Self {
+        Self { id: p.id, name: p.name.clone(), completed: p.todos.iter().filter(|t| t.completed).count(), total: p.todos.len() }
+    }
+}
+
+/// A task as events carry it: its fields without the merge bookkeeping (see `crdt`), which
+/// changes with every sync and isn't meant for hooks.
+#[derive(Clone, Serialize)]
+pub struct TodoInfo {
+    pub id: Id,
+    pub title: String,
+    pub completed: bool,
+    pub subtasks: Vec<SubtaskInfo>,
+    pub description: String,
+    pub created_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub due: Option<NaiveDate>,
+    pub blocked_by: Vec<Id>,
+    pub status: String,
+}
+
+impl TodoInfo {
+    pub fn of(t: &Todo) -> Self {
+        Self {
+            id: t.id,
+            title: t.title.clone(),
+            completed: t.completed,
+            subtasks: t.subtasks.iter().map(SubtaskInfo::of).collect(),
+            description: t.description.clone(),
+            created_at: t.created_at,
+            completed_at: t.completed_at,
+            due: t.due,
+            blocked_by: t.blocked_by.clone(),
+            status: t.status.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct SubtaskInfo {
+    pub id: Id,
+    pub title: String,
+    pub completed: bool,
+    pub created_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub description: String,
+    pub children: Vec<SubtaskInfo>,
+}
+
+impl SubtaskInfo {
+    pub fn of(s: &Subtask) -> Self {
+        Self {
+            id: s.id,
+            title: s.title.clone(),
+            completed: s.completed,
+            created_at: s.created_at,
+            completed_at: s.completed_at,
+            description: s.description.clone(),
+            children: s.children.iter().map(SubtaskInfo::of).collect(),
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct Event {
+    pub event: Kind,
+    pub at: DateTime<Utc>,
+    pub project: ProjectInfo,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub todo: Option<TodoInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subtask: Option<SubtaskInfo>,
+    // The project a moved task came from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<ProjectInfo>,
+}
+
+impl Event {
+    fn new(event: Kind, project: &Project) -> Self {
+        Self { event, at: Utc::now(), project: ProjectInfo::of(project), todo: None, subtask: None, from: None }
+    }
+
+    fn todo(self, todo: &Todo) -> Self {
+        Self { todo: Some(TodoInfo::of(todo)), ..self }
+    }
+}
+
+/// The events that turn `old` into `new`.
+pub fn diff(old: &[Project], new: &[Project]) -> Vec<Event> {
+    let mut events = Vec::new();
+    let old_todos: HashMap<Id, (&Project, &Todo)> = old.iter().flat_map(|p| p.todos.iter().map(move |t| (t.id, (p, t)))).collect();
+    // Counts for a project are taken after the change
+    fn current<'a>(new: &'a [Project], p: &'a Project) -> &'a Project {
+        new.iter().find(|n| n.id == p.id).unwrap_or(p)
+    }
+
+    for p in new {
+        if !old.iter().any(|o| o.id == p.id) {
+            events.push(Event::new(Kind::ProjectCreated, p));
+        }
+        for t in &p.todos {
+            let Some((from, before)) = old_todos.get(&t.id) else {
+                events.push(Event::new(Kind::TodoCreated, p).todo(t));
+                continue;
+            };
+            if from.id != p.id {
+                events.push(Event { from: Some(ProjectInfo::of(current(new, from))), ..Event::new(Kind::TodoMoved, p).todo(t) });
+            }
+            if before.completed != t.completed {
+                let kind = if t.completed { Kind::TodoCompleted } else { Kind::TodoReopened };
+                events.push(Event::new(kind, p).todo(t));
+            }
+            let mut was = HashMap::new();
+            Subtask::walk(&before.subtasks, 0, &mut |s, _| { was.insert(s.id, s.completed); });
+            toggled(&t.subtasks, &was, &mut |s| events.push(Event { subtask: Some(SubtaskInfo::of(s)), ..Event::new(Kind::SubtaskToggled, p).todo(t) }));
+        }
+    }
+
+    let remaining: HashSet<Id> = new.iter().flat_map(|p| p.todos.iter().map(|t| t.id)).collect();
+    for p in old {
+        for t in p.todos.iter().filter(|t| !remaining.contains(&t.id)) {
+            events.push(Event::new(Kind::TodoDeleted, current(new, p)).todo(t));
+        }
+    }
+    events
+}
+
+// Subtasks whose completion changed; those that only followed a toggled parent aren't repeated
+fn toggled<'a>(list: &'a [Subtask], was: &HashMap<Id, bool>, found: &mut impl FnMut(&'a Subtask)) {
+    for s in list {
+        if was.get(&s.id).is_some_and(|done| *done != s.completed) {
+            found(s);
+        } else {
+            toggled(&s.children, was, found);
+        }
+    }
+}
+
+// The state as of the last save, to compare the next one with; `None` while a workspace loads
+static BASELINE: Mutex<Option<Vec<Project>>> = Mutex::new(None);
+static HOOKS: Mutex<Vec<Hook>> = Mutex::new(Vec::new());
+static DELIVERIES: OnceLock<Sender<(Hook, String, String)>> = OnceLock::new();
+
+pub fn set_hooks(hooks: Vec<Hook>) {
+    *HOOKS.lock().unwrap_or_else(|e| e.into_inner()) = hooks;
+}
+
+/// Forget the baseline, e.g. when another workspace is opened; saves until the next `observe`
+/// are part of loading it.
+pub fn reset() {
+    *BASELINE.lock().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Take `projects` as the new baseline without sending events.
+pub fn observe(projects: &[Project]) {
+    *BASELINE.lock().unwrap_or_else(|e| e.into_inner()) = Some(projects.to_vec());
+}
+
+/// Send events for what changed since the last save to the hooks that want them.
+pub fn saved(projects: &[Project]) {
+    let mut baseline = BASELINE.lock().unwrap_or_else(|e| e.into_inner());
+    if baseline.is_none() {
+        return;
+    }
+    let old = baseline.replace(projects.to_vec()).unwrap_or_default();
+    drop(baseline);
+    let hooks: Vec<Hook> = HOOKS.lock().unwrap_or_else(|e| e.into_inner()).iter().filter(|h| h.enabled).cloned().collect();
+    if hooks.is_empty() {
+        return;
+    }
+    for event in diff(&old, projects) {
+        let Ok(body) = serde_json::to_string(&event) else { continue };
+        for hook in hooks.iter().filter(|h| h.wants(event.event)) {
+            let _ = deliveries().send((hook.clone(), event.event.name().to_string(), body.clone()));
+        }
+    }
+}
+
+// Webhooks are posted one at a time, in order, off the UI thread
+fn deliveries() -> &'static Sender<(Hook, String, String)> {
+    DELIVERIES.get_or_init(|| {
+        let (tx, rx) = mpsc::channel::<(Hook, String, String)>();
+        thread::Builder::new()
+            .name("events".into())
+            .spawn(move || {
+                for (hook, name, body) in rx {
+                    match hook.kind {
+                        HookKind::Webhook => report(&hook, &name, post(&hook.target, &name, &body)),
+                        // A slow command shouldn't hold up the others
+                        HookKind::Command => { thread::spawn(move || report(&hook, &name, run(&hook.target, &name, &body))); }
+                    }
+                }
+            })
+            .expect("failed to start the events thread");
+        tx
+    })
+}
+
+fn report(hook: &Hook, name: &str, result: Result<(), String>) {
+    match result {
+        Ok(()) => info!("[Events] Sent {} to {}", name, hook.target),
+        Err(e) => warn!("[Events] {} hook {} failed for {}: {}", hook.kind.label(), hook.target, name, e),
+    }
+}
+
+fn post(url: &str, name: &str, body: &str) -> Result<(), String> {
+    ureq::post(url)
+        .timeout(TIMEOUT)
+        .set("Content-Type", "application/json")
+        .set("X-Dx-Todo-Event", name)
+        .send_string(body)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+fn run(command: &str, name: &str, body: &str) -> Result<(), String> {
+    #[cfg(target_os = "windows")]
+    let mut cmd = { let mut c = Command::new("cmd"); c.args(["/C", command]); c };
+    #[cfg(not(target_os = "windows"))]
+    let mut cmd = { let mut c = Command::new("sh"); c.args(["-c", command]); c };
+    let mut child = cmd
+        .env("DX_TODO_EVENT", name)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // A command that doesn't read its input closes the pipe early; that's fine
+        let _ = stdin.write_all(body.as_bytes());
+    }
+    let out = child.wait_with_output().map_err(|e| e.to_string())?;
+    if out.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    Err(format!("{}{}", out.status, stderr.lines().next().map(|l| format!(": {l}")).unwrap_or_default()))
+}
+
+/// Send `hook` a made-up event and wait for the outcome, to check it's set up right.
+pub async fn test(hook: Hook) -> Result<(), String> {
+    let kind = hook.events.first().copied().unwrap_or(Kind::TodoCompleted);
+    let mut project = Project::new(Id::new(), "Example project".into());
+    let mut todo = Todo::new(Id::new(), "Test event from DX Todo".into());
+    todo.set_completed(kind != Kind::TodoReopened);
+    project.todos.push(todo.clone());
+    let event = match kind {
+        Kind::ProjectCreated => Event::new(kind, &project),
+        _ => Event::new(kind, &project).todo(&todo),
+    };
+    let body = serde_json::to_string(&event).map_err(|e| e.to_string())?;
+    let (tx, rx) = oneshot::channel();
+    thread::spawn(move || {
+        let result = match hook.kind {
+            HookKind::Webhook => post(&hook.target, kind.name(), &body),
+            HookKind::Command => run(&hook.target, kind.name(), &body),
+        };
+        let _ = tx.send(result);
+    });
+    rx.await.unwrap_or_else(|_| Err("the test stopped unexpectedly".into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn project(name: &str, titles: &[&str]) -> Project {
+        let mut p = Project::new(Id::new(), name.into());
+        p.todos = titles.iter().map(|t| Todo::new(Id::new(), t.to_string())).collect();
+        p
+    }
+
+    // (event, task or subtask title) pairs, sorted
+    fn names(events: &[Event]) -> Vec<(&'static str, String)> {
+        let mut names: Vec<_> = events.iter().map(|e| {
+            let title = e.subtask.as_ref().map(|s| s.title.clone()).or_else(|| e.todo.as_ref().map(|t| t.title.clone()));
+            (e.event.name(), title.unwrap_or_default())
+        }).collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn diff_names_what_changed() {
+        let mut work = project("Work", &["Write report", "Plan trip", "Old task"]);
+        work.todos[1].add_subtask(None, "Book flights".into());
+        work.todos[1].add_subtask(None, "Book hotel".into());
+        work.todos[2].set_completed(true);
+        let home = project("Home", &["Water plants"]);
+        let old = vec![work, home];
+
+        let mut new = old.clone();
+        new[0].todos[0].set_completed(true);
+        new[0].todos[2].set_completed(false);
+        let flights = new[0].todos[1].subtasks[0].id;
+        new[0].todos[1].toggle_subtask(flights);
+        let moved = new[1].todos.remove(0);
+        new[0].todos.push(moved);
+        new[0].todos.push(Todo::new(Id::new(), "Call Sam".into()));
+        new.push(project("Garden", &[]));
+
+        let events = diff(&old, &new);
+        assert_eq!(names(&events), [
+            ("project.created", String::new()),
+            ("subtask.toggled", "Book flights".into()),
+            ("todo.completed", "Write report".into()),
+            ("todo.created", "Call Sam".into()),
+            ("todo.moved", "Water plants".into()),
+            ("todo.reopened", "Old task".into()),
+        ]);
+        let moved = events.iter().find(|e| e.event == Kind::TodoMoved).unwrap();
+        assert_eq!(moved.from.as_ref().map(|p| (p.name.as_str(), p.total)), Some(("Home", 0)));
+        // Counts are after the change
+        assert_eq!((moved.project.completed, moved.project.total), (1, 5));
+
+        let removed = diff(&new, &old);
+        assert!(names(&removed).contains(&("todo.deleted", "Call Sam".into())));
+        assert!(diff(&new, &new).is_empty());
+    }
+
+    #[test]
+    fn webhook_gets_the_event() {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let port = server.server_addr().to_ip().unwrap().port();
+        let received = thread::spawn(move || {
+            let mut request = server.recv().unwrap();
+            let header = request.headers().iter().find(|h| h.field.equiv("X-Dx-Todo-Event")).map(|h| h.value.to_string());
+            let mut body = String::new();
+            request.as_reader().read_to_string(&mut body).unwrap();
+            request.respond(tiny_http::Response::empty(204)).unwrap();
+            (header, body)
+        });
+
+        let mut projects = vec![project("Work", &["Write report"])];
+        crate::crdt::stamp(&[], &mut projects);
+        projects[0].todos[0].set_completed(true);
+        let event = Event::new(Kind::TodoCompleted, &projects[0]).todo(&projects[0].todos[0]);
+        let body = serde_json::to_string(&event).unwrap();
+        post(&format!("http://127.0.0.1:{port}/hook"), event.event.name(), &body).unwrap();
+
+        let (header, body) = received.join().unwrap();
+        assert_eq!(header.as_deref(), Some("todo.completed"));
+        let sent: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(sent["event"], "todo.completed");
+        assert_eq!(sent["project"]["completed"], 1);
+        assert_eq!(sent["todo"]["title"], "Write report");
+        let todo = sent["todo"].as_object().unwrap();
+        assert!(!todo.contains_key("rank") && !todo.contains_key("stamps") && !todo.contains_key("removed"));
+    }
+}
@@ -0,0 +1,162 @@
+use pulldown_cmark::{html::push_html, CowStr, Event, Options, Parser, Tag};
+use std::fs;
+use std::path::Path;
+
+use crate::models::{Project, Subtask, Todo};
+
+// Inline the app stylesheet so the page is a single self-contained file
+const MAIN_CSS: &str = include_str!("../../assets/main.css");
+// The app shell pins everything to one viewport; a static page needs to scroll
+const REPORT_CSS: &str = r#"
+body { overflow: auto; }
+.app { height: auto; min-height: 100vh; overflow: visible; display: block; }
+.card, .list-item { animation: none; }
+.list { max-height: none; overflow: visible; }
+.list-item { align-items: flex-start; }
+.report-project + .report-project { margin-top: 28px; }
+.report-summary { display: flex; align-items: center; gap: 12px; margin: 4px 0 12px; }
+.report-summary .progress { flex: 1; }
+.todo-title-row { display: flex; align-items: center; gap: 10px; }
+.todo-title-row .progress { width: 120px; }
+details.todo-more { margin-top: 6px; }
+details.todo-more > summary { cursor: pointer; color: var(--muted); font-size: 13px; }
+.desc-rendered { font-size: 14px; color: #334155; margin: 6px 0 0 30px; }
+.desc-rendered p { margin: 4px 0; }
+.sub-node > details > summary { list-style: none; cursor: pointer; display: flex; align-items: center; gap: 8px; }
+.sub-node > details > summary::-webkit-details-marker { display: none; }
+.sub-node .subtasks { margin-left: 26px; }
+"#;
+
+pub fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+// Only web and mail links (or relative ones) survive into the page; `javascript:`, `data:`
+// and friends are blanked out
+fn safe_url(url: &str) -> bool {
+    // Browsers ignore whitespace and control characters inside the scheme, e.g. "java\tscript:"
+    let url: String = url.chars().filter(|c| !c.is_ascii_whitespace() && !c.is_control()).collect();
+    match url.find([':', '/', '?', '#']) {
+        Some(i) if url[i..].starts_with(':') => matches!(url[..i].to_ascii_lowercase().as_str(), "http" | "https" | "mailto"),
+        _ => true,
+    }
+}
+
+fn sanitize(url: CowStr<'_>) -> CowStr<'_> {
+    if safe_url(&url) { url } else { CowStr::Borrowed("") }
+}
+
+// Render a Markdown description; raw HTML in the source is shown as text
+pub fn render_markdown(src: &str) -> String {
+    let opts = Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TABLES | Options::ENABLE_TASKLISTS;
+    let parser = Parser::new_ext(src, opts).map(|ev| match ev {
+        Event::Html(h) | Event::InlineHtml(h) => Event::Text(h),
+        Event::Start(Tag::Link { link_type, dest_url, title, id }) => Event::Start(Tag::Link { link_type, dest_url: sanitize(dest_url), title, id }),
+        Event::Start(Tag::Image { link_type, dest_url, title, id }) => Event::Start(Tag::Image { link_type, dest_url: sanitize(dest_url), title, id }),
+        other => other,
+    });
+    let mut out = String::new();
+    push_html(&mut out, parser);
+    out
+}
+
+pub fn progress_bar(done: usize, total: usize) -> String {
+    let pct = (done * 100).checked_div(total).unwrap_or(0);
+    format!(r#"<div class="progress" title="{pct}%"><div class="progress-fill" style="width:{pct}%"></div></div><span class="meta">{done}/{total}</span>"#)
+}
+
+fn checkbox(checked: bool) -> &'static str {
+    if checked { r#"<input type="checkbox" checked disabled>"# } else { r#"<input type="checkbox" disabled>"# }
+}
+
+fn render_todo(out: &mut String, t: &Todo) {
+    let title_cls = if t.completed { "item-title completed" } else { "item-title" };
+    out.push_str(r#"<li class="list-item">"#);
+    out.push_str(checkbox(t.completed));
+    out.push_str(r#"<div class="content"><div class="todo-title-row">"#);
+    out.push_str(&format!(r#"<span class="{title_cls}">{}</span>"#, escape(&t.title)));
+    if !t.subtasks.is_empty() {
+        let (done, total) = t.subtask_progress();
+        out.push_str(&progress_bar(done, total));
+    }
+    out.push_str("</div>");
+    if !t.description.trim().is_empty() {
+        out.push_str(&format!(r#"<div class="desc-rendered">{}</div>"#, render_markdown(&t.description)));
+    }
+    if !t.subtasks.is_empty() {
+        out.push_str(&format!(r#"<details class="todo-more" open><summary>{} subtask(s)</summary>"#, t.subtask_progress().1));
+        render_subtasks(out, &t.subtasks);
+        out.push_str("</details>");
+    }
+    out.push_str("</div></li>");
+}
+
+// Nested lists; nodes with children collapse via <details>
+fn render_subtasks(out: &mut String, list: &[Subtask]) {
+    out.push_str(r#"<ul class="subtasks">"#);
+    for s in list {
+        let cls = if s.completed { "sub-title completed" } else { "sub-title" };
+        let row = format!(r#"<span class="sub-item">{}<span class="{cls}">{}</span></span>"#, checkbox(s.completed), escape(&s.title));
+        out.push_str(r#"<li class="sub-node">"#);
+        if s.children.is_empty() {
+            out.push_str(&row);
+        } else {
+            let (done, total) = s.progress();
+            out.push_str(&format!(r#"<details open><summary>{row}<span class="meta">{}/{}</span></summary>"#, done - s.completed as usize, total - 1));
+        }
+        if !s.description.trim().is_empty() {
+            out.push_str(&format!(r#"<div class="desc-rendered">{}</div>"#, render_markdown(&s.description)));
+        }
+        if !s.children.is_empty() {
+            render_subtasks(out, &s.children);
+            out.push_str("</details>");
+        }
+        out.push_str("</li>");
+    }
+    out.push_str("</ul>");
+}
+
+fn render_project(out: &mut String, p: &Project) {
+    let done = p.todos.iter().filter(|t| t.completed).count();
+    out.push_str(r#"<section class="report-project">"#);
+    out.push_str(&format!(r#"<div class="header"><h1 class="title">{}</h1></div>"#, escape(&p.name)));
+    out.push_str(r#"<div class="report-summary">"#);
+    out.push_str(&progress_bar(done, p.todos.len()));
+    out.push_str("</div>");
+    out.push_str(r#"<ul class="list">"#);
+    for t in &p.todos {
+        render_todo(out, t);
+    }
+    out.push_str("</ul></section>");
+}
+
+// Wrap body markup into a standalone page carrying the app styles
+pub fn page(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n<title>{}</title>\n<style>\n{}\n{}\n</style>\n</head>\n<body>\n<div class=\"app\"><div class=\"card\">\n{}\n</div></div>\n</body>\n</html>\n",
+        escape(title), MAIN_CSS, REPORT_CSS, body
+    )
+}
+
+pub fn render_projects(title: &str, projects: &[Project]) -> String {
+    let mut body = String::new();
+    for p in projects {
+        render_project(&mut body, p);
+    }
+    page(title, &body)
+}
+
+pub fn write_projects(title: &str, projects: &[Project], path: &Path) -> Result<(), String> {
+    fs::write(path, render_projects(title, projects)).map_err(|e| format!("write error: {e}"))
+}
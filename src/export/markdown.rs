@@ -0,0 +1,28 @@
+use crate::models::{Project, Subtask};
+
+// GitHub-style task lists; descriptions are already Markdown and are indented under their task
+pub fn render_projects(projects: &[Project]) -> String {
+    let mut out = String::new();
+    for p in projects {
+        if !out.is_empty() { out.push('\n'); }
+        out.push_str(&format!("# {}\n\n", p.name));
+        for t in &p.todos {
+            let mark = if t.completed { "[x]" } else { "[ ]" };
+            out.push_str(&format!("- {} {}", mark, t.title));
+            if let Some(due) = t.due { out.push_str(&format!(" (due {})", due)); }
+            out.push('\n');
+            for line in t.description.trim().lines() {
+                out.push_str(&format!("  {}\n", line));
+            }
+            Subtask::walk(&t.subtasks, 1, &mut |s, depth| {
+                let indent = "  ".repeat(depth);
+                let mark = if s.completed { "[x]" } else { "[ ]" };
+                out.push_str(&format!("{}- {} {}\n", indent, mark, s.title));
+                for line in s.description.trim().lines() {
+                    out.push_str(&format!("{}  {}\n", indent, line));
+                }
+            });
+        }
+    }
+    out
+}
@@ -0,0 +1,118 @@
+pub mod html;
+pub mod markdown;
+pub mod pdf;
+
+use dioxus::logger::tracing::{info, warn};
+use rfd::FileDialog;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::components::toast::{ToastAction, ToastState};
+use crate::id::Id;
+use crate::models::Project;
+use crate::report::Report;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Pdf,
+    Html,
+    Markdown,
+}
+
+impl ExportFormat {
+    pub fn label(self) -> &'static str {
+        match self {
+            ExportFormat::Pdf => "PDF",
+            ExportFormat::Html => "HTML",
+            ExportFormat::Markdown => "Markdown",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Pdf => "pdf",
+            ExportFormat::Html => "html",
+            ExportFormat::Markdown => "md",
+        }
+    }
+}
+
+fn write_text(contents: &str, path: &Path) -> Result<(), String> {
+    fs::write(path, contents).map_err(|e| format!("write error: {e}"))
+}
+
+// Native save dialog, opened in `dir` when set; `None` when the user cancels
+pub fn ask_save_path(format: ExportFormat, title: &str, file_stem: &str, dir: Option<&Path>) -> Option<PathBuf> {
+    let mut dialog = FileDialog::new();
+    if let Some(dir) = dir {
+        dialog = dialog.set_directory(dir);
+    }
+    dialog
+        .set_title(title)
+        .add_filter(format.label(), &[format.extension()])
+        .set_file_name(format!("{}.{}", file_stem, format.extension()))
+        .save_file()
+}
+
+// Export the active project. Returns the written path, or `None` when the user cancels the save dialog.
+pub fn export_active_project(projects: &[Project], active_id: Option<Id>, format: ExportFormat, dir: Option<&Path>) -> Result<Option<PathBuf>, String> {
+    let active_id = active_id.ok_or_else(|| "No active project selected".to_string())?;
+    let project = projects.iter().find(|p| p.id == active_id).ok_or_else(|| "Active project not found".to_string())?;
+
+    let Some(path) = ask_save_path(format, &format!("Export Project to {}", format.label()), &project.name, dir) else {
+        return Ok(None);
+    };
+    let title = format!("Project: {}", project.name);
+    match format {
+        ExportFormat::Pdf => pdf::write_lines(&title, &pdf::project_lines(project), &path)?,
+        ExportFormat::Html => html::write_projects(&title, std::slice::from_ref(project), &path)?,
+        ExportFormat::Markdown => write_text(&markdown::render_projects(std::slice::from_ref(project)), &path)?,
+    }
+    Ok(Some(path))
+}
+
+// Export every project into one HTML page
+pub fn export_all_projects_html(projects: &[Project], dir: Option<&Path>) -> Result<Option<PathBuf>, String> {
+    if projects.is_empty() {
+        return Err("There are no projects to export".into());
+    }
+    let Some(path) = ask_save_path(ExportFormat::Html, "Export All Projects to HTML", "projects", dir) else {
+        return Ok(None);
+    };
+    html::write_projects("Projects", projects, &path)?;
+    Ok(Some(path))
+}
+
+// Export a progress report in any format
+pub fn export_report(report: &Report, format: ExportFormat, dir: Option<&Path>) -> Result<Option<PathBuf>, String> {
+    let Some(path) = ask_save_path(format, &format!("Export Report to {}", format.label()), &report.file_stem(), dir) else {
+        return Ok(None);
+    };
+    match format {
+        ExportFormat::Pdf => pdf::write_lines(&report.title(), &report.to_pdf_lines(), &path)?,
+        ExportFormat::Html => write_text(&report.to_html(), &path)?,
+        ExportFormat::Markdown => write_text(&report.to_markdown(), &path)?,
+    }
+    Ok(Some(path))
+}
+
+// Surface an export outcome to the user, opening the file too when `open_file` is set
+pub fn notify(toasts: &mut ToastState, res: Result<Option<PathBuf>, String>, open_file: bool) {
+    match res {
+        Ok(Some(path)) => {
+            info!("[Export] Wrote {}", path.display());
+            if open_file && let Err(e) = opener::open(&path) {
+                warn!("[Export] Couldn't open {}: {}", path.display(), e);
+            }
+            toasts.success(
+                format!("Exported to {}", path.display()),
+                vec![ToastAction::OpenFile(path.clone()), ToastAction::ShowInFolder(path)],
+            );
+        }
+        Ok(None) => { toasts.info("Export canceled"); }
+        Err(e) => {
+            warn!("[Export] Error: {}", e);
+            toasts.error(format!("Export failed: {e}"));
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use printpdf::{PdfDocument, PdfDocumentReference, Mm, BuiltinFont};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use crate::models::{Project, Subtask};
+
+/// One line of PDF output: text and font size in points.
+pub type Line = (String, f64);
+
+// Flatten a project into printable lines (tasks, subtasks, descriptions)
+pub fn project_lines(project: &Project) -> Vec<Line> {
+    let mut lines = vec![(format!("Project: {}", project.name), 16.0), (String::new(), 10.0)];
+    for t in &project.todos {
+        let mark = if t.completed { "[x]" } else { "[ ]" };
+        lines.push((format!("{} {}", mark, t.title), 12.0));
+        // Subtasks, indented by depth
+        Subtask::walk(&t.subtasks, 1, &mut |s, depth| {
+            let mark = if s.completed { "[x]" } else { "[ ]" };
+            lines.push((format!("{}{} {}", "    ".repeat(depth), mark, s.title), 11.0));
+            if !s.description.trim().is_empty() {
+                lines.push((format!("{}  — {}", "    ".repeat(depth), s.description.trim()), 10.0));
+            }
+        });
+        if !t.description.trim().is_empty() {
+            lines.push((format!("    — {}", t.description.trim()), 10.0));
+        }
+    }
+    lines
+}
+
+// Write lines to a simple A4 PDF using printpdf's built-in Helvetica font
+pub fn write_lines(title: &str, lines: &[Line], path: &Path) -> Result<(), String> {
+    let (doc, page1, layer1) = PdfDocument::new(title, Mm(210.0), Mm(297.0), "Layer 1");
+    let font = doc.add_builtin_font(BuiltinFont::Helvetica).map_err(|e| format!("font error: {e}"))?;
+
+    // Page layout
+    let mut current_page = page1;
+    let mut current_layer = doc.get_page(current_page).get_layer(layer1);
+    let margin_left = Mm(15.0);
+    let margin_top = Mm(15.0);
+    let line_height = Mm(6.0);
+    let mut cursor_y = Mm(297.0) - margin_top;
+
+    // Helper to write line and handle pagination
+    let mut write_line = |doc: &PdfDocumentReference, text: &str, size_pt: f64| {
+        if cursor_y.0 < 20.0 { // new page if near bottom
+            let (p, l) = doc.add_page(Mm(210.0), Mm(297.0), "Layer");
+            current_page = p;
+            current_layer = doc.get_page(current_page).get_layer(l);
+            cursor_y = Mm(297.0) - margin_top;
+        }
+        current_layer.use_text(text, size_pt, margin_left, cursor_y, &font);
+        cursor_y = Mm(cursor_y.0 - line_height.0);
+    };
+
+    for (text, size) in lines {
+        write_line(&doc, text, *size);
+    }
+
+    let mut out = BufWriter::new(File::create(path).map_err(|e| format!("create error: {e}"))?);
+    doc.save(&mut out).map_err(|e| format!("save error: {e}"))?;
+    Ok(())
+}
@@ -0,0 +1,411 @@
+//! Sync between machines through a shared folder (Syncthing, Dropbox, a network drive, or any
+//! local directory). Each device appends its changes to its own `<device>.jsonl` there, so no
+//! file ever has two writers, and reads the other devices' files from where it left off.
+//!
+//! A line holds what one sync changed: the projects touched, each with only its changed tasks,
+//! stamped as `crdt` keeps them. Other devices fold those lines in with `crdt::merge`, like any
+//! other copy of the workspace, so edits to different fields of a task both survive and moves
+//! keep their order. When two devices change the same field without having seen each other's
+//! change, the later edit wins on every device and the pair is listed as a conflict.
+use crate::crdt::{self, Clash, Target};
+use crate::models::{upgrade_ids, Project, Todo};
+use chrono::{DateTime, Utc};
+use dioxus::logger::tracing::{info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+const EXT: &str = "jsonl";
+
+/// One line of a device file.
+#[derive(Serialize, Deserialize)]
+struct Change {
+    at: DateTime<Utc>,
+    device: String,
+    // Projects that changed or hold changed tasks, with just those tasks; removals are in
+    // their tombstones
+    projects: Vec<Project>,
+    // The same projects and tasks, and removed tasks, as they were before; a receiver that
+    // changed them from something else had an edit of its own the sender didn't see
+    before: Vec<Project>,
+}
+
+/// Two devices changed the same field concurrently. `kept` won; `other` can still be chosen.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Conflict {
+    pub target: Target,
+    pub label: String,
+    pub field: String,
+    pub kept: Value,
+    pub other: Value,
+    // Device whose change was involved, for display
+    pub device: String,
+}
+
+impl Conflict {
+    fn new(clash: Clash, device: String) -> Self {
+        let (kept, other) = (clash.kept().clone(), clash.other().clone());
+        Self { target: clash.target, label: clash.label, field: clash.field, kept, other, device }
+    }
+
+    pub fn field_label(&self) -> String {
+        match self.field.as_str() {
+            crdt::REMOVED => "Removed".into(),
+            field => {
+                let text = field.replace('_', " ");
+                let mut chars = text.chars();
+                chars.next().map(|c| c.to_uppercase().chain(chars).collect()).unwrap_or_default()
+            }
+        }
+    }
+}
+
+/// Short text for a field value in the conflict list.
+pub fn display_value(v: &Value) -> String {
+    let text = match v {
+        Value::Null => "(none)".to_string(),
+        Value::String(s) if s.is_empty() => "(empty)".to_string(),
+        Value::String(s) => s.clone(),
+        Value::Object(m) => m.get("title").or_else(|| m.get("name")).and_then(Value::as_str).unwrap_or("…").to_string(),
+        Value::Array(list) if list.iter().any(Value::is_object) => format!("{} item(s)", list.len()),
+        other => other.to_string(),
+    };
+    if text.chars().count() > 80 { format!("{}…", text.chars().take(80).collect::<String>()) } else { text }
+}
+
+// Local bookkeeping, kept in the workspace's data dir (not the shared folder)
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Meta {
+    // Bytes read so far from each other device's file
+    offsets: HashMap<String, u64>,
+    // Start of each such file's first line; another start means the file was replaced
+    heads: HashMap<String, String>,
+    conflicts: Vec<Conflict>,
+    // State as of the last sync; local changes are the diff from it. `None` before the first
+    synced: Option<Vec<Project>>,
+}
+
+pub struct Engine {
+    dir: PathBuf,
+    device: String,
+    meta_path: PathBuf,
+    meta: Meta,
+}
+
+/// A name for this device that's unique enough among a user's machines and safe as a file name.
+pub fn new_device_id() -> String {
+    let host = std::env::var("HOSTNAME").or_else(|_| std::env::var("COMPUTERNAME")).unwrap_or_else(|_| "device".into());
+    let host: String = host.chars().filter(|c| c.is_ascii_alphanumeric() || *c == '-').take(24).collect();
+    let nanos = Utc::now().timestamp_nanos_opt().unwrap_or_default() as u64;
+    format!("{}-{:06x}", if host.is_empty() { "device" } else { &host }, (nanos ^ std::process::id() as u64) & 0xff_ffff)
+}
+
+impl Engine {
+    /// Sync the workspace in `data_dir` through `dir`.
+    pub fn open(dir: PathBuf, device: String, data_dir: &Path) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+        let meta_path = data_dir.join("sync.json");
+        let mut meta = match fs::read_to_string(&meta_path) {
+            Ok(s) => serde_json::from_str(&s).unwrap_or_else(|e| {
+                warn!("[Sync] Starting over, couldn't read {}: {}", meta_path.display(), e);
+                Meta::default()
+            }),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Meta::default(),
+            Err(e) => return Err(e),
+        };
+        // Bookkeeping from before UUIDs; otherwise the upgrade would be published as a change
+        if let Some(synced) = &mut meta.synced {
+            upgrade_ids(synced);
+        }
+        for c in &mut meta.conflicts {
+            c.target = c.target.upgrade();
+        }
+        info!("[Sync] Syncing through {} as {}", dir.display(), device);
+        Ok(Self { dir, device, meta_path, meta })
+    }
+
+    pub fn conflicts(&self) -> &[Conflict] {
+        &self.meta.conflicts
+    }
+
+    pub fn dismiss(&mut self, index: usize) -> io::Result<()> {
+        if index < self.meta.conflicts.len() {
+            self.meta.conflicts.remove(index);
+        }
+        self.save_meta()
+    }
+
+    /// Publish local changes, then merge in other devices' changes. Returns the merged projects
+    /// when anything came in.
+    pub fn sync(&mut self, projects: &[Project]) -> io::Result<Option<Vec<Project>>> {
+        let base = self.meta.synced.clone().unwrap_or_default();
+        let ours = self.publish(&base, projects)?;
+        let merged = self.pull(ours)?;
+        self.save_meta()?;
+        Ok(merged)
+    }
+
+    fn save_meta(&self) -> io::Result<()> {
+        let s = serde_json::to_string(&self.meta).map_err(io::Error::other)?;
+        let tmp = self.meta_path.with_extension("json.tmp");
+        fs::write(&tmp, s)?;
+        fs::rename(&tmp, &self.meta_path)
+    }
+
+    // The window's projects stamped against the last sync, so what changed since wins merges
+    fn publish(&mut self, base: &[Project], projects: &[Project]) -> io::Result<Vec<Project>> {
+        let mut ours = projects.to_vec();
+        crdt::stamp(base, &mut ours);
+        let changed = delta(base, &ours);
+        if !changed.is_empty() {
+            let before = previous(base, &changed, &ours);
+            let change = Change { at: Utc::now(), device: self.device.clone(), projects: changed, before };
+            let mut line = serde_json::to_string(&change).map_err(io::Error::other)?;
+            line.push('\n');
+            let path = self.dir.join(format!("{}.{EXT}", self.device));
+            let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+            file.write_all(line.as_bytes())?;
+            file.sync_data()?;
+        }
+        self.meta.synced = Some(ours.clone());
+        Ok(ours)
+    }
+
+    fn pull(&mut self, ours: Vec<Project>) -> io::Result<Option<Vec<Project>>> {
+        let mut changes = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            let Some(name) = path.file_stem().and_then(|s| s.to_str()).map(str::to_string) else { continue };
+            if path.extension().and_then(|e| e.to_str()) != Some(EXT) || name == self.device {
+                continue;
+            }
+            let mut file = File::open(&path)?;
+            let len = file.metadata()?.len();
+            let head = read_head(&mut file)?;
+            let mut offset = self.meta.offsets.get(&name).copied().unwrap_or(0);
+            if offset > len || self.meta.heads.get(&name).is_some_and(|h| *h != head) {
+                // The file was replaced; merging it again is harmless
+                offset = 0;
+            }
+            file.seek(SeekFrom::Start(offset))?;
+            let mut bytes = Vec::new();
+            file.read_to_end(&mut bytes)?;
+            // Only whole lines; the rest, maybe cut inside a character, may still be arriving
+            let complete = bytes.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+            let text = String::from_utf8_lossy(&bytes[..complete]);
+            for line in text.lines().filter(|l| !l.trim().is_empty()) {
+                match serde_json::from_str::<Change>(line) {
+                    Ok(mut c) => {
+                        // Devices on older versions still write numeric ids
+                        upgrade_ids(&mut c.projects);
+                        upgrade_ids(&mut c.before);
+                        changes.push(c);
+                    }
+                    Err(e) => warn!("[Sync] Skipping unreadable change in {}: {}", path.display(), e),
+                }
+            }
+            if offset + complete as u64 > 0 {
+                self.meta.heads.insert(name.clone(), head);
+            }
+            self.meta.offsets.insert(name, offset + complete as u64);
+        }
+        if changes.is_empty() {
+            return Ok(None);
+        }
+        let merged = changes.iter().fold(ours.clone(), |acc, c| crdt::merge(&acc, &c.projects));
+        // A later clash over the same field replaces an earlier one
+        let mut found: Vec<Conflict> = Vec::new();
+        for c in changes {
+            for clash in crdt::clashes(&c.before, &ours, &c.projects, &merged) {
+                found.retain(|f| (f.target, &f.field) != (clash.target, &clash.field));
+                found.push(Conflict::new(clash, c.device.clone()));
+            }
+        }
+        self.meta.conflicts.extend(found);
+        if merged == ours {
+            return Ok(None);
+        }
+        self.meta.synced = Some(merged.clone());
+        Ok(Some(merged))
+    }
+}
+
+// Enough of a device file's first line to tell it from a replacement: each line starts with
+// the time it was written
+fn read_head(file: &mut File) -> io::Result<String> {
+    let mut bytes = Vec::new();
+    Read::by_ref(file).take(64).read_to_end(&mut bytes)?;
+    let end = bytes.iter().position(|&b| b == b'\n').unwrap_or(bytes.len());
+    Ok(String::from_utf8_lossy(&bytes[..end]).into_owned())
+}
+
+/// The projects with a conflict's other value applied; it syncs out like any local edit.
+pub fn choose_other(projects: &[Project], conflict: &Conflict) -> Vec<Project> {
+    let mut projects = projects.to_vec();
+    crdt::put(&mut projects, conflict.target, &conflict.field, &conflict.other);
+    projects
+}
+
+// What changed from `old` to `new`: projects whose own fields, rank or tombstones changed, or
+// that hold changed tasks, each with just those tasks
+fn delta(old: &[Project], new: &[Project]) -> Vec<Project> {
+    let saved: HashMap<_, _> = old.iter().map(|p| (p.id, p)).collect();
+    let saved_todos: HashMap<_, _> = old.iter().flat_map(|p| p.todos.iter().map(move |t| (t.id, (p.id, t)))).collect();
+    new.iter().filter_map(|p| {
+        let todos: Vec<Todo> = p.todos.iter().filter(|t| saved_todos.get(&t.id) != Some(&(p.id, *t))).cloned().collect();
+        let same = saved.get(&p.id).is_some_and(|o| {
+            (&o.name, &o.columns, o.inbox, &o.rank, &o.stamps, &o.removed) == (&p.name, &p.columns, p.inbox, &p.rank, &p.stamps, &p.removed)
+        });
+        (!same || !todos.is_empty()).then(|| Project { todos, ..record(p) })
+    }).collect()
+}
+
+// A project without its todos
+fn record(p: &Project) -> Project {
+    Project {
+        id: p.id,
+        name: p.name.clone(),
+        todos: Vec::new(),
+        columns: p.columns.clone(),
+        inbox: p.inbox,
+        rank: p.rank.clone(),
+        stamps: p.stamps.clone(),
+        removed: p.removed.clone(),
+    }
+}
+
+// What `changed` (see `delta`) was before: its projects as in `old`, holding the old versions
+// of its tasks and of the tasks removed since
+fn previous(old: &[Project], changed: &[Project], new: &[Project]) -> Vec<Project> {
+    let touched: HashSet<_> = changed.iter().flat_map(|p| p.todos.iter().map(|t| t.id)).collect();
+    let present: HashSet<_> = new.iter().flat_map(|p| p.todos.iter().map(|t| t.id)).collect();
+    old.iter().filter_map(|p| {
+        let todos: Vec<Todo> = p.todos.iter().filter(|t| touched.contains(&t.id) || !present.contains(&t.id)).cloned().collect();
+        let listed = changed.iter().any(|c| c.id == p.id);
+        (listed || !todos.is_empty()).then(|| Project { todos, ..record(p) })
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::id::Id;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("dx-todo-{name}-{}", Id::new()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    // Sync `projects` and take in what came back, as the app does
+    fn sync(engine: &mut Engine, projects: &mut Vec<Project>) {
+        if let Some(merged) = engine.sync(projects).unwrap() {
+            *projects = merged;
+        }
+    }
+
+    fn titles(projects: &[Project]) -> Vec<&str> {
+        projects[0].todos.iter().map(|t| t.title.as_str()).collect()
+    }
+
+    #[test]
+    fn two_devices_converge_after_concurrent_edits() {
+        let shared = temp_dir("shared");
+        let (dir_a, dir_b) = (temp_dir("a"), temp_dir("b"));
+        let mut a = Engine::open(shared.clone(), "a".into(), &dir_a).unwrap();
+        let mut b = Engine::open(shared.clone(), "b".into(), &dir_b).unwrap();
+
+        let mut p = Project::new(Id::new(), "Work".into());
+        p.todos = vec![Todo::new(Id::new(), "Write report".into()), Todo::new(Id::new(), "Call Sam".into())];
+        let mut on_a = vec![p];
+        let mut on_b = Vec::new();
+        sync(&mut a, &mut on_a);
+        sync(&mut b, &mut on_b);
+        assert_eq!(titles(&on_b), ["Write report", "Call Sam"]);
+
+        // Different fields of one task, the same field of another, and a new task on one side
+        on_a[0].todos[0].title = "Write the report".into();
+        on_a[0].todos[1].title = "Call Sam today".into();
+        on_b[0].todos[0].description = "Due Friday".into();
+        on_b[0].todos[1].title = "Email Sam".into();
+        on_b[0].todos.push(Todo::new(Id::new(), "Book a room".into()));
+        sync(&mut a, &mut on_a);
+        sync(&mut b, &mut on_b);
+        sync(&mut a, &mut on_a);
+
+        assert!(on_a == on_b);
+        assert_eq!(titles(&on_a), ["Write the report", "Email Sam", "Book a room"]);
+        assert_eq!(on_a[0].todos[0].description, "Due Friday");
+
+        // The later edit won on both devices; the other value can still be chosen
+        let conflict = a.conflicts().first().cloned().expect("a conflict over the title");
+        assert_eq!(a.conflicts().len(), 1);
+        assert_eq!(conflict.field, "title");
+        assert_eq!(conflict.kept, "Email Sam");
+        assert_eq!(conflict.other, "Call Sam today");
+        assert_eq!(conflict.device, "b");
+        assert_eq!(b.conflicts().len(), 1);
+        assert_eq!(b.conflicts()[0].kept, "Email Sam");
+
+        on_a = choose_other(&on_a, &conflict);
+        a.dismiss(0).unwrap();
+        sync(&mut a, &mut on_a);
+        sync(&mut b, &mut on_b);
+        assert_eq!(titles(&on_b), ["Write the report", "Call Sam today", "Book a room"]);
+        assert!(on_a == on_b);
+        // Made after seeing the other edit, so not another conflict
+        assert_eq!(b.conflicts().len(), 1);
+
+        for dir in [shared, dir_a, dir_b] {
+            let _ = fs::remove_dir_all(dir);
+        }
+    }
+
+    #[test]
+    fn replaced_and_half_written_files_are_read_from_whole_lines() {
+        let shared = temp_dir("shared");
+        let (dir_a, dir_b) = (temp_dir("a"), temp_dir("b"));
+        let mut a = Engine::open(shared.clone(), "a".into(), &dir_a).unwrap();
+        let mut b = Engine::open(shared.clone(), "b".into(), &dir_b).unwrap();
+
+        let mut on_a = vec![Project::new(Id::new(), "Work".into())];
+        on_a[0].todos = vec![Todo::new(Id::new(), "Write report".into())];
+        let mut on_b = Vec::new();
+        sync(&mut a, &mut on_a);
+        sync(&mut b, &mut on_b);
+        assert_eq!(titles(&on_b), ["Write report"]);
+
+        // A character cut in half at the end of the file waits for the rest
+        let path = shared.join(format!("a.{EXT}"));
+        let whole = fs::read(&path).unwrap();
+        on_a[0].todos[0].title = "Write the résumé".into();
+        sync(&mut a, &mut on_a);
+        let written = fs::read(&path).unwrap();
+        let cut = whole.len() + written[whole.len()..].iter().position(|&b| b >= 0x80).unwrap() + 1;
+        fs::write(&path, &written[..cut]).unwrap();
+        sync(&mut b, &mut on_b);
+        assert_eq!(titles(&on_b), ["Write report"]);
+        fs::write(&path, &written).unwrap();
+        sync(&mut b, &mut on_b);
+        assert_eq!(titles(&on_b), ["Write the résumé"]);
+
+        // The file replaced by a longer one, e.g. restored from elsewhere, is read from the start
+        let (other, dir_c) = (temp_dir("other"), temp_dir("c"));
+        let mut c = Engine::open(other.clone(), "a".into(), &dir_c).unwrap();
+        let mut on_c = vec![Project::new(Id::new(), "Home".into())];
+        on_c[0].todos = (0..20).map(|i| Todo::new(Id::new(), format!("Chore {i}"))).collect();
+        sync(&mut c, &mut on_c);
+        assert!(fs::metadata(other.join(format!("a.{EXT}"))).unwrap().len() > written.len() as u64);
+        fs::copy(other.join(format!("a.{EXT}")), &path).unwrap();
+        sync(&mut b, &mut on_b);
+        assert!(on_b.iter().any(|p| p.name == "Home" && p.todos.len() == 20));
+
+        for dir in [shared, other, dir_a, dir_b, dir_c] {
+            let _ = fs::remove_dir_all(dir);
+        }
+    }
+}
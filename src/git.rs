@@ -0,0 +1,463 @@
+//! Git mode: the workspace folder is a git repository holding one file per project, and each
+//! save is a commit. Uses the `git` command-line tool, which must be on the PATH.
+use crate::crdt;
+use crate::id::Id;
+use crate::journal::{self, Op};
+use crate::models::{find_todo, upgrade_ids, Project, Todo};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+use std::thread;
+use tokio::sync::oneshot;
+
+const PROJECTS_DIR: &str = "projects";
+const ORDER_FILE: &str = "workspace.json";
+const REMOTE: &str = "origin";
+// Local app files that don't belong in history
+const GITIGNORE: &str = ".lock\n*.tmp\nprojects.json\nprojects.log\nsync.json\n";
+// Used when git has no user configured, so commits still work out of the box
+const FALLBACK_NAME: &str = "To-Do app";
+const FALLBACK_EMAIL: &str = "todo@localhost";
+
+// Held for each whole git operation (a save's commit, a pull, a push). Commits from the save thread
+// would otherwise run in the middle of a pull: index.lock errors, or a half-merged tree committed
+static EXCLUSIVE: Mutex<()> = Mutex::new(());
+
+// Project order; the projects themselves are in `projects/<id>.json`
+#[derive(Default, Serialize, Deserialize)]
+struct Order {
+    order: Vec<Id>,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct Commit {
+    pub id: String,
+    pub short: String,
+    pub when: DateTime<Local>,
+    pub author: String,
+    pub message: String,
+}
+
+pub struct Repo {
+    dir: PathBuf,
+}
+
+impl Repo {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    // Only ever the workspace's own repository: git would otherwise act on any checkout the
+    // folder happens to sit in
+    fn git(&self, args: &[&str]) -> io::Result<String> {
+        if !self.dir.join(".git").exists() {
+            return Err(io::Error::other(format!("{} isn't a git repository", self.dir.display())));
+        }
+        self.run(args)
+    }
+
+    fn run(&self, args: &[&str]) -> io::Result<String> {
+        let out = Command::new("git")
+            .arg("-C")
+            .arg(&self.dir)
+            .args(args)
+            // A remote asking for credentials fails instead of waiting for input that never comes
+            .env("GIT_TERMINAL_PROMPT", "0")
+            .output()?;
+        if !out.status.success() {
+            let err = String::from_utf8_lossy(&out.stderr).trim().to_string();
+            return Err(io::Error::other(if err.is_empty() { format!("git {} failed", args.join(" ")) } else { err }));
+        }
+        Ok(String::from_utf8_lossy(&out.stdout).into_owned())
+    }
+
+    /// Make the folder a repository if it isn't one yet.
+    pub fn init(&self) -> io::Result<()> {
+        if self.dir.join(".git").exists() {
+            return Ok(());
+        }
+        fs::create_dir_all(&self.dir)?;
+        self.run(&["init", "--quiet"])?;
+        fs::write(self.dir.join(".gitignore"), GITIGNORE)
+    }
+
+    /// Commit everything that changed; returns whether there was anything to commit.
+    pub fn commit_all(&self, message: &str) -> io::Result<bool> {
+        self.git(&["add", "--all"])?;
+        // A merge is committed even when it kept our files as they were
+        let merging = self.dir.join(".git").join("MERGE_HEAD").exists();
+        if !merging && self.git(&["diff", "--cached", "--quiet"]).is_ok() {
+            return Ok(false);
+        }
+        let identity = self.identity();
+        let mut args: Vec<&str> = identity.iter().map(String::as_str).collect();
+        args.extend(["commit", "--quiet", "-m", message]);
+        self.git(&args)?;
+        Ok(true)
+    }
+
+    // `-c` options naming the fallback author where git has none configured
+    fn identity(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        for (key, fallback) in [("user.name", FALLBACK_NAME), ("user.email", FALLBACK_EMAIL)] {
+            if !self.git(&["config", key]).is_ok_and(|s| !s.trim().is_empty()) {
+                args.extend(["-c".to_string(), format!("{key}={fallback}")]);
+            }
+        }
+        args
+    }
+
+    pub fn log(&self, limit: usize) -> io::Result<Vec<Commit>> {
+        // No commits yet is not an error here
+        if self.git(&["rev-parse", "--verify", "--quiet", "HEAD"]).is_err() {
+            return Ok(Vec::new());
+        }
+        let out = self.git(&["log", &format!("-n{limit}"), "--format=%H%x1f%h%x1f%aI%x1f%an%x1f%s"])?;
+        Ok(out.lines().filter_map(|line| {
+            let mut parts = line.split('\u{1f}');
+            let (id, short, when, author, message) = (parts.next()?, parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+            Some(Commit {
+                id: id.into(),
+                short: short.into(),
+                when: DateTime::parse_from_rfc3339(when).ok()?.with_timezone(&Local),
+                author: author.into(),
+                message: message.into(),
+            })
+        }).collect())
+    }
+
+    /// The workspace as it was at `rev`.
+    pub fn projects_at(&self, rev: &str) -> io::Result<Vec<Project>> {
+        let files = self.git(&["ls-tree", "--name-only", rev, &format!("{PROJECTS_DIR}/")])?;
+        let mut projects = Vec::new();
+        for file in files.lines().filter(|f| f.ends_with(".json")) {
+            let text = self.git(&["show", &format!("{rev}:{file}")])?;
+            projects.push(serde_json::from_str(&text).map_err(io::Error::other)?);
+        }
+        let order = self.git(&["show", &format!("{rev}:{ORDER_FILE}")]).ok()
+            .and_then(|s| serde_json::from_str::<Order>(&s).ok())
+            .unwrap_or_default();
+        sort_projects(&mut projects, &order);
+        // Commits from before UUIDs
+        upgrade_ids(&mut projects);
+        Ok(projects)
+    }
+
+    pub fn remote_url(&self) -> Option<String> {
+        self.git(&["remote", "get-url", REMOTE]).ok().map(|s| s.trim().to_string()).filter(|s| !s.is_empty())
+    }
+
+    pub fn set_remote_url(&self, url: &str) -> io::Result<()> {
+        if self.remote_url().is_some() {
+            self.git(&["remote", "set-url", REMOTE, url])?;
+        } else {
+            self.git(&["remote", "add", REMOTE, url])?;
+        }
+        Ok(())
+    }
+
+    // Works before the first commit too
+    fn branch(&self) -> io::Result<String> {
+        Ok(self.git(&["symbolic-ref", "--short", "HEAD"])?.trim().to_string())
+    }
+
+    fn is_ancestor(&self, older: &str, newer: &str) -> bool {
+        self.git(&["merge-base", "--is-ancestor", older, newer]).is_ok()
+    }
+
+    pub fn push(&self) -> io::Result<()> {
+        let branch = self.branch()?;
+        self.git(&["push", "--quiet", "--set-upstream", REMOTE, &branch])?;
+        Ok(())
+    }
+
+    /// Merge the remote's changes. Rather than merging files line by line, both states are
+    /// merged field by field (see `crdt`), so concurrent edits never conflict; the result is
+    /// committed as a merge of the two histories.
+    pub fn pull(&self) -> io::Result<()> {
+        let branch = self.branch()?;
+        self.git(&["fetch", "--quiet", REMOTE, &branch])?;
+        let has_head = self.git(&["rev-parse", "--verify", "--quiet", "HEAD"]).is_ok();
+        if !has_head {
+            // `init`'s .gitignore isn't committed yet and would block the checkout; the remote
+            // usually brings the same one
+            let ignore = self.dir.join(".gitignore");
+            if fs::read_to_string(&ignore).ok().as_deref() == Some(GITIGNORE) {
+                fs::remove_file(&ignore)?;
+            }
+            let merged = self.git(&["merge", "--quiet", "--ff-only", "FETCH_HEAD"]);
+            if !ignore.exists() {
+                fs::write(&ignore, GITIGNORE)?;
+            }
+            return merged.map(|_| ());
+        }
+        if self.is_ancestor("HEAD", "FETCH_HEAD") {
+            self.git(&["merge", "--quiet", "--ff-only", "FETCH_HEAD"])?;
+            return Ok(());
+        }
+        if self.is_ancestor("FETCH_HEAD", "HEAD") {
+            return Ok(());
+        }
+        let mut ours = read_tree(&self.dir)?;
+        let mut theirs = self.projects_at("FETCH_HEAD")?;
+        // Files written before ranks and stamps get them first, so their order survives
+        crdt::stamp(&[], &mut ours);
+        crdt::stamp(&[], &mut theirs);
+        let merged = crdt::merge(&ours, &theirs);
+        // Record both parents but keep our files, then write the merged state over them. Two
+        // devices that each started their own repository share no history at first
+        let identity = self.identity();
+        let mut args: Vec<&str> = identity.iter().map(String::as_str).collect();
+        args.extend(["merge", "--quiet", "--no-commit", "--strategy=ours", "--allow-unrelated-histories", "FETCH_HEAD"]);
+        self.git(&args)?;
+        let written = write_tree(&self.dir, &merged).and_then(|_| self.commit_all(&format!("Merge changes from {REMOTE}")));
+        if let Err(e) = written {
+            let _ = self.git(&["merge", "--abort"]);
+            return Err(e);
+        }
+        Ok(())
+    }
+}
+
+fn sort_projects(projects: &mut [Project], order: &Order) {
+    projects.sort_by_key(|p| (order.order.iter().position(|id| *id == p.id).unwrap_or(usize::MAX), p.id));
+}
+
+fn project_file(dir: &Path, id: Id) -> PathBuf {
+    dir.join(PROJECTS_DIR).join(format!("{id}.json"))
+}
+
+/// Whether `dir` holds a workspace saved in git mode.
+pub fn has_tree(dir: &Path) -> bool {
+    dir.join(PROJECTS_DIR).is_dir()
+}
+
+/// What `read_tree` reads: the project folder and the order file.
+pub fn tree_paths(dir: &Path) -> [PathBuf; 2] {
+    [dir.join(PROJECTS_DIR), dir.join(ORDER_FILE)]
+}
+
+/// Write one file per project plus the project order; files are only rewritten when they changed.
+pub fn write_tree(dir: &Path, projects: &[Project]) -> io::Result<()> {
+    let root = dir.join(PROJECTS_DIR);
+    fs::create_dir_all(&root)?;
+    for p in projects {
+        let path = project_file(dir, p.id);
+        let text = serde_json::to_string_pretty(p).map_err(io::Error::other)?;
+        if fs::read_to_string(&path).ok().as_deref() != Some(text.as_str()) {
+            fs::write(&path, text)?;
+        }
+    }
+    for entry in fs::read_dir(&root)? {
+        let path = entry?.path();
+        let id = path.file_stem().and_then(|s| s.to_str()).and_then(|s| s.parse::<Id>().ok());
+        if id.is_some_and(|id| !projects.iter().any(|p| p.id == id)) {
+            fs::remove_file(&path)?;
+        }
+    }
+    let order = Order { order: projects.iter().map(|p| p.id).collect() };
+    fs::write(dir.join(ORDER_FILE), serde_json::to_string_pretty(&order).map_err(io::Error::other)?)
+}
+
+pub fn read_tree(dir: &Path) -> io::Result<Vec<Project>> {
+    let mut projects = Vec::new();
+    for entry in fs::read_dir(dir.join(PROJECTS_DIR))? {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let text = fs::read_to_string(&path)?;
+        projects.push(serde_json::from_str(&text).map_err(|e| io::Error::other(format!("{}: {e}", path.display())))?);
+    }
+    let order = fs::read_to_string(dir.join(ORDER_FILE)).ok()
+        .and_then(|s| serde_json::from_str::<Order>(&s).ok())
+        .unwrap_or_default();
+    sort_projects(&mut projects, &order);
+    upgrade_ids(&mut projects);
+    Ok(projects)
+}
+
+/// A commit message for going from `old` to `new`, e.g. "Complete 'Write report' in Work".
+pub fn describe(old: &[Project], new: &[Project]) -> String {
+    let project_name = |list: &[Project], id: Id| list.iter().find(|p| p.id == id).map(|p| p.name.clone()).unwrap_or_default();
+    let mut lines: Vec<(bool, String)> = journal::diff(old, new).into_iter().filter_map(|op| Some(match op {
+        Op::PutTodo { project, todo } => {
+            let name = project_name(new, project);
+            match find_todo(old, todo.id) {
+                None => (true, format!("Add '{}' to {}", todo.title, name)),
+                Some((from, _)) if from.id != project => (true, format!("Move '{}' to {}", todo.title, name)),
+                Some((_, prev)) if !prev.completed && todo.completed => (true, format!("Complete '{}' in {}", todo.title, name)),
+                Some((_, prev)) if prev.completed && !todo.completed => (true, format!("Reopen '{}' in {}", todo.title, name)),
+                Some((_, prev)) if prev.title != todo.title => (true, format!("Rename '{}' to '{}' in {}", prev.title, todo.title, name)),
+                // Only its rank changed
+                Some((_, prev)) if Todo { rank: prev.rank.clone(), stamps: prev.stamps.clone(), ..todo.clone() } == *prev => (false, format!("Reorder tasks in {name}")),
+                Some(_) => (true, format!("Edit '{}' in {}", todo.title, name)),
+            }
+        }
+        Op::RemoveTodo { id } => {
+            let text = find_todo(old, id).map(|(p, t)| format!("Remove '{}' from {}", t.title, p.name)).unwrap_or_else(|| "Remove a task".into());
+            (true, text)
+        }
+        Op::PutProject { id, name, columns, inbox, .. } => match old.iter().find(|p| p.id == id) {
+            None => (true, format!("Add project {name}")),
+            Some(p) if p.name != name => (true, format!("Rename project {} to {}", p.name, name)),
+            Some(p) if p.columns != columns => (true, format!("Change columns in {name}")),
+            Some(p) if p.inbox != inbox => (true, format!("Change {name}")),
+            // Only its rank, stamps or tombstones changed; the other ops say why
+            Some(_) => return None,
+        },
+        Op::RemoveProject { id } => (true, format!("Remove project {}", project_name(old, id))),
+        Op::OrderProjects { .. } => (false, "Reorder projects".into()),
+        Op::OrderTodos { project, .. } => (false, format!("Reorder tasks in {}", project_name(new, project))),
+    })).collect();
+    // A move shows up both as a new rank and a new order
+    let mut seen = HashSet::new();
+    lines.retain(|line| seen.insert(line.clone()));
+    // Say what happened before how things moved around
+    lines.sort_by_key(|(main, _)| !*main);
+    match lines.len() {
+        0 => "Save workspace".into(),
+        1 => lines.remove(0).1,
+        n => format!("{} and {} more change(s)", lines.remove(0).1, n - 1),
+    }
+}
+
+/// Run `work` while no other git operation of this process runs. Don't wait for the save thread
+/// (`storage::flush`) inside: it needs the lock to commit.
+pub fn exclusive<T>(work: impl FnOnce() -> T) -> T {
+    let _guard = EXCLUSIVE.lock().unwrap_or_else(|e| e.into_inner());
+    work()
+}
+
+/// Run blocking git work off the UI thread.
+pub async fn background<T: Send + 'static>(work: impl FnOnce() -> io::Result<T> + Send + 'static) -> io::Result<T> {
+    let (tx, rx) = oneshot::channel();
+    thread::spawn(move || {
+        let _ = tx.send(work());
+    });
+    rx.await.unwrap_or_else(|_| Err(io::Error::other("git task stopped unexpectedly")))
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("dx-todo-{name}-{}", Id::new()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn workspace(name: &str, titles: &[&str]) -> Vec<Project> {
+        let mut p = Project::new(Id::new(), name.into());
+        p.todos = titles.iter().map(|t| Todo::new(Id::new(), t.to_string())).collect();
+        let mut projects = vec![p];
+        crdt::stamp(&[], &mut projects);
+        projects
+    }
+
+    // Save `edit` of what's in `repo` as the save thread does: stamped against the last commit
+    fn save(repo: &Repo, edit: impl FnOnce(&mut Project)) -> Vec<Project> {
+        let old = read_tree(&repo.dir).unwrap();
+        let mut new = old.clone();
+        new.iter_mut().find(|p| p.name == "Work").map(edit).unwrap();
+        crdt::stamp(&old, &mut new);
+        write_tree(&repo.dir, &new).unwrap();
+        assert!(repo.commit_all(&describe(&old, &new)).unwrap());
+        new
+    }
+
+    fn titles<'a>(projects: &'a [Project], name: &str) -> Vec<&'a str> {
+        projects.iter().find(|p| p.name == name).map(|p| p.todos.iter().map(|t| t.title.as_str()).collect()).unwrap_or_default()
+    }
+
+    #[test]
+    fn push_and_pull_merge_concurrent_edits() {
+        let remote = temp_dir("remote");
+        let out = Command::new("git").arg("-C").arg(&remote).args(["init", "--bare", "--quiet"]).output().unwrap();
+        assert!(out.status.success());
+        let remote_url = remote.to_string_lossy().into_owned();
+        let (a, b, c) = (Repo::new(temp_dir("a")), Repo::new(temp_dir("b")), Repo::new(temp_dir("c")));
+
+        a.init().unwrap();
+        write_tree(&a.dir, &workspace("Work", &["Write report", "Call Sam"])).unwrap();
+        assert!(a.commit_all("Add project Work").unwrap());
+        a.set_remote_url(&remote_url).unwrap();
+        a.push().unwrap();
+
+        // A repository of its own first, so the first pull joins two histories
+        b.init().unwrap();
+        write_tree(&b.dir, &workspace("Home", &["Water plants"])).unwrap();
+        assert!(b.commit_all("Add project Home").unwrap());
+        b.set_remote_url(&remote_url).unwrap();
+        b.pull().unwrap();
+        assert_eq!(titles(&read_tree(&b.dir).unwrap(), "Work"), ["Write report", "Call Sam"]);
+        b.push().unwrap();
+        a.pull().unwrap();
+
+        // Both edit before seeing the other's commit
+        save(&a, |p| p.todos[0].title = "Write the report".into());
+        a.push().unwrap();
+        save(&b, |p| p.todos[1].set_completed(true));
+        assert!(b.push().is_err());
+        b.pull().unwrap();
+        b.push().unwrap();
+        a.pull().unwrap();
+
+        let (on_a, on_b) = (read_tree(&a.dir).unwrap(), read_tree(&b.dir).unwrap());
+        assert!(on_a == on_b);
+        assert_eq!(titles(&on_a, "Work"), ["Write the report", "Call Sam"]);
+        assert_eq!(titles(&on_a, "Home"), ["Water plants"]);
+        assert!(on_a.iter().flat_map(|p| &p.todos).any(|t| t.title == "Call Sam" && t.completed));
+        let log: Vec<String> = a.log(3).unwrap().into_iter().map(|c| c.message).collect();
+        assert_eq!(log, ["Merge changes from origin", "Complete 'Call Sam' in Work", "Rename 'Write report' to 'Write the report' in Work"]);
+
+        // A new, empty repository takes the remote's history as it is
+        c.init().unwrap();
+        c.set_remote_url(&remote_url).unwrap();
+        c.pull().unwrap();
+        assert!(read_tree(&c.dir).unwrap() == on_b);
+        assert!(c.dir.join(".gitignore").exists());
+
+        // A folder inside another checkout isn't taken for a repository of its own
+        let nested = Repo::new(a.dir.join(PROJECTS_DIR));
+        assert!(nested.set_remote_url("elsewhere").is_err());
+        assert!(nested.push().is_err() && nested.pull().is_err());
+        assert_eq!(a.remote_url().as_deref(), Some(remote_url.as_str()));
+
+        for dir in [remote, a.dir, b.dir, c.dir] {
+            let _ = fs::remove_dir_all(dir);
+        }
+    }
+
+    #[test]
+    fn describe_names_the_change() {
+        let old = workspace("Work", &["Write report", "Call Sam"]);
+        let edited = |edit: &dyn Fn(&mut Vec<Project>)| {
+            let mut new = old.clone();
+            edit(&mut new);
+            crdt::stamp(&old, &mut new);
+            describe(&old, &new)
+        };
+
+        assert_eq!(describe(&old, &old), "Save workspace");
+        assert_eq!(edited(&|p| p[0].todos.push(Todo::new(Id::new(), "Book a room".into()))), "Add 'Book a room' to Work");
+        assert_eq!(edited(&|p| p[0].todos[0].set_completed(true)), "Complete 'Write report' in Work");
+        assert_eq!(edited(&|p| p[0].todos[1].due = chrono::NaiveDate::from_ymd_opt(2026, 1, 2)), "Edit 'Call Sam' in Work");
+        // The tombstone left in the project isn't a change of its own
+        assert_eq!(edited(&|p| { p[0].todos.remove(1); }), "Remove 'Call Sam' from Work");
+        assert_eq!(edited(&|p| p[0].name = "Job".into()), "Rename project Work to Job");
+        assert_eq!(edited(&|p| p[0].todos.swap(0, 1)), "Reorder tasks in Work");
+        assert_eq!(
+            edited(&|p| {
+                p[0].todos[0].set_completed(true);
+                p[0].todos[1].set_completed(true);
+            }),
+            "Complete 'Write report' in Work and 1 more change(s)"
+        );
+    }
+}
@@ -0,0 +1,128 @@
+//! Ids of projects, todos and subtasks: UUIDv7, so any device can create items offline without
+//! colliding with another's, and ids sort by creation time.
+//!
+//! Data saved before these ids carries small numbers. They still parse, as legacy ids, and are
+//! upgraded on load (`models::upgrade_ids`) to UUIDs derived from the number. The derivation is
+//! the same on every device, so copies of a workspace upgraded separately still line up, and old
+//! links can be upgraded the same way.
+use serde::de::{self, Deserializer};
+use serde::ser::Serializer;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+// Namespace of the UUIDs that legacy ids are upgraded to
+const LEGACY: Uuid = Uuid::from_u128(0x6478_2d74_6f64_6f2d_8000_6c65_6761_6379);
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Id(Uuid);
+
+impl Id {
+    /// A fresh id, for every new project, todo and subtask; later calls in this process sort after
+    /// earlier ones.
+    pub fn new() -> Self {
+        Self(Uuid::now_v7())
+    }
+
+    /// Which of `n` buckets the id falls in, for things like picking a color; spread evenly.
+    pub fn bucket(&self, n: usize) -> usize {
+        (self.0.as_u64_pair().1 % n as u64) as usize
+    }
+
+    /// Whether this is a number from before UUIDs, still to be upgraded.
+    pub fn is_legacy(&self) -> bool {
+        self.legacy_number().is_some()
+    }
+
+    /// The id a legacy project id is upgraded to; other ids are returned unchanged.
+    pub fn upgrade_project(self) -> Self {
+        self.upgrade("project")
+    }
+
+    /// The id a legacy todo id is upgraded to. Todo numbers were unique across projects.
+    pub fn upgrade_todo(self) -> Self {
+        self.upgrade("todo")
+    }
+
+    /// The id a legacy subtask id is upgraded to; subtask numbers were only unique within `todo`.
+    pub fn upgrade_subtask(self, todo: Id) -> Self {
+        self.upgrade(&format!("todo/{}/subtask", todo.0))
+    }
+
+    fn upgrade(self, scope: &str) -> Self {
+        match self.legacy_number() {
+            Some(n) => Self(Uuid::new_v5(&LEGACY, format!("{scope}/{n}").as_bytes())),
+            None => self,
+        }
+    }
+
+    fn legacy(n: u64) -> Self {
+        Self(Uuid::from_u64_pair(0, n))
+    }
+
+    // The number of an id from before UUIDs
+    fn legacy_number(&self) -> Option<u64> {
+        match self.0.as_u64_pair() {
+            (0, n) => Some(n),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Id {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for Id {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.legacy_number() {
+            Some(n) => write!(f, "{n}"),
+            None => write!(f, "{}", self.0),
+        }
+    }
+}
+
+impl fmt::Debug for Id {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+// Route segments, form values and links
+impl FromStr for Id {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(n) = s.parse::<u64>() {
+            return Ok(Self::legacy(n));
+        }
+        Uuid::parse_str(s).map(Self).map_err(|e| format!("invalid id {s}: {e}"))
+    }
+}
+
+impl Serialize for Id {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.legacy_number() {
+            Some(n) => serializer.serialize_u64(n),
+            None => serializer.collect_str(&self.0),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Id {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Number(u64),
+            Text(String),
+        }
+        match Repr::deserialize(deserializer)? {
+            Repr::Number(n) => Ok(Self::legacy(n)),
+            Repr::Text(s) => s.parse().map_err(de::Error::custom),
+        }
+    }
+}
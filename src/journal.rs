@@ -0,0 +1,179 @@
+//! Append-only change log kept next to the projects snapshot. Each (debounced) save appends one entry
+//! with the operations that turn the previous state into the new one; loading replays the entries on
+//! top of the snapshot, and compaction folds them into a new snapshot.
+use crate::crdt::{Stamps, Tombstones};
+use crate::id::Id;
+use crate::models::{Project, Todo};
+use chrono::{DateTime, Utc};
+use dioxus::logger::tracing::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+/// One change. Values are stored whole, so replaying an op twice gives the same result.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Op {
+    // Create a project or update its name, columns and merge bookkeeping; its todos are left alone
+    PutProject {
+        id: Id,
+        name: String,
+        columns: Vec<String>,
+        inbox: bool,
+        #[serde(default)]
+        rank: String,
+        #[serde(default)]
+        stamps: Stamps,
+        #[serde(default)]
+        removed: Tombstones,
+    },
+    RemoveProject { id: Id },
+    OrderProjects { ids: Vec<Id> },
+    // Create or replace a todo (with its subtasks), moving it to `project` if it's elsewhere
+    PutTodo { project: Id, todo: Todo },
+    RemoveTodo { id: Id },
+    OrderTodos { project: Id, ids: Vec<Id> },
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Entry {
+    pub at: DateTime<Utc>,
+    pub ops: Vec<Op>,
+}
+
+/// The operations that turn `old` into `new`.
+pub fn diff(old: &[Project], new: &[Project]) -> Vec<Op> {
+    let mut ops = Vec::new();
+    let old_projects: HashMap<Id, &Project> = old.iter().map(|p| (p.id, p)).collect();
+    let old_todos: HashMap<Id, (Id, &Todo)> = old.iter().flat_map(|p| p.todos.iter().map(move |t| (t.id, (p.id, t)))).collect();
+
+    for p in new {
+        let prev = old_projects.get(&p.id);
+        let same = |o: &&Project| {
+            o.name == p.name && o.columns == p.columns && o.inbox == p.inbox && o.rank == p.rank && o.stamps == p.stamps && o.removed == p.removed
+        };
+        if !prev.is_some_and(same) {
+            ops.push(Op::PutProject {
+                id: p.id,
+                name: p.name.clone(),
+                columns: p.columns.clone(),
+                inbox: p.inbox,
+                rank: p.rank.clone(),
+                stamps: p.stamps.clone(),
+                removed: p.removed.clone(),
+            });
+        }
+        for t in &p.todos {
+            if !old_todos.get(&t.id).is_some_and(|(pid, o)| *pid == p.id && *o == t) {
+                ops.push(Op::PutTodo { project: p.id, todo: t.clone() });
+            }
+        }
+    }
+
+    let new_todos: HashMap<Id, Id> = new.iter().flat_map(|p| p.todos.iter().map(move |t| (t.id, p.id))).collect();
+    for p in old {
+        for t in &p.todos {
+            if !new_todos.contains_key(&t.id) {
+                ops.push(Op::RemoveTodo { id: t.id });
+            }
+        }
+    }
+    for p in old {
+        if !new.iter().any(|n| n.id == p.id) {
+            ops.push(Op::RemoveProject { id: p.id });
+        }
+    }
+
+    if !old.iter().map(|p| p.id).eq(new.iter().map(|p| p.id)) {
+        ops.push(Op::OrderProjects { ids: new.iter().map(|p| p.id).collect() });
+    }
+    for p in new {
+        let ids: Vec<Id> = p.todos.iter().map(|t| t.id).collect();
+        // Replaying the puts and removes keeps old todos in place and appends new ones
+        let kept = old_projects.get(&p.id).map(|o| o.todos.iter().map(|t| t.id).filter(|id| ids.contains(id)).collect::<Vec<_>>()).unwrap_or_default();
+        let replayed: Vec<Id> = kept.iter().copied().chain(ids.iter().copied().filter(|id| !kept.contains(id))).collect();
+        if replayed != ids {
+            ops.push(Op::OrderTodos { project: p.id, ids });
+        }
+    }
+    ops
+}
+
+// Stable sort by position in `ids`; anything not listed keeps its place after them
+fn order_by<T>(list: &mut [T], ids: &[Id], key: impl Fn(&T) -> Id) {
+    list.sort_by_key(|item| ids.iter().position(|id| *id == key(item)).unwrap_or(usize::MAX));
+}
+
+pub fn apply(projects: &mut Vec<Project>, op: Op) {
+    match op {
+        Op::PutProject { id, name, columns, inbox, rank, stamps, removed } => match projects.iter_mut().find(|p| p.id == id) {
+            Some(p) => {
+                p.name = name;
+                p.columns = columns;
+                p.inbox = inbox;
+                p.rank = rank;
+                p.stamps = stamps;
+                p.removed = removed;
+            }
+            None => projects.push(Project { columns, inbox, rank, stamps, removed, ..Project::new(id, name) }),
+        },
+        Op::RemoveProject { id } => projects.retain(|p| p.id != id),
+        Op::OrderProjects { ids } => order_by(projects, &ids, |p| p.id),
+        Op::PutTodo { project, todo } => {
+            let Some(target) = projects.iter().position(|p| p.id == project) else {
+                warn!("[Journal] Skipping todo {} for missing project {}", todo.id, project);
+                return;
+            };
+            if let Some(slot) = projects[target].todos.iter_mut().find(|t| t.id == todo.id) {
+                *slot = todo;
+                return;
+            }
+            for p in projects.iter_mut() {
+                p.todos.retain(|t| t.id != todo.id);
+            }
+            projects[target].todos.push(todo);
+        }
+        Op::RemoveTodo { id } => {
+            for p in projects.iter_mut() {
+                p.todos.retain(|t| t.id != id);
+            }
+        }
+        Op::OrderTodos { project, ids } => {
+            if let Some(p) = projects.iter_mut().find(|p| p.id == project) {
+                order_by(&mut p.todos, &ids, |t| t.id);
+            }
+        }
+    }
+}
+
+/// Append one entry and sync it to disk before returning.
+pub fn append(path: &Path, ops: Vec<Op>) -> io::Result<()> {
+    let mut line = serde_json::to_string(&Entry { at: Utc::now(), ops }).map_err(io::Error::other)?;
+    line.push('\n');
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(line.as_bytes())?;
+    file.sync_data()
+}
+
+/// Apply the log's entries in order; returns how many were applied. A torn last line
+/// (the app stopped mid-write) is skipped.
+pub fn replay(path: &Path, projects: &mut Vec<Project>) -> io::Result<usize> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+    let mut applied = 0;
+    for (n, line) in text.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+        match serde_json::from_str::<Entry>(line) {
+            Ok(entry) => {
+                entry.ops.into_iter().for_each(|op| apply(projects, op));
+                applied += 1;
+            }
+            Err(e) => warn!("[Journal] Skipping unreadable entry on line {} of {}: {}", n + 1, path.display(), e),
+        }
+    }
+    Ok(applied)
+}
@@ -0,0 +1,104 @@
+use directories::ProjectDirs;
+use std::{
+    fs,
+    path::PathBuf,
+    sync::{Mutex, OnceLock},
+};
+use tracing_appender::rolling::{Builder, Rotation};
+use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+
+/// Environment variable holding a `tracing` filter, e.g. `debug` or `dx_todo_app=trace`.
+pub const LOG_ENV: &str = "DX_TODO_LOG";
+const LOG_PREFIX: &str = "dx_todo_app";
+const LOG_SUFFIX: &str = "log";
+const MAX_LOG_FILES: usize = 7;
+const DIAGNOSTIC_LINES: usize = 300;
+
+pub fn log_dir() -> Option<PathBuf> {
+    let proj = ProjectDirs::from("com", "dx", "dx_todo_app")?;
+    Some(proj.cache_dir().join("logs"))
+}
+
+fn default_filter() -> &'static str {
+    if cfg!(debug_assertions) { "dx_todo_app=debug,info" } else { "dx_todo_app=info,warn" }
+}
+
+// Install the global subscriber: daily-rotated log file plus stderr.
+// Must run before `dioxus::launch`, which otherwise installs its own default logger.
+pub fn init() {
+    let filter = || EnvFilter::try_from_env(LOG_ENV).unwrap_or_else(|_| EnvFilter::new(default_filter()));
+
+    let file_layer = log_dir()
+        .and_then(|dir| {
+            fs::create_dir_all(&dir).ok()?;
+            Builder::new()
+                .rotation(Rotation::DAILY)
+                .filename_prefix(LOG_PREFIX)
+                .filename_suffix(LOG_SUFFIX)
+                .max_log_files(MAX_LOG_FILES)
+                .build(dir)
+                .ok()
+        })
+        .map(|appender| fmt::layer().with_ansi(false).with_writer(appender).with_filter(filter()));
+
+    let stderr_layer = fmt::layer().with_writer(std::io::stderr).with_filter(filter());
+
+    let _ = tracing_subscriber::registry().with(file_layer).with(stderr_layer).try_init();
+}
+
+// Newest log files, oldest first
+fn recent_log_files(limit: usize) -> Vec<PathBuf> {
+    let Some(dir) = log_dir() else { return Vec::new() };
+    let Ok(entries) = fs::read_dir(dir) else { return Vec::new() };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.starts_with(LOG_PREFIX) && n.ends_with(LOG_SUFFIX)))
+        .collect();
+    // Rotated names embed the date, so lexical order is chronological
+    files.sort();
+    let skip = files.len().saturating_sub(limit);
+    files.split_off(skip)
+}
+
+/// The log file currently being written, if any exist yet.
+pub fn current_log_file() -> Option<PathBuf> {
+    recent_log_files(1).pop()
+}
+
+/// Plain-text bundle for bug reports: app/version/platform info and the tail of recent logs.
+pub fn diagnostics_report() -> String {
+    let mut out = String::new();
+    out.push_str(&format!("{} {}\n", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")));
+    out.push_str(&format!("os: {} ({})\n", std::env::consts::OS, std::env::consts::ARCH));
+    out.push_str(&format!("log filter: {}\n", std::env::var(LOG_ENV).unwrap_or_else(|_| default_filter().to_string())));
+    out.push_str(&format!("log dir: {}\n", log_dir().map(|d| d.display().to_string()).unwrap_or_else(|| "unavailable".into())));
+
+    let lines: Vec<String> = recent_log_files(2)
+        .iter()
+        .filter_map(|p| fs::read_to_string(p).ok())
+        .flat_map(|s| s.lines().map(str::to_string).collect::<Vec<_>>())
+        .collect();
+    let start = lines.len().saturating_sub(DIAGNOSTIC_LINES);
+    out.push_str(&format!("\n--- last {} log lines ---\n", lines.len() - start));
+    for line in &lines[start..] {
+        out.push_str(line);
+        out.push('\n');
+    }
+    out
+}
+
+// Kept alive for the whole process: on X11 the copied text is served by the owning clipboard instance
+static CLIPBOARD: OnceLock<Mutex<Option<arboard::Clipboard>>> = OnceLock::new();
+
+pub fn copy_diagnostics_to_clipboard() -> Result<(), String> {
+    copy_to_clipboard(diagnostics_report())
+}
+
+pub fn copy_to_clipboard(text: String) -> Result<(), String> {
+    let mut guard = CLIPBOARD.get_or_init(|| Mutex::new(None)).lock().map_err(|e| e.to_string())?;
+    let clipboard = match &mut *guard {
+        Some(c) => c,
+        slot @ None => slot.insert(arboard::Clipboard::new().map_err(|e| e.to_string())?),
+    };
+    clipboard.set_text(text).map_err(|e| e.to_string())
+}
@@ -1,121 +1,128 @@
 use dioxus::prelude::*;
-use dioxus_router::prelude::use_navigator;
+use dioxus_router::prelude::{use_navigator, use_route};
 use dioxus::events::Key;
-use std::cmp::max;
-use printpdf::{PdfDocument, PdfDocumentReference, Mm, BuiltinFont};
-use rfd::FileDialog;
+use dioxus::logger::tracing::{debug, info, warn};
+use chrono::NaiveDate;
+use std::path::PathBuf;
 
 mod models;
 mod storage;
 mod components;
-use models::{Filter, Todo, Subtask, Project};
+mod logging;
+mod export;
+mod report;
+mod stats;
+mod deps;
+mod views;
+mod deeplink;
+mod settings;
+mod cli;
+mod workspace;
+mod journal;
+mod folder_sync;
+mod git;
+mod watch;
+mod id;
+mod crdt;
+mod api;
+mod events;
+use models::{edit_project_of, edit_todo, ensure_inbox, find_todo, move_before, Filter, Todo, Project};
+use id::Id;
 use storage::{load_or_migrate_projects, save_projects};
 use components::{
     header::Header,
     add_form::AddForm,
     filter_bar::FilterBar,
     todo_item::TodoItem,
+    progress::ProgressBar,
+    subtask_tree::{SubtaskActions, SubtaskNode},
 };
 use components::projects::ProjectsState;
 use components::header::HeaderState;
+use components::toast::{ToastHost, ToastState};
+use components::settings::{SettingsState, WorkspaceState};
+use components::confirm::ConfirmDialog;
+use components::sync::{ConflictDialog, ReadOnlyBanner, SyncState};
+use views::SmartView;
 
 const FAVICON: Asset = asset!("/assets/favicon.ico");
 const MAIN_CSS: Asset = asset!("/assets/main.css");
+// How often the sync folder is checked for changes from other devices
+const SYNC_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+// Quiet period after a data file change event before it's read
+const CHANGE_SETTLE: std::time::Duration = std::time::Duration::from_millis(300);
 
-fn main() { dioxus::launch(App); }
-
-// Export active project to a simple PDF using printpdf's built-in Helvetica font
-fn export_active_project_pdf(projects: &Vec<Project>, active_id: Option<u64>) -> Result<(), String> {
-    let active_id = active_id.ok_or_else(|| "No active project selected".to_string())?;
-    let project = projects.iter().find(|p| p.id == active_id).ok_or_else(|| "Active project not found".to_string())?;
-
-    // Ask for save path
-    let Some(path) = FileDialog::new()
-        .set_title("Export Project to PDF")
-        .set_file_name(&format!("{}.pdf", project.name))
-        .save_file() else {
-        return Err("Save canceled".into());
-    };
-
-    // Document A4
-    let (doc, page1, layer1) = PdfDocument::new(&format!("Project: {}", project.name), Mm(210.0), Mm(297.0), "Layer 1");
-    let font = doc.add_builtin_font(BuiltinFont::Helvetica).map_err(|e| format!("font error: {e}"))?;
-
-    // Page layout
-    let mut current_page = page1;
-    let mut current_layer = doc.get_page(current_page).get_layer(layer1);
-    let margin_left = Mm(15.0);
-    let margin_top = Mm(15.0);
-    let line_height = Mm(6.0);
-    let mut cursor_y = Mm(297.0) - margin_top;
-
-    // Helper to write line and handle pagination
-    let mut write_line = |doc: &PdfDocumentReference, text: &str, size_pt: f64| {
-        if cursor_y.0 < 20.0 { // new page if near bottom
-            let (p, l) = doc.add_page(Mm(210.0), Mm(297.0), "Layer");
-            current_page = p;
-            current_layer = doc.get_page(current_page).get_layer(l);
-            cursor_y = Mm(297.0) - margin_top;
-        }
-        current_layer.use_text(text, size_pt, margin_left, cursor_y, &font);
-        cursor_y = Mm(cursor_y.0 - line_height.0);
-    };
-
-    // Header
-    write_line(&doc, &format!("Project: {}", project.name), 16.0);
-    write_line(&doc, "", 10.0);
-
-    // Tasks
-    for t in &project.todos {
-        let mark = if t.completed { "[x]" } else { "[ ]" };
-        write_line(&doc, &format!("{} {}", mark, t.title), 12.0);
-        // Subtasks
-        for s in &t.subtasks {
-            let mark = if s.completed { "[x]" } else { "[ ]" };
-            // indent by adding spaces
-            write_line(&doc, &format!("    {} {}", mark, s.title), 11.0);
-        }
-        if !t.description.trim().is_empty() {
-            write_line(&doc, &format!("    — {}", t.description.trim()), 10.0);
-        }
+fn main() {
+    match cli::init() {
+        Ok(args) if args.help => { print!("{}", cli::USAGE); return; }
+        Ok(_) => {}
+        Err(e) => { eprint!("error: {e}\n\n{}", cli::USAGE); std::process::exit(2); }
     }
-
-    use std::fs::File;
-    use std::io::BufWriter;
-    let mut out = BufWriter::new(File::create(&path).map_err(|e| format!("create error: {e}"))?);
-    doc.save(&mut out).map_err(|e| format!("save error: {e}"))?;
-    Ok(())
+    logging::init();
+    // A link opened while the app runs goes to that instance instead of starting a read-only second one
+    if deeplink::forward() {
+        info!("[App] Passed the link to the running instance");
+        return;
+    }
+    info!("[App] Starting {} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+    if let Err(e) = deeplink::register() {
+        warn!("[App] Couldn't register the {}:// link handler: {}", deeplink::SCHEME, e);
+    }
+    if let Err(e) = deeplink::listen() {
+        warn!("[App] Couldn't listen for {}:// links from other launches: {}", deeplink::SCHEME, e);
+    }
+    dioxus::launch(App);
 }
+
 // Types and persistence are defined in `models.rs` and `storage.rs`.
 
 #[derive(Clone, Copy)]
 struct AppState {
     projects: Signal<Vec<Project>>,
-    active_project_id: Signal<Option<u64>>,
+    active_project_id: Signal<Option<Id>>,
     new_title: Signal<String>,
-    editing_id: Signal<Option<u64>>,
+    editing_id: Signal<Option<Id>>,
     editing_text: Signal<String>,
-    next_id: Signal<u64>,
     filter: Signal<Filter>,
 }
 
 #[derive(Routable, Clone, PartialEq)]
 pub enum Route {
     #[route("/")] Projects {},
-    #[route("/list")] List {},
-    #[route("/todo/:id")] Details { id: u64 },
+    // Task screens share the sidebar
+    #[layout(Shell)]
+        #[route("/project/:pid")] List { pid: Id },
+        #[route("/project/:pid/todo/:id")] Details { pid: Id, id: Id },
+        #[route("/project/:pid/board")] Board { pid: Id },
+        #[route("/view/:view")] View { view: SmartView },
+    #[end_layout]
+    #[route("/report")] Report {},
+    #[route("/stats")] Stats {},
+    #[route("/calendar")] Calendar {},
+    #[route("/settings")] Settings {},
+    #[route("/history")] History {},
+}
+
+impl Route {
+    /// The project a route is scoped to; visiting it makes that project active.
+    pub fn project_id(&self) -> Option<Id> {
+        match self {
+            Route::List { pid } | Route::Board { pid } | Route::Details { pid, .. } => Some(*pid),
+            _ => None,
+        }
+    }
 }
 
 #[component]
 fn App() -> Element {
     // State
     let mut projects = use_signal(Vec::<Project>::new);
-    let mut active_project_id = use_signal(|| Option::<u64>::None);
+    let mut active_project_id = use_signal(|| Option::<Id>::None);
     let new_title = use_signal(String::new);
-    let editing_id = use_signal(|| Option::<u64>::None);
+    let editing_id = use_signal(|| Option::<Id>::None);
     let editing_text = use_signal(String::new);
-    let mut next_id = use_signal(|| 1u64);
-    let filter = use_signal(|| Filter::All);
+    let mut settings = use_signal(settings::load);
+    let filter = use_signal(move || settings.peek().default_filter);
 
     // Provide context for screens
     use_context_provider(|| AppState {
@@ -124,32 +131,141 @@ fn App() -> Element {
         new_title: new_title.clone(),
         editing_id: editing_id.clone(),
         editing_text: editing_text.clone(),
-        next_id: next_id.clone(),
         filter: filter.clone(),
     });
-    // Provide Projects and Header contexts
-    use_context_provider(|| ProjectsState { projects: projects.clone(), active_project_id: active_project_id.clone() });
+    // Provide Projects, Header and notification contexts
+    let projects_state = use_context_provider(|| ProjectsState { projects: projects.clone(), active_project_id: active_project_id.clone() });
     let active_project_snap = use_signal(|| Option::<Project>::None);
-    use_context_provider(|| HeaderState { active_project: active_project_snap.clone() });
+    let mut save_status = use_signal(|| storage::SaveStatus::Saved);
+    use_context_provider(|| HeaderState { active_project: active_project_snap.clone(), save_status });
+    // Mirror the background writer's progress for the header
+    use_future(move || async move {
+        let mut status = storage::save_status();
+        while status.changed().await.is_ok() {
+            let now = status.borrow_and_update().clone();
+            save_status.set(now);
+        }
+    });
+    // Don't lose queued edits when the window closes
+    #[cfg(feature = "desktop")]
+    dioxus::desktop::use_wry_event_handler(|event, _| {
+        use dioxus::desktop::{tao::event::Event, WindowEvent};
+        if let Event::WindowEvent { event: WindowEvent::CloseRequested | WindowEvent::Destroyed, .. } = event {
+            storage::flush();
+        }
+    });
+    let toasts = use_signal(Vec::new);
+    let next_toast_id = use_signal(|| 1u64);
+    let toast_state = use_context_provider(|| ToastState { toasts, next_id: next_toast_id });
+    use_context_provider(|| SettingsState { settings });
+    // Workspace for this run; changing `workspace_dir` reloads the projects below
+    let (start_name, start_dir) = use_hook(move || {
+        let mut prefs = settings.peek().clone();
+        let start = workspace::resolve_start(cli::args(), &mut prefs)
+            .or_else(|e| {
+                warn!("[App] Couldn't resolve the workspace folder: {}", e);
+                workspace::app_data_dir().map(|dir| (workspace::DEFAULT_WORKSPACE.to_string(), dir))
+            })
+            .unwrap_or_else(|e| {
+                // Opened read-only below: storage refuses the empty folder
+                warn!("[App] No app data folder either: {}", e);
+                (workspace::DEFAULT_WORKSPACE.to_string(), PathBuf::new())
+            });
+        if prefs.device_id.is_empty() {
+            prefs.device_id = folder_sync::new_device_id();
+        }
+        crdt::set_device(&prefs.device_id);
+        if prefs != *settings.peek() {
+            settings::save(&prefs);
+            settings.set(prefs);
+        }
+        start
+    });
+    let workspace_name = use_signal(|| start_name);
+    let workspace_dir = use_signal(|| start_dir);
+    use_context_provider(|| WorkspaceState { name: workspace_name, dir: workspace_dir });
+    let read_only = use_signal(|| false);
+    let pending = use_signal(|| None);
+    let engine = use_signal(|| None);
+    let sync_status = use_signal(|| None);
+    let mut sync = use_context_provider(|| SyncState { read_only, pending, engine, status: sync_status });
+    let mut watcher = use_signal(|| Option::<Task>::None);
+
+    // Opened through a `dx-todo://` link: start on that screen
+    use_hook(|| {
+        if let Some(route) = deeplink::from_args() {
+            info!("[App] Opening deep link route {}", route);
+            history().replace(route.to_string());
+        }
+    });
+    // ... and on the screen of links opened while running
+    use_future(|| async {
+        let Some(mut links) = deeplink::incoming() else { return };
+        while let Some(route) = links.recv().await {
+            info!("[App] Opening forwarded link route {}", route);
+            #[cfg(feature = "desktop")]
+            dioxus::desktop::window().set_focus();
+            history().push(route.to_string());
+        }
+    });
+
+    use_effect(move || storage::set_journaled(settings.read().journal));
+    use_effect(move || events::set_hooks(settings.read().hooks.clone()));
 
-    // One-time load from disk after first render
+    // Load from disk after first render and again whenever another workspace is opened
     use_effect(move || {
-        let loaded = load_or_migrate_projects();
-        println!("[App] Loaded {} project(s)", loaded.len());
+        storage::set_data_dir(workspace_dir.read().clone());
+        let git = settings.peek().find_workspace(&workspace_name.peek()).is_some_and(|w| w.git);
+        storage::set_git_mode(git);
+        match storage::lock_data_dir() {
+            Ok(locked) => sync.read_only.set(!locked),
+            Err(e) => {
+                warn!("[App] Couldn't lock the data dir: {}", e);
+                if workspace_dir.peek().as_os_str().is_empty() {
+                    sync.read_only.set(true);
+                    let mut toasts = toast_state;
+                    toasts.error(format!("Couldn't find a folder for your data ({e}); nothing will be saved."));
+                }
+            }
+        }
+        sync.pending.set(None);
+        let mut loaded = load_or_migrate_projects();
+        info!("[App] Loaded {} project(s)", loaded.len());
+        if ensure_inbox(&mut loaded) {
+            info!("[App] Created Inbox project");
+            save_projects(&loaded);
+        }
         if !loaded.is_empty() {
-            // choose first project by default if not selected
-            if active_project_id.read().is_none() {
-                println!("[App] No active project set. Selecting first: id={} name={} ", loaded[0].id, loaded[0].name);
-                active_project_id.set(Some(loaded[0].id));
+            // choose the first regular project unless the active one is still there (peek: no reload on switch)
+            let active = *active_project_id.peek();
+            if !active.is_some_and(|id| loaded.iter().any(|p| p.id == id)) {
+                let first = loaded.iter().find(|p| !p.inbox).unwrap_or(&loaded[0]);
+                info!("[App] No active project set. Selecting first: id={} name={}", first.id, first.name);
+                active_project_id.set(Some(first.id));
             }
-            // compute next id across all todos
-            let max_id_val = loaded
-                .iter()
-                .flat_map(|p| p.todos.iter())
-                .fold(0u64, |acc, t| max(acc, t.id));
-            next_id.set(max_id_val + 1);
         }
+        storage::set_baseline(&loaded);
         projects.set(loaded);
+
+        // Reload when the data files are changed by another instance, a sync tool, an editor or git
+        if let Some(task) = watcher.take() { task.cancel(); }
+        let task = spawn(async move {
+            let paths = match storage::source_paths() {
+                Ok(paths) => paths,
+                Err(e) => { warn!("[App] Not watching for external changes: {}", e); return; }
+            };
+            let (_watcher, mut changes) = match watch::watch_files(&paths) {
+                Ok(w) => w,
+                Err(e) => { warn!("[App] Not watching the data files for external changes: {}", e); return; }
+            };
+            while changes.recv().await.is_some() {
+                // Let a burst of writes settle before reading the file
+                tokio::time::sleep(CHANGE_SETTLE).await;
+                while changes.try_recv().is_ok() {}
+                sync.check_file(projects_state, toast_state);
+            }
+        });
+        watcher.set(Some(task));
     });
     // keep active project snapshot updated for header
     {
@@ -158,87 +274,174 @@ fn App() -> Element {
         let mut active_project_snap = active_project_snap.clone();
         use_effect(move || {
             let opt = active_project_id.read().and_then(|id| projects.read().iter().find(|p| p.id == id).cloned());
-            if let Some(ref p) = opt { println!("[HeaderState] Active project snapshot updated: id={} name={}", p.id, p.name); } else { println!("[HeaderState] Active project snapshot updated: None"); }
+            if let Some(ref p) = opt { debug!("[HeaderState] Active project snapshot updated: id={} name={}", p.id, p.name); } else { debug!("[HeaderState] Active project snapshot updated: None"); }
             active_project_snap.set(opt);
         });
     }
+    // Folder sync for the open workspace: (re)started when its data or sync folder changes
+    let sync_dir = use_memo(move || {
+        let prefs = settings.read();
+        prefs.find_workspace(&workspace_name.read()).and_then(|w| w.sync_dir.clone())
+    });
+    use_effect(move || {
+        let data_dir = workspace_dir.read().clone();
+        let device = settings.peek().device_id.clone();
+        let opened = sync_dir.read().clone().and_then(|dir| {
+            folder_sync::Engine::open(dir, device, &data_dir)
+                .map_err(|e| warn!("[App] Couldn't start folder sync: {}", e))
+                .ok()
+        });
+        sync.status.set(None);
+        sync.engine.set(opened);
+    });
+    use_future(move || async move {
+        loop {
+            tokio::time::sleep(SYNC_INTERVAL).await;
+            sync.sync_folder(projects_state, toast_state);
+        }
+    });
+    // Local API: (re)started when it's turned on or its port changes. Requests are answered here,
+    // against the live projects, so every screen sees API changes right away
+    let api_port = use_memo(move || {
+        let prefs = settings.read();
+        prefs.api_enabled.then_some(prefs.api_port)
+    });
+    let mut api_server = use_signal(|| Option::<api::Server>::None);
+    let mut api_task = use_signal(|| Option::<Task>::None);
+    use_effect(move || {
+        let port = *api_port.read();
+        if let Some(task) = api_task.take() { task.cancel(); }
+        api_server.set(None);
+        let Some(port) = port else { return };
+        let (server, mut calls) = match api::Server::start(port) {
+            Ok(started) => started,
+            Err(e) => {
+                warn!("[App] Couldn't start the API server on port {}: {}", port, e);
+                let mut toasts = toast_state;
+                toasts.error(format!("Couldn't start the API server on port {port}: {e}"));
+                return;
+            }
+        };
+        api_server.set(Some(server));
+        api_task.set(Some(spawn(async move {
+            while let Some(call) = calls.recv().await {
+                let mut list = projects.peek().clone();
+                let token = settings.peek().api_token.clone();
+                let (reply, changed) = api::handle(&mut list, &call.request, &token, storage::is_read_only());
+                if changed {
+                    save_projects(&list);
+                    projects.set(list);
+                }
+                call.answer(reply);
+            }
+        })));
+    });
     rsx! {
         // Inject global assets once so all routes (including Projects) are styled on first load
         document::Link { rel: "icon", href: FAVICON }
         document::Link { rel: "stylesheet", href: MAIN_CSS }
-        Router::<Route> {}
+        div { class: "theme-root", "data-theme": settings.read().theme.attr(),
+            ReadOnlyBanner {}
+            Router::<Route> {}
+            ToastHost {}
+            ConflictDialog {}
+        }
+    }
+}
+
+// Layout for task screens: sidebar plus the routed screen
+#[component]
+fn Shell() -> Element {
+    // The active project follows the route; it only feeds the header and sidebar
+    let mut active = use_context::<ProjectsState>().active_project_id;
+    let pid = use_route::<Route>().project_id();
+    use_effect(use_reactive!(|pid| if pid.is_some() && *active.peek() != pid { active.set(pid) }));
+    // Leaving a screen writes its queued edits right away instead of after the save delay
+    let route = use_route::<Route>();
+    use_effect(use_reactive!(|route| { let _ = route; storage::request_flush(); }));
+    rsx! {
+        div { class: "shell",
+            components::sidebar::Sidebar {}
+            main { class: "shell-main", Outlet::<Route> {} }
+        }
     }
 }
 
 // Home list screen
 #[component]
-fn List() -> Element {
+fn List(pid: Id) -> Element {
     let state = use_context::<AppState>();
     let mut projects = state.projects;
-    let mut active_project_id = state.active_project_id;
     let mut new_title = state.new_title;
     let mut editing_id = state.editing_id;
     let mut editing_text = state.editing_text;
-    let mut next_id = state.next_id;
     let mut filter = state.filter;
+    let mut toasts = use_context::<ToastState>();
     let nav = use_navigator();
 
-    // Guard: require active project
-    let active_id_opt = *active_project_id.read();
-    let active_id = match active_id_opt { Some(id) => id, None => { println!("[List] No active project. Redirecting to Projects."); nav.push(Route::Projects {}); return rsx!{ div { class: "app", div { class: "card", "Select a project" } } }; } };
+    // Guard: the project in the route must exist
+    if !projects.read().iter().any(|p| p.id == pid) {
+        return rsx!{ div { class: "app", div { class: "card", "Project not found" } } };
+    }
 
     // Add todo
     let mut on_add = move |title: String| {
         if title.trim().is_empty() { return; }
-        let id = *next_id.read();
-        next_id.set(id + 1);
-        if let Some(p) = projects.write().iter_mut().find(|p| p.id == active_id) {
-            p.todos.push(Todo { id, title, completed: false, subtasks: Vec::new(), description: String::new() });
+        if let Some(p) = projects.write().iter_mut().find(|p| p.id == pid) {
+            p.todos.push(Todo::new(Id::new(), title));
         }
         save_projects(&projects.read());
     };
 
     // Item handlers
-    let mut toggle = move |id: u64| {
-        if let Some(t) = projects.write().iter_mut().find(|p| p.id == active_id).and_then(|p| p.todos.iter_mut().find(|t| t.id == id)) {
-            let target = !t.completed;
-            t.completed = target;
-            // propagate to subtasks
-            for s in &mut t.subtasks { s.completed = target; }
+    let mut toggle = move |id: Id| {
+        // propagate to the whole subtask tree; completing a task that still waits on others is refused
+        let res = edit_todo(&mut projects.write(), id, |t| t.set_completed_deep(!t.completed));
+        match res {
+            Ok(()) => save_projects(&projects.read()),
+            Err(msg) => { toasts.error(msg); }
         }
-        save_projects(&projects.read());
     };
-    let mut start_edit = move |id: u64, text: String| { editing_id.set(Some(id)); editing_text.set(text); };
+    let mut start_edit = move |id: Id, text: String| { editing_id.set(Some(id)); editing_text.set(text); };
     let mut cancel_edit = move || { editing_id.set(None); editing_text.set(String::new()); };
-    let mut save_edit = move |id: u64| { let text = editing_text.read().clone(); if let Some(t) = projects.write().iter_mut().find(|p| p.id == active_id).and_then(|p| p.todos.iter_mut().find(|t| t.id == id)) { t.title = text.clone(); } save_projects(&projects.read()); editing_id.set(None); editing_text.set(String::new()); };
-    let mut remove_item = move |id: u64| { if let Some(p) = projects.write().iter_mut().find(|p| p.id == active_id) { p.todos.retain(|t| t.id != id); } save_projects(&projects.read()); };
-    let mut clear_completed = move || { if let Some(p) = projects.write().iter_mut().find(|p| p.id == active_id) { p.todos.retain(|t| !t.completed); } save_projects(&projects.read()); };
+    let mut save_edit = move |id: Id| { let text = editing_text.read().clone(); if let Some(t) = projects.write().iter_mut().find(|p| p.id == pid).and_then(|p| p.todos.iter_mut().find(|t| t.id == id)) { t.title = text.clone(); } save_projects(&projects.read()); editing_id.set(None); editing_text.set(String::new()); };
+    let mut remove_item = move |id: Id| { if let Some(p) = projects.write().iter_mut().find(|p| p.id == pid) { p.todos.retain(|t| t.id != id); } deps::forget(&mut projects.write(), &[id]); save_projects(&projects.read()); };
+    let mut clear_completed = move || {
+        let mut removed = Vec::new();
+        if let Some(p) = projects.write().iter_mut().find(|p| p.id == pid) {
+            removed = p.todos.iter().filter(|t| t.completed).map(|t| t.id).collect();
+            p.todos.retain(|t| !t.completed);
+        }
+        deps::forget(&mut projects.write(), &removed);
+        save_projects(&projects.read());
+    };
     let mut confirming_clear = use_signal(|| false);
+    let mut confirming_remove = use_signal(|| Option::<Id>::None);
+    let settings = use_context::<SettingsState>().settings;
+    let confirm_delete = settings.read().confirm_delete;
 
     // Drag & drop reordering state and handlers
-    let mut dragging_from = use_signal(|| Option::<u64>::None);
-    let mut drag_over = use_signal(|| Option::<u64>::None);
-    let mut on_drag_start_item = move |id: u64| { dragging_from.set(Some(id)); };
-    let mut on_drag_over_item = move |id: u64| { drag_over.set(Some(id)); };
-    let mut on_drag_leave_item = move |id: u64| {
+    let mut dragging_from = use_signal(|| Option::<Id>::None);
+    let mut drag_over = use_signal(|| Option::<Id>::None);
+    let mut on_drag_start_item = move |id: Id| { dragging_from.set(Some(id)); };
+    let mut on_drag_over_item = move |id: Id| { drag_over.set(Some(id)); };
+    let mut on_drag_leave_item = move |id: Id| {
         if drag_over.read().as_ref() == Some(id).as_ref() { drag_over.set(None); }
     };
-    let mut on_drag_end_item = move |_id: u64| {
+    let mut on_drag_end_item = move |_id: Id| {
         dragging_from.set(None);
         drag_over.set(None);
     };
-    let mut on_drop_on_item = move |target_id: u64| {
+    let mut on_drop_on_item = move |target_id: Id| {
         let src_opt = *dragging_from.read();
         if let Some(src_id) = src_opt {
             if src_id == target_id { dragging_from.set(None); return; }
-            if let Some(p) = projects.write().iter_mut().find(|p| p.id == active_id) {
+            if let Some(p) = projects.write().iter_mut().find(|p| p.id == pid) {
                 if let (Some(src_idx), Some(dst_idx)) = (
                     p.todos.iter().position(|t| t.id == src_id),
                     p.todos.iter().position(|t| t.id == target_id),
                 ) {
-                    let item = p.todos.remove(src_idx);
-                    let insert_idx = if src_idx < dst_idx { dst_idx - 1 } else { dst_idx };
-                    p.todos.insert(insert_idx, item);
+                    move_before(&mut p.todos, src_idx, dst_idx);
                 }
             }
             dragging_from.set(None);
@@ -254,15 +457,15 @@ fn List() -> Element {
             div { class: "card",
                 // header actions
                 Header { 
-                    count: projects.read().iter().find(|p| p.id == active_id).map(|p| p.todos.len()).unwrap_or(0),
-                    on_switch: move |_| { println!("[Header] Switch clicked"); nav.push(Route::Projects {}); },
-                    on_export: move |_| {
-                        println!("[Header] Export clicked");
-                        let res = export_active_project_pdf(&projects.read(), *active_project_id.read());
-                        match res {
-                            Ok(()) => println!("[Export] Success"),
-                            Err(e) => println!("[Export] Error: {}", e),
-                        }
+                    count: projects.read().iter().find(|p| p.id == pid).map(|p| p.todos.len()).unwrap_or(0),
+                    on_stats: move |_| { nav.push(Route::Stats {}); },
+                    on_board: move |_| { nav.push(Route::Board { pid }); },
+                    on_calendar: move |_| { nav.push(Route::Calendar {}); },
+                    on_export: move |format: export::ExportFormat| {
+                        debug!("[Header] Export clicked: {}", format.label());
+                        let prefs = settings.read().clone();
+                        let res = export::export_active_project(&projects.read(), Some(pid), format, prefs.export_dir.as_deref());
+                        export::notify(&mut toasts, res, prefs.open_after_export);
                     }
                 }
                 AddForm { value: new_title.read().clone(),
@@ -278,19 +481,28 @@ fn List() -> Element {
                         new_title.set(String::new());
                     }
                 }
-                FilterBar { active: *filter.read(), on_all: move |_| filter.set(Filter::All), on_active: move |_| filter.set(Filter::Active), on_completed: move |_| filter.set(Filter::Completed), on_clear_completed: move |_| confirming_clear.set(true) }
+                FilterBar { active: *filter.read(), on_all: move |_| filter.set(Filter::All), on_active: move |_| filter.set(Filter::Active), on_completed: move |_| filter.set(Filter::Completed), on_blocked: move |_| filter.set(Filter::Blocked), on_actionable: move |_| filter.set(Filter::Actionable), on_clear_completed: move |_| if confirm_delete { confirming_clear.set(true) } else { clear_completed() } }
                 ul { class: "list",
                     {
-                        let items: Vec<Todo> = projects.read().iter().find(|p| p.id == active_id).map(|p| p.todos.clone()).unwrap_or_else(|| Vec::new());
+                        // Pair each visible task with the titles of its open blockers
+                        let items: Vec<(Todo, Vec<String>)> = {
+                            let list = projects.read();
+                            list.iter().find(|p| p.id == pid).map(|p| p.todos.iter()
+                                .map(|t| (t.clone(), deps::open_blockers(&list, t).iter().map(|b| b.title.clone()).collect::<Vec<_>>()))
+                                .filter(|(t, blockers)| filter.read().matches(t, !blockers.is_empty()))
+                                .collect()).unwrap_or_else(|| Vec::new())
+                        };
                         rsx! {
-                            for t in items.into_iter().filter(|t| match *filter.read() { Filter::All => true, Filter::Active => !t.completed, Filter::Completed => t.completed }) {
+                            for (t, blockers) in items.into_iter() {
                         TodoItem {
                             todo: t.clone(),
+                            pid,
+                            blockers,
                             is_editing: editing_id.read().as_ref().is_some_and(|eid| *eid == t.id),
                             editing_text: editing_text.read().clone(),
                             on_toggle: move |_| toggle(t.id),
                             on_start_edit: move |_| start_edit(t.id, t.title.clone()),
-                            on_remove: move |_| remove_item(t.id),
+                            on_remove: move |_| if confirm_delete { confirming_remove.set(Some(t.id)) } else { remove_item(t.id) },
                             on_save_click: move |_| save_edit(t.id),
                             on_save_key: move |e: dioxus::events::KeyboardEvent| { if e.key() == Key::Enter { save_edit(t.id); } },
                             on_edit_input: move |e: dioxus::events::FormEvent| editing_text.set(e.value()),
@@ -308,16 +520,15 @@ fn List() -> Element {
                     }
                 }
                 if *confirming_clear.read() {
-                    // modal overlay captures keys; Escape closes
-                    div { class: "modal-overlay", tabindex: 0, onkeydown: move |e: dioxus::events::KeyboardEvent| if e.key() == Key::Escape { confirming_clear.set(false) },
-                        div { class: "modal",
-                            h3 { class: "title", "Clear completed tasks?" }
-                            p { class: "meta", "This cannot be undone." }
-                            div { class: "actions",
-                                button { class: "btn btn-danger", autofocus: "true", onclick: move |_| { clear_completed(); confirming_clear.set(false); }, "Confirm" }
-                                button { class: "btn btn-ghost", onclick: move |_| { confirming_clear.set(false); }, "Cancel" }
-                            }
-                        }
+                    ConfirmDialog { title: "Clear completed tasks?", message: "This cannot be undone.",
+                        on_confirm: move |_| { clear_completed(); confirming_clear.set(false); },
+                        on_cancel: move |_| confirming_clear.set(false),
+                    }
+                }
+                if let Some(rid) = *confirming_remove.read() {
+                    ConfirmDialog { title: "Remove this task?", message: "Its subtasks are removed too. This cannot be undone.",
+                        on_confirm: move |_| { remove_item(rid); confirming_remove.set(None); },
+                        on_cancel: move |_| confirming_remove.set(None),
                     }
                 }
             }
@@ -325,7 +536,7 @@ fn List() -> Element {
     }
 }
 
-// Projects screen renders list/create projects and sets active_project_id
+// Projects screen renders list/create projects
 #[component]
 fn Projects() -> Element {
     let state = use_context::<AppState>();
@@ -335,57 +546,210 @@ fn Projects() -> Element {
     rsx! { components::projects::Projects {} }
 }
 
+// Progress report over a date range, across all projects
+#[component]
+fn Report() -> Element {
+    rsx! { components::report::Report {} }
+}
+
+// Statistics dashboard across all projects
+#[component]
+fn Stats() -> Element {
+    rsx! { components::stats::Stats {} }
+}
+
+#[component]
+fn Board(pid: Id) -> Element {
+    rsx! { components::board::Board { pid } }
+}
+
+#[component]
+fn Calendar() -> Element {
+    rsx! { components::calendar::Calendar {} }
+}
+
+#[component]
+fn Settings() -> Element {
+    rsx! { components::settings::Settings {} }
+}
+
+#[component]
+fn History() -> Element {
+    rsx! { components::history::History {} }
+}
+
+// Inbox/Today/Upcoming/Completed recently, across all projects
+#[component]
+fn View(view: SmartView) -> Element {
+    rsx! { components::smart_view::SmartList { view } }
+}
+
 // Details screen
 #[component]
-fn Details(id: u64) -> Element {
+fn Details(pid: Id, id: Id) -> Element {
     let state = use_context::<AppState>();
     let mut projects = state.projects;
-    let active_project_id = state.active_project_id;
+    let mut toasts = use_context::<ToastState>();
     let nav = use_navigator();
 
-    // Guard: require active project
-    let active_id = match *active_project_id.read() { Some(id) => id, None => { nav.push(Route::Projects {}); return rsx!{ div { class: "app", div { class: "card", "Select a project" } } }; } };
-
-    let todo_opt = projects.read().iter().find(|p| p.id == active_id).and_then(|p| p.todos.iter().cloned().find(|t| t.id == id));
+    let todo_opt = projects.read().iter().find(|p| p.id == pid).and_then(|p| p.todos.iter().cloned().find(|t| t.id == id));
     let Some(todo) = todo_opt else { return rsx!{ div { class: "app", div { class: "card", "Not found" } } }; };
 
-    // Subtasks handlers
-    let mut add_sub = move |title: String| {
+    // Subtask tree handlers; completion rules live on `Todo`
+    let mut add_sub = move |parent: Option<Id>, title: String| {
         if title.trim().is_empty() { return; }
-        if let Some(it) = projects.write().iter_mut().find(|p| p.id == active_id).and_then(|p| p.todos.iter_mut().find(|t| t.id == id)) {
-            let next_sid = it.subtasks.iter().map(|s| s.id).max().unwrap_or(0) + 1;
-            it.subtasks.push(Subtask { id: next_sid, title, completed: false });
-            // New subtask means parent can't be completed
-            it.completed = false;
+        if let Some(it) = projects.write().iter_mut().find(|p| p.id == pid).and_then(|p| p.todos.iter_mut().find(|t| t.id == id)) {
+            it.add_subtask(parent, title);
         }
         save_projects(&projects.read());
     };
-    let mut toggle_sub = move |sid: u64| {
-        if let Some(it) = projects.write().iter_mut().find(|p| p.id == active_id).and_then(|p| p.todos.iter_mut().find(|t| t.id == id)) {
-            if let Some(st) = it.subtasks.iter_mut().find(|s| s.id == sid) {
-                st.completed = !st.completed;
-            }
-            // If there is at least one subtask and all are completed, mark parent done
-            if !it.subtasks.is_empty() {
-                it.completed = it.subtasks.iter().all(|s| s.completed);
-            }
+    // Both can complete the task by finishing its last open subtask, which a blocked task refuses
+    let toggle_sub = move |sid: Id| {
+        let res = edit_todo(&mut projects.write(), id, |it| it.toggle_subtask(sid));
+        match res {
+            Ok(()) => save_projects(&projects.read()),
+            Err(msg) => { toasts.error(msg); }
+        }
+    };
+    let remove_sub = move |sid: Id| {
+        let res = edit_todo(&mut projects.write(), id, |it| { it.remove_subtask(sid); });
+        match res {
+            Ok(()) => save_projects(&projects.read()),
+            Err(msg) => { toasts.error(msg); }
+        }
+    };
+    let rename_sub = move |(sid, title): (Id, String)| {
+        if let Some(st) = projects.write().iter_mut().find(|p| p.id == pid).and_then(|p| p.todos.iter_mut().find(|t| t.id == id)).and_then(|t| t.find_subtask_mut(sid)) {
+            st.title = title;
         }
         save_projects(&projects.read());
     };
-    let mut remove_sub = move |sid: u64| {
-        if let Some(it) = projects.write().iter_mut().find(|p| p.id == active_id).and_then(|p| p.todos.iter_mut().find(|t| t.id == id)) {
-            it.subtasks.retain(|s| s.id != sid);
-            if !it.subtasks.is_empty() {
-                it.completed = it.subtasks.iter().all(|s| s.completed);
-            } else {
-                // No subtasks: do not auto-complete; leave as-is
+    // Promote: the subtask (with its children) becomes a task right after this one
+    // (taking out the last open subtask completes this task, so it goes through the blocked check)
+    let promote_sub = move |sid: Id| {
+        let res = edit_project_of(&mut projects.write(), id, |p| {
+            let idx = p.todos.iter().position(|t| t.id == id)?;
+            let node = p.todos[idx].remove_subtask(sid)?;
+            p.todos.insert(idx + 1, Todo::from_subtask(Id::new(), node));
+            Some(())
+        });
+        match res {
+            Ok(Some(())) => save_projects(&projects.read()),
+            Ok(None) => {}
+            Err(msg) => { toasts.error(msg); }
+        }
+    };
+    // Demote: this task (with its subtasks) moves under another task of the project
+    let mut demote_to = move |target_id: Id| {
+        if target_id == id { return; }
+        // A done task joining a target whose other subtasks are done completes the target
+        let res = edit_project_of(&mut projects.write(), target_id, |p| {
+            let idx = p.todos.iter().position(|t| t.id == id)?;
+            let todo = p.todos.remove(idx);
+            p.todos.iter_mut().find(|t| t.id == target_id)?.adopt(todo);
+            Some(())
+        });
+        let moved = match res {
+            Ok(moved) => moved.is_some(),
+            Err(msg) => { toasts.error(msg); false }
+        };
+        if moved {
+            // The task is now a subtask and can no longer block anything
+            deps::forget(&mut projects.write(), &[id]);
+            save_projects(&projects.read());
+            nav.replace(Route::Details { pid, id: target_id });
+        }
+    };
+
+    // Drag & drop reordering of subtasks, mirroring the task list
+    let mut sub_dragging = use_signal(|| Option::<Id>::None);
+    let mut sub_drag_over = use_signal(|| Option::<Id>::None);
+    let on_sub_drop = move |target_sid: Id| {
+        let src_opt = *sub_dragging.read();
+        if let Some(src_sid) = src_opt {
+            sub_dragging.set(None);
+            sub_drag_over.set(None);
+            if src_sid == target_sid { return; }
+            if let Some(it) = projects.write().iter_mut().find(|p| p.id == pid).and_then(|p| p.todos.iter_mut().find(|t| t.id == id)) {
+                it.move_subtask_before(src_sid, target_sid);
             }
+            save_projects(&projects.read());
+        }
+    };
+
+    let mut update_sub_desc = move |sid: Id, v: String| {
+        if let Some(st) = projects.write().iter_mut().find(|p| p.id == pid).and_then(|p| p.todos.iter_mut().find(|t| t.id == id)).and_then(|t| t.find_subtask_mut(sid)) {
+            st.description = v;
+        }
+        save_projects(&projects.read());
+    };
+    let mut update_due = move |v: String| {
+        if let Some(it) = projects.write().iter_mut().find(|p| p.id == pid).and_then(|p| p.todos.iter_mut().find(|t| t.id == id)) {
+            // An empty date input clears the due date
+            it.due = NaiveDate::parse_from_str(&v, "%Y-%m-%d").ok();
+        }
+        save_projects(&projects.read());
+    };
+    let mut update_desc = move |v: String| { if let Some(it) = projects.write().iter_mut().find(|p| p.id == pid).and_then(|p| p.todos.iter_mut().find(|t| t.id == id)) { it.description = v; } save_projects(&projects.read()); };
+
+    // Dependencies; the blocker may live in any project
+    let mut add_blocker = move |bid: Id| {
+        if deps::would_cycle(&projects.read(), id, bid) {
+            toasts.error("That task already waits on this one; adding it would create a cycle.");
+            return;
+        }
+        if let Some(it) = projects.write().iter_mut().find(|p| p.id == pid).and_then(|p| p.todos.iter_mut().find(|t| t.id == id))
+            && !it.blocked_by.contains(&bid)
+        {
+            it.blocked_by.push(bid);
+        }
+        save_projects(&projects.read());
+    };
+    let mut remove_blocker = move |bid: Id| {
+        if let Some(it) = projects.write().iter_mut().find(|p| p.id == pid).and_then(|p| p.todos.iter_mut().find(|t| t.id == id)) {
+            it.blocked_by.retain(|b| *b != bid);
         }
         save_projects(&projects.read());
     };
-    let mut update_desc = move |v: String| { if let Some(it) = projects.write().iter_mut().find(|p| p.id == active_id).and_then(|p| p.todos.iter_mut().find(|t| t.id == id)) { it.description = v; } save_projects(&projects.read()); };
+    // (project id, todo id, project name, title, completed); stale ids are skipped
+    let blockers: Vec<(Id, Id, String, String, bool)> = {
+        let list = projects.read();
+        todo.blocked_by.iter()
+            .filter_map(|bid| find_todo(&list, *bid))
+            .map(|(p, t)| (p.id, t.id, p.name.clone(), t.title.clone(), t.completed))
+            .collect()
+    };
+    // Open tasks in any project that can become a blocker without closing a loop
+    let blocker_candidates: Vec<(Id, String)> = {
+        let list = projects.read();
+        list.iter()
+            .flat_map(|p| p.todos.iter().map(move |t| (p, t)))
+            .filter(|(_, t)| t.id != id && !t.completed && !todo.blocked_by.contains(&t.id) && !deps::would_cycle(&list, id, t.id))
+            .map(|(p, t)| (t.id, format!("{} · {}", p.name, t.title)))
+            .collect()
+    };
+    let mut blocker_pick = use_signal(|| Option::<Id>::None);
+    let open_blockers = blockers.iter().filter(|b| !b.4).count();
 
     let mut sub_input = use_signal(String::new);
+    let sub_actions = SubtaskActions {
+        on_toggle: EventHandler::new(toggle_sub),
+        on_remove: EventHandler::new(remove_sub),
+        on_add_child: EventHandler::new(move |(pid, title): (Id, String)| add_sub(Some(pid), title)),
+        on_update_desc: EventHandler::new(move |(sid, v): (Id, String)| update_sub_desc(sid, v)),
+        on_rename: EventHandler::new(rename_sub),
+        on_promote: EventHandler::new(promote_sub),
+        on_drag_start: EventHandler::new(move |sid: Id| sub_dragging.set(Some(sid))),
+        on_drag_over: EventHandler::new(move |sid: Id| sub_drag_over.set(Some(sid))),
+        on_drag_leave: EventHandler::new(move |sid: Id| if *sub_drag_over.read() == Some(sid) { sub_drag_over.set(None) }),
+        on_drag_end: EventHandler::new(move |_| { sub_dragging.set(None); sub_drag_over.set(None); }),
+        on_drop: EventHandler::new(on_sub_drop),
+    };
+    // Other tasks of this project that can receive this one as a subtask
+    let demote_targets: Vec<(Id, String)> = projects.read().iter().find(|p| p.id == pid)
+        .map(|p| p.todos.iter().filter(|t| t.id != id).map(|t| (t.id, t.title.clone())).collect())
+        .unwrap_or_default();
+    let mut demote_pick = use_signal(|| Option::<Id>::None);
 
     rsx! {
         document::Link { rel: "icon", href: FAVICON }
@@ -393,22 +757,95 @@ fn Details(id: u64) -> Element {
         div { class: "app",
             div { class: "card",
                 div { class: "row between", style: "margin-bottom:12px;",
-                    button { class: "btn btn-ghost", onclick: move |_| { nav.push(Route::List {}); }, "← Back" }
+                    div { class: "actions",
+                        button { class: "btn btn-ghost", onclick: move |_| { nav.push(Route::List { pid }); }, "← Back" }
+                        button { class: "btn btn-ghost", title: "Copy a dx-todo:// link to this task",
+                            onclick: move |_| match logging::copy_to_clipboard(deeplink::link(&Route::Details { pid, id })) {
+                                Ok(()) => { toasts.info("Link copied to clipboard"); }
+                                Err(e) => { toasts.error(format!("Couldn't copy link: {e}")); }
+                            },
+                            "Copy link"
+                        }
+                    }
+                    if !demote_targets.is_empty() {
+                        div { class: "actions demote",
+                            select { class: "text", aria_label: "Demote to a subtask of",
+                                onchange: move |e| demote_pick.set(e.value().parse().ok()),
+                                option { value: "", selected: demote_pick.read().is_none(), "Make subtask of…" }
+                                for (tid, ttitle) in demote_targets.iter().cloned() {
+                                    option { value: "{tid}", selected: *demote_pick.read() == Some(tid), "{ttitle}" }
+                                }
+                            }
+                            button { class: "btn btn-ghost", disabled: demote_pick.read().is_none(),
+                                onclick: move |_| { let pick = *demote_pick.read(); if let Some(t) = pick { demote_to(t); } },
+                                "Demote to subtask"
+                            }
+                        }
+                    }
                 }
                 h2 { class: "title", "{todo.title}" }
+                div { class: "row due-row",
+                    label { class: "meta", r#for: "due-input", "Due" }
+                    input { id: "due-input", class: "text due-input", r#type: "date", value: todo.due.map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or_default(), onchange: move |e| update_due(e.value()) }
+                }
                 textarea { class: "text desc", rows: "3", placeholder: "Description...", value: "{todo.description}", oninput: move |e| update_desc(e.value()) }
-                h3 { style: "margin-top:16px;", "Subtasks" }
+                div { class: "row sub-header",
+                    h3 { "Blocked by" }
+                    if open_blockers > 0 {
+                        span { class: "blocked-badge", "{open_blockers} open" }
+                    }
+                }
+                ul { class: "deps",
+                    for (bpid, bid, pname, btitle, bdone) in blockers.iter().cloned() {
+                        li { key: "dep-{bid}", class: "dep-item",
+                            input { r#type: "checkbox", checked: bdone, disabled: true }
+                            button { class: "btn btn-link", title: "Open task",
+                                onclick: move |_| { nav.push(Route::Details { pid: bpid, id: bid }); },
+                                "{btitle}"
+                            }
+                            span { class: "meta", "{pname}" }
+                            button { class: "btn btn-ghost sub-remove", title: "Remove dependency", onclick: move |_| remove_blocker(bid), "✕" }
+                        }
+                    }
+                    if blockers.is_empty() {
+                        li { class: "meta", "Not waiting on anything." }
+                    }
+                }
+                if !blocker_candidates.is_empty() {
+                    div { class: "dep-add",
+                        select { class: "text", aria_label: "Add a blocking task",
+                            onchange: move |e| blocker_pick.set(e.value().parse().ok()),
+                            option { value: "", selected: blocker_pick.read().is_none(), "Add a blocking task…" }
+                            for (cid, label) in blocker_candidates.iter().cloned() {
+                                option { value: "{cid}", selected: *blocker_pick.read() == Some(cid), "{label}" }
+                            }
+                        }
+                        button { class: "btn btn-ghost", disabled: blocker_pick.read().is_none(),
+                            onclick: move |_| { let pick = *blocker_pick.read(); if let Some(b) = pick { add_blocker(b); blocker_pick.set(None); } },
+                            "Add"
+                        }
+                    }
+                }
+                div { class: "row sub-header",
+                    h3 { "Subtasks" }
+                    if !todo.subtasks.is_empty() {
+                        {
+                            let (done, total) = todo.subtask_progress();
+                            rsx! {
+                                ProgressBar { done, total }
+                                span { class: "meta", "{done}/{total}" }
+                            }
+                        }
+                    }
+                }
                 ul { class: "subtasks",
                     for st in todo.subtasks.clone().into_iter() {
-                        li { key: "sub-{st.id}", class: "sub-item",
-                            input { r#type: "checkbox", checked: st.completed, onclick: move |_| toggle_sub(st.id) }
-                            span { class: if st.completed { "sub-title completed" } else { "sub-title" }, "{st.title}" }
-                            button { class: "btn btn-ghost sub-remove", onclick: move |_| remove_sub(st.id), "✕" }
-                        }
+                        SubtaskNode { key: "sub-{st.id}", node: st, actions: sub_actions,
+                            dragging: *sub_dragging.read(), drag_over: *sub_drag_over.read() }
                     }
                     li { class: "sub-add",
-                        input { class: "text sub-input", r#type: "text", placeholder: "Add a subtask…", value: "{sub_input.read()}", oninput: move |e| sub_input.set(e.value()), onkeydown: move |e| { if e.key() == Key::Enter { let v = sub_input.read().trim().to_string(); if !v.is_empty() { add_sub(v); sub_input.set(String::new()); } } } }
-                        button { class: "btn btn-primary sub-add-btn", onclick: move |_| { let v = sub_input.read().trim().to_string(); if !v.is_empty() { add_sub(v); sub_input.set(String::new()); } }, "Add" }
+                        input { class: "text sub-input", r#type: "text", placeholder: "Add a subtask…", value: "{sub_input.read()}", oninput: move |e| sub_input.set(e.value()), onkeydown: move |e| { if e.key() == Key::Enter { let v = sub_input.read().trim().to_string(); if !v.is_empty() { add_sub(None, v); sub_input.set(String::new()); } } } }
+                        button { class: "btn btn-primary sub-add-btn", onclick: move |_| { let v = sub_input.read().trim().to_string(); if !v.is_empty() { add_sub(None, v); sub_input.set(String::new()); } }, "Add" }
                     }
                 }
             }
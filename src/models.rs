@@ -1,34 +1,512 @@
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::crdt::{Stamps, Tombstones};
+use crate::deps;
+use crate::id::Id;
+
 #[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub struct Todo {
-    pub id: u64,
+    pub id: Id,
     pub title: String,
     pub completed: bool,
     #[serde(default)]
     pub subtasks: Vec<Subtask>,
     #[serde(default)]
     pub description: String,
+    // Timestamps are absent on data saved before they were tracked
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub completed_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub due: Option<NaiveDate>,
+    // Ids of todos (in any project) that must be completed first
+    #[serde(default)]
+    pub blocked_by: Vec<Id>,
+    // Board column name; see `Project::column_of` for how it combines with `completed`
+    #[serde(default)]
+    pub status: String,
+    // Merge bookkeeping, kept up to date when saving; see `crdt`
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub rank: String,
+    #[serde(default, skip_serializing_if = "Stamps::is_empty")]
+    pub stamps: Stamps,
+    // Removed subtasks
+    #[serde(default, skip_serializing_if = "Tombstones::is_empty")]
+    pub removed: Tombstones,
 }
 
+/// A node in a todo's subtask tree.
 #[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub struct Subtask {
-    pub id: u64,
+    pub id: Id,
     pub title: String,
     pub completed: bool,
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub completed_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub children: Vec<Subtask>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub rank: String,
+    #[serde(default, skip_serializing_if = "Stamps::is_empty")]
+    pub stamps: Stamps,
 }
 
 #[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub struct Project {
-    pub id: u64,
+    pub id: Id,
     pub name: String,
     #[serde(default)]
     pub todos: Vec<Todo>,
+    // Board columns, left to right; the last one means "done"
+    #[serde(default = "default_columns")]
+    pub columns: Vec<String>,
+    // The quick-capture project behind the Inbox view; see `ensure_inbox`
+    #[serde(default)]
+    pub inbox: bool,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub rank: String,
+    #[serde(default, skip_serializing_if = "Stamps::is_empty")]
+    pub stamps: Stamps,
+    // Removed todos
+    #[serde(default, skip_serializing_if = "Tombstones::is_empty")]
+    pub removed: Tombstones,
+}
+
+pub const DEFAULT_COLUMNS: [&str; 4] = ["Backlog", "In Progress", "Review", "Done"];
+
+fn default_columns() -> Vec<String> {
+    DEFAULT_COLUMNS.iter().map(|c| c.to_string()).collect()
+}
+
+impl Project {
+    pub fn new(id: Id, name: String) -> Self {
+        Self {
+            id,
+            name,
+            todos: Vec::new(),
+            columns: default_columns(),
+            inbox: false,
+            rank: String::new(),
+            stamps: Stamps::new(),
+            removed: Tombstones::new(),
+        }
+    }
+
+    fn last_column(&self) -> usize {
+        self.columns.len().saturating_sub(1)
+    }
+
+    /// Board column of `todo`. Completion wins, so the list checkbox and the board stay in step:
+    /// completed tasks sit in the last column, open ones in their status column (or the first).
+    pub fn column_of(&self, todo: &Todo) -> usize {
+        let last = self.last_column();
+        if todo.completed { return last; }
+        self.columns[..last].iter().position(|c| *c == todo.status).unwrap_or(0)
+    }
+
+    /// Move a task into column `col`, before `before` if given (else to the end of the list).
+    pub fn move_to_column(&mut self, todo_id: Id, col: usize, before: Option<Id>) -> bool {
+        let Some(name) = self.columns.get(col).cloned() else { return false };
+        let done = col == self.last_column();
+        let Some(src) = self.todos.iter().position(|t| t.id == todo_id) else { return false };
+        let t = &mut self.todos[src];
+        t.status = name;
+        if t.completed != done { t.set_completed_deep(done); }
+        match before.and_then(|b| self.todos.iter().position(|t| t.id == b)) {
+            Some(dst) if dst != src => move_before(&mut self.todos, src, dst),
+            Some(_) => {}
+            None => { let t = self.todos.remove(src); self.todos.push(t); }
+        }
+        true
+    }
+
+    // Column edits go through these two. They only move cards: completion is never changed, so done
+    // tasks follow whichever column ends up last and open ones stay out of it
+    fn stamp_statuses(&mut self) {
+        let cols: Vec<usize> = self.todos.iter().map(|t| self.column_of(t)).collect();
+        for (t, c) in self.todos.iter_mut().zip(cols) {
+            t.status = self.columns[c].clone();
+        }
+    }
+
+    fn apply_columns(&mut self) {
+        let last = self.last_column();
+        for t in self.todos.iter_mut() {
+            let col = if t.completed {
+                last
+            } else {
+                // An open column that moved into last place keeps its open cards just left of it
+                self.columns.iter().position(|c| *c == t.status).unwrap_or(0).min(last.saturating_sub(1))
+            };
+            t.status = self.columns[col].clone();
+        }
+    }
+
+    /// Add a column just before the last one, so "done" stays last. Names must be unique.
+    pub fn add_column(&mut self, name: String) -> bool {
+        if name.is_empty() || self.columns.contains(&name) { return false; }
+        self.stamp_statuses();
+        let at = self.last_column();
+        self.columns.insert(at, name);
+        self.apply_columns();
+        true
+    }
+
+    pub fn rename_column(&mut self, col: usize, name: String) -> bool {
+        if name.is_empty() || self.columns.contains(&name) || col >= self.columns.len() { return false; }
+        self.stamp_statuses();
+        let old = std::mem::replace(&mut self.columns[col], name.clone());
+        for t in self.todos.iter_mut().filter(|t| t.status == old) {
+            t.status = name.clone();
+        }
+        true
+    }
+
+    /// Remove a column; its tasks move to the column on its left (or the new first column).
+    pub fn remove_column(&mut self, col: usize) -> bool {
+        // Keep at least one open column and the done column
+        if self.columns.len() <= 2 || col >= self.columns.len() { return false; }
+        self.stamp_statuses();
+        let old = self.columns.remove(col);
+        let target = self.columns[col.saturating_sub(1)].clone();
+        for t in self.todos.iter_mut().filter(|t| t.status == old) {
+            t.status = target.clone();
+        }
+        self.apply_columns();
+        true
+    }
+
+    /// Swap column `col` with its neighbour (`left` or right).
+    pub fn shift_column(&mut self, col: usize, left: bool) -> bool {
+        let other = if left { col.checked_sub(1) } else { Some(col + 1) };
+        let Some(other) = other.filter(|o| *o < self.columns.len()) else { return false };
+        self.stamp_statuses();
+        self.columns.swap(col, other);
+        self.apply_columns();
+        true
+    }
 }
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum Filter {
     All,
     Active,
     Completed,
+    // Open and waiting on an unfinished dependency
+    Blocked,
+    // Open and free to start
+    Actionable,
+}
+
+impl Filter {
+    pub fn matches(self, todo: &Todo, blocked: bool) -> bool {
+        match self {
+            Filter::All => true,
+            Filter::Active => !todo.completed,
+            Filter::Completed => todo.completed,
+            Filter::Blocked => !todo.completed && blocked,
+            Filter::Actionable => !todo.completed && !blocked,
+        }
+    }
+}
+
+impl Todo {
+    pub fn new(id: Id, title: String) -> Self {
+        Self {
+            id,
+            title,
+            completed: false,
+            subtasks: Vec::new(),
+            description: String::new(),
+            created_at: Some(Utc::now()),
+            completed_at: None,
+            due: None,
+            blocked_by: Vec::new(),
+            status: String::new(),
+            rank: String::new(),
+            stamps: Stamps::new(),
+            removed: Tombstones::new(),
+        }
+    }
+
+    // Keeps `completed_at` in step with `completed`
+    pub fn set_completed(&mut self, done: bool) {
+        if done && !self.completed {
+            self.completed_at = Some(Utc::now());
+        } else if !done {
+            self.completed_at = None;
+        }
+        self.completed = done;
+    }
+
+    /// Complete or reopen the todo together with its whole subtask tree.
+    pub fn set_completed_deep(&mut self, done: bool) {
+        self.set_completed(done);
+        for s in &mut self.subtasks { s.set_completed_deep(done); }
+    }
+
+    /// Completed and total nodes across the whole subtask tree.
+    pub fn subtask_progress(&self) -> (usize, usize) {
+        self.subtasks.iter().fold((0, 0), |(d, t), s| {
+            let (sd, st) = s.progress();
+            (d + sd, t + st)
+        })
+    }
+
+    pub fn find_subtask_mut(&mut self, id: Id) -> Option<&mut Subtask> {
+        find_in_mut(&mut self.subtasks, id)
+    }
+
+    /// Add a subtask under `parent` (or at the top level); its new open child reopens the ancestors.
+    pub fn add_subtask(&mut self, parent: Option<Id>, title: String) -> Option<Id> {
+        let id = Id::new();
+        let node = Subtask::new(id, title);
+        match parent {
+            None => self.subtasks.push(node),
+            Some(pid) => self.find_subtask_mut(pid)?.children.push(node),
+        }
+        self.sync_completion();
+        Some(id)
+    }
+
+    /// Toggle a subtask: descendants follow it, ancestors and the todo are re-derived from their children.
+    pub fn toggle_subtask(&mut self, id: Id) {
+        if let Some(st) = self.find_subtask_mut(id) {
+            let target = !st.completed;
+            st.set_completed_deep(target);
+        }
+        self.sync_completion();
+    }
+
+    /// Move subtask `src` just before `target`, possibly into another branch.
+    /// Refused when `target` lies inside `src`'s own subtree.
+    pub fn move_subtask_before(&mut self, src: Id, target: Id) -> bool {
+        if src == target { return false; }
+        match find_in(&self.subtasks, src) {
+            // The target must survive the removal, so it has to exist outside the moved subtree
+            Some(node) if find_in(&node.children, target).is_none() && find_in(&self.subtasks, target).is_some() => {}
+            _ => return false,
+        }
+        let Some(node) = remove_in(&mut self.subtasks, src) else { return false };
+        let Some(list) = list_containing_mut(&mut self.subtasks, target) else { return false };
+        let dst_idx = list.iter().position(|s| s.id == target).unwrap_or(list.len());
+        list.insert(dst_idx, node);
+        self.sync_completion();
+        true
+    }
+
+    /// A subtask promoted to a top-level todo; its children become the new todo's subtasks.
+    pub fn from_subtask(id: Id, s: Subtask) -> Self {
+        Self {
+            id,
+            title: s.title,
+            completed: s.completed,
+            subtasks: s.children,
+            description: s.description,
+            created_at: s.created_at,
+            completed_at: s.completed_at,
+            due: None,
+            blocked_by: Vec::new(),
+            status: String::new(),
+            rank: String::new(),
+            stamps: Stamps::new(),
+            removed: Tombstones::new(),
+        }
+    }
+
+    /// Demote `other` into this todo's subtask tree; its nodes get new ids, as they're new subtasks here.
+    pub fn adopt(&mut self, other: Todo) {
+        let mut node = Subtask {
+            id: Id::new(),
+            title: other.title,
+            completed: other.completed,
+            created_at: other.created_at,
+            completed_at: other.completed_at,
+            description: other.description,
+            children: other.subtasks,
+            rank: String::new(),
+            stamps: Stamps::new(),
+        };
+        node.renumber();
+        self.subtasks.push(node);
+        self.sync_completion();
+    }
+
+    pub fn remove_subtask(&mut self, id: Id) -> Option<Subtask> {
+        let removed = remove_in(&mut self.subtasks, id);
+        self.sync_completion();
+        removed
+    }
+
+    // A node with children is complete exactly when all its children are; applied bottom-up to every depth.
+    // Leaves, and a todo without subtasks, keep their own state.
+    pub fn sync_completion(&mut self) {
+        for s in &mut self.subtasks { s.sync_completion(); }
+        if !self.subtasks.is_empty() {
+            let all_done = self.subtasks.iter().all(|s| s.completed);
+            self.set_completed(all_done);
+        }
+    }
+}
+
+impl Subtask {
+    pub fn new(id: Id, title: String) -> Self {
+        Self {
+            id,
+            title,
+            completed: false,
+            created_at: Some(Utc::now()),
+            completed_at: None,
+            description: String::new(),
+            children: Vec::new(),
+            rank: String::new(),
+            stamps: Stamps::new(),
+        }
+    }
+
+    pub fn set_completed(&mut self, done: bool) {
+        if done && !self.completed {
+            self.completed_at = Some(Utc::now());
+        } else if !done {
+            self.completed_at = None;
+        }
+        self.completed = done;
+    }
+
+    pub fn set_completed_deep(&mut self, done: bool) {
+        self.set_completed(done);
+        for c in &mut self.children { c.set_completed_deep(done); }
+    }
+
+    fn sync_completion(&mut self) {
+        for c in &mut self.children { c.sync_completion(); }
+        if !self.children.is_empty() {
+            let all_done = self.children.iter().all(|c| c.completed);
+            self.set_completed(all_done);
+        }
+    }
+
+    /// Completed and total nodes in this subtree, including itself.
+    pub fn progress(&self) -> (usize, usize) {
+        self.children.iter().fold((self.completed as usize, 1), |(d, t), c| {
+            let (cd, ct) = c.progress();
+            (d + cd, t + ct)
+        })
+    }
+
+    fn renumber(&mut self) {
+        self.id = Id::new();
+        self.stamps.clear();
+        for c in &mut self.children { c.renumber(); }
+    }
+
+    fn upgrade_ids(&mut self, todo: Id) -> bool {
+        let legacy = self.id.is_legacy();
+        self.id = self.id.upgrade_subtask(todo);
+        self.children.iter_mut().fold(legacy, |acc, c| c.upgrade_ids(todo) | acc)
+    }
+
+    /// Pre-order walk yielding each node with its depth (0 = top level).
+    pub fn walk<'a>(list: &'a [Subtask], depth: usize, f: &mut impl FnMut(&'a Subtask, usize)) {
+        for s in list {
+            f(s, depth);
+            Subtask::walk(&s.children, depth + 1, f);
+        }
+    }
+}
+
+fn find_in(list: &[Subtask], id: Id) -> Option<&Subtask> {
+    list.iter().find_map(|s| if s.id == id { Some(s) } else { find_in(&s.children, id) })
+}
+
+fn find_in_mut(list: &mut [Subtask], id: Id) -> Option<&mut Subtask> {
+    for s in list {
+        if s.id == id { return Some(s); }
+        if let Some(found) = find_in_mut(&mut s.children, id) { return Some(found); }
+    }
+    None
+}
+
+fn list_containing_mut(list: &mut Vec<Subtask>, id: Id) -> Option<&mut Vec<Subtask>> {
+    if list.iter().any(|s| s.id == id) {
+        return Some(list);
+    }
+    list.iter_mut().find_map(|s| list_containing_mut(&mut s.children, id))
+}
+
+/// Make sure there is an Inbox project, placed first; returns whether one was added.
+pub fn ensure_inbox(projects: &mut Vec<Project>) -> bool {
+    if projects.iter().any(|p| p.inbox) { return false; }
+    projects.insert(0, Project { inbox: true, ..Project::new(Id::new(), "Inbox".into()) });
+    true
+}
+
+/// Look a task up by id across all projects, with the project that owns it.
+pub fn find_todo(projects: &[Project], id: Id) -> Option<(&Project, &Todo)> {
+    projects.iter().find_map(|p| p.todos.iter().find(|t| t.id == id).map(|t| (p, t)))
+}
+
+pub fn find_todo_mut(projects: &mut [Project], id: Id) -> Option<&mut Todo> {
+    projects.iter_mut().flat_map(|p| p.todos.iter_mut()).find(|t| t.id == id)
+}
+
+/// Run `edit` on the project holding task `id`. This is the path for every change that can complete
+/// a task (checkbox, subtasks, board moves, the API): if the edit completes it while its blockers are
+/// still open (see `deps::completion_blocked`), it is undone and the reason returned instead.
+pub fn edit_project_of<R>(projects: &mut [Project], id: Id, edit: impl FnOnce(&mut Project) -> R) -> Result<R, String> {
+    let blocked = deps::completion_blocked(projects, id);
+    let p = projects.iter_mut().find(|p| p.todos.iter().any(|t| t.id == id)).ok_or("Task not found")?;
+    // Only a blocked task can be refused, so only then is there anything to roll back to
+    let before = blocked.is_some().then(|| p.clone());
+    let out = edit(p);
+    if let (Some(reason), Some(before)) = (blocked, before) && p.todos.iter().any(|t| t.id == id && t.completed) {
+        *p = before;
+        return Err(reason);
+    }
+    Ok(out)
+}
+
+/// `edit_project_of` for changes to the task itself.
+pub fn edit_todo<R>(projects: &mut [Project], id: Id, edit: impl FnOnce(&mut Todo) -> R) -> Result<R, String> {
+    edit_project_of(projects, id, |p| p.todos.iter_mut().find(|t| t.id == id).map(edit))?.ok_or_else(|| "Task not found".into())
+}
+
+/// Replace ids from before UUIDs, and every reference to them, with their upgraded ids (see
+/// `Id::upgrade_project`); returns whether there were any. Safe to run on upgraded data.
+pub fn upgrade_ids(projects: &mut [Project]) -> bool {
+    let mut legacy = false;
+    for p in projects {
+        legacy |= p.id.is_legacy();
+        p.id = p.id.upgrade_project();
+        p.removed = std::mem::take(&mut p.removed).into_iter().map(|(id, stamp)| (id.upgrade_todo(), stamp)).collect();
+        for t in &mut p.todos {
+            legacy |= t.id.is_legacy();
+            t.id = t.id.upgrade_todo();
+            for b in &mut t.blocked_by { *b = b.upgrade_todo(); }
+            let todo = t.id;
+            t.removed = std::mem::take(&mut t.removed).into_iter().map(|(id, stamp)| (id.upgrade_subtask(todo), stamp)).collect();
+            for s in &mut t.subtasks { legacy |= s.upgrade_ids(todo); }
+        }
+    }
+    legacy
+}
+
+/// Drag-and-drop reorder shared by todos and subtasks: the item at `src_idx` lands just before the one at `dst_idx`.
+pub fn move_before<T>(list: &mut Vec<T>, src_idx: usize, dst_idx: usize) {
+    let item = list.remove(src_idx);
+    let insert_idx = if src_idx < dst_idx { dst_idx - 1 } else { dst_idx };
+    list.insert(insert_idx, item);
+}
+
+fn remove_in(list: &mut Vec<Subtask>, id: Id) -> Option<Subtask> {
+    if let Some(idx) = list.iter().position(|s| s.id == id) {
+        return Some(list.remove(idx));
+    }
+    list.iter_mut().find_map(|s| remove_in(&mut s.children, id))
 }
@@ -0,0 +1,214 @@
+use chrono::{DateTime, Local, NaiveDate, Utc};
+
+use crate::export::html::{self, escape};
+use crate::export::pdf::Line;
+use crate::models::{Project, Subtask};
+
+/// A task or subtask mentioned in a report; subtasks carry the path of their ancestors' titles.
+#[derive(Clone, PartialEq)]
+pub struct ReportItem {
+    pub title: String,
+    pub parent: Option<String>,
+    pub date: NaiveDate,
+}
+
+impl ReportItem {
+    pub fn label(&self) -> String {
+        match &self.parent {
+            Some(parent) => format!("{} › {}", parent, self.title),
+            None => self.title.clone(),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub struct ProjectReport {
+    pub name: String,
+    pub completed: Vec<ReportItem>,
+    pub created: Vec<ReportItem>,
+    // `date` is the due date
+    pub overdue: Vec<ReportItem>,
+    // Items still open when the period ended
+    pub open_at_end: usize,
+}
+
+impl ProjectReport {
+    /// Share of work finished in the period: completed / (completed + still open at the end).
+    pub fn completion_rate(&self) -> Option<f64> {
+        let total = self.completed.len() + self.open_at_end;
+        (total > 0).then(|| self.completed.len() as f64 / total as f64)
+    }
+
+    pub fn rate_label(&self) -> String {
+        self.completion_rate().map(|r| format!("{:.0}%", r * 100.0)).unwrap_or_else(|| "n/a".into())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.completed.is_empty() && self.created.is_empty() && self.overdue.is_empty()
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "Completed: {} · Created: {} · Overdue: {} · Completion rate: {}",
+            self.completed.len(), self.created.len(), self.overdue.len(), self.rate_label()
+        )
+    }
+
+    // (heading, items, prefix for the item date)
+    pub fn sections(&self) -> [(&'static str, &[ReportItem], &'static str); 3] {
+        [("Completed", &self.completed, ""), ("Created", &self.created, ""), ("Overdue", &self.overdue, "due ")]
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub struct Report {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub projects: Vec<ProjectReport>,
+}
+
+fn local_date(ts: DateTime<Utc>) -> NaiveDate {
+    ts.with_timezone(&Local).date_naive()
+}
+
+// Completion/creation bookkeeping shared by todos and subtasks
+fn tally(
+    pr: &mut ProjectReport,
+    item: ReportItem,
+    created_at: Option<DateTime<Utc>>,
+    completed_at: Option<DateTime<Utc>>,
+    completed: bool,
+    from: NaiveDate,
+    to: NaiveDate,
+) {
+    let in_range = |d: NaiveDate| d >= from && d <= to;
+    let created = created_at.map(local_date);
+    let done = completed_at.map(local_date);
+    if let Some(d) = created.filter(|d| in_range(*d)) {
+        pr.created.push(ReportItem { date: d, ..item.clone() });
+    }
+    if let Some(d) = done.filter(|d| in_range(*d)) {
+        pr.completed.push(ReportItem { date: d, ..item });
+    }
+    // Existed by the end of the period (unknown creation counts as existing) and not finished by then
+    let existed = created.is_none_or(|d| d <= to);
+    let finished_by_end = match done {
+        Some(d) => d <= to,
+        // Completed before timestamps were tracked
+        None => completed,
+    };
+    if existed && !finished_by_end {
+        pr.open_at_end += 1;
+    }
+}
+
+fn tally_subtasks(pr: &mut ProjectReport, list: &[Subtask], path: &str, from: NaiveDate, to: NaiveDate) {
+    for s in list {
+        let item = ReportItem { title: s.title.clone(), parent: Some(path.to_string()), date: from };
+        tally(pr, item, s.created_at, s.completed_at, s.completed, from, to);
+        tally_subtasks(pr, &s.children, &format!("{} › {}", path, s.title), from, to);
+    }
+}
+
+/// Build a report over `from..=to` (local dates); `today` bounds what counts as overdue.
+pub fn build(projects: &[Project], from: NaiveDate, to: NaiveDate, today: NaiveDate) -> Report {
+    let mut out = Vec::new();
+    for p in projects {
+        let mut pr = ProjectReport { name: p.name.clone(), completed: Vec::new(), created: Vec::new(), overdue: Vec::new(), open_at_end: 0 };
+        for t in &p.todos {
+            let item = ReportItem { title: t.title.clone(), parent: None, date: from };
+            tally(&mut pr, item, t.created_at, t.completed_at, t.completed, from, to);
+            // Overdue: due by the end of the period, already past, and not done on time
+            if let Some(due) = t.due
+                && due <= to
+                && due < today
+            {
+                let done_on_time = match t.completed_at {
+                    Some(ts) => local_date(ts) <= due,
+                    None => t.completed,
+                };
+                if !done_on_time {
+                    pr.overdue.push(ReportItem { title: t.title.clone(), parent: None, date: due });
+                }
+            }
+            tally_subtasks(&mut pr, &t.subtasks, &t.title, from, to);
+        }
+        for list in [&mut pr.completed, &mut pr.created, &mut pr.overdue] {
+            list.sort_by_key(|i| i.date);
+        }
+        out.push(pr);
+    }
+    Report { from, to, projects: out }
+}
+
+impl Report {
+    pub fn title(&self) -> String {
+        format!("Progress report {} – {}", self.from, self.to)
+    }
+
+    // File name stem for exports
+    pub fn file_stem(&self) -> String {
+        format!("report-{}-{}", self.from, self.to)
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!("# {}\n", self.title());
+        for pr in &self.projects {
+            out.push_str(&format!("\n## {}\n\n{}\n", pr.name, pr.summary()));
+            if pr.is_empty() {
+                out.push_str("\n_No activity in this period._\n");
+                continue;
+            }
+            for (heading, items, date_prefix) in pr.sections() {
+                if items.is_empty() { continue; }
+                out.push_str(&format!("\n### {}\n\n", heading));
+                for i in items {
+                    let mark = if heading == "Completed" { "[x]" } else { "[ ]" };
+                    out.push_str(&format!("- {} {} ({}{})\n", mark, i.label(), date_prefix, i.date));
+                }
+            }
+        }
+        out
+    }
+
+    pub fn to_html(&self) -> String {
+        let mut body = format!(r#"<div class="header"><h1 class="title">{}</h1></div>"#, escape(&self.title()));
+        for pr in &self.projects {
+            let total = pr.completed.len() + pr.open_at_end;
+            body.push_str(r#"<section class="report-project">"#);
+            body.push_str(&format!(r#"<h2>{}</h2><div class="report-summary">{}</div><p class="meta">{}</p>"#,
+                escape(&pr.name), html::progress_bar(pr.completed.len(), total), escape(&pr.summary())));
+            if pr.is_empty() {
+                body.push_str(r#"<p class="meta">No activity in this period.</p>"#);
+            }
+            for (heading, items, date_prefix) in pr.sections() {
+                if items.is_empty() { continue; }
+                body.push_str(&format!(r#"<details class="todo-more" open><summary>{} ({})</summary><ul class="subtasks">"#, heading, items.len()));
+                for i in items {
+                    body.push_str(&format!(r#"<li class="sub-item"><span class="sub-title">{}</span><span class="meta">{}{}</span></li>"#,
+                        escape(&i.label()), date_prefix, i.date));
+                }
+                body.push_str("</ul></details>");
+            }
+            body.push_str("</section>");
+        }
+        html::page(&self.title(), &body)
+    }
+
+    pub fn to_pdf_lines(&self) -> Vec<Line> {
+        let mut lines = vec![(self.title(), 16.0), (String::new(), 10.0)];
+        for pr in &self.projects {
+            lines.push((pr.name.clone(), 14.0));
+            lines.push((pr.summary().replace('·', "|"), 10.0));
+            for (heading, items, date_prefix) in pr.sections() {
+                if items.is_empty() { continue; }
+                lines.push((format!("  {}", heading), 12.0));
+                for i in items {
+                    lines.push((format!("    - {} ({}{})", i.label().replace('›', ">"), date_prefix, i.date), 11.0));
+                }
+            }
+            lines.push((String::new(), 10.0));
+        }
+        lines
+    }
+}
@@ -0,0 +1,145 @@
+// User preferences, stored as `settings.json` in the OS config dir (apart from the task data).
+use directories::ProjectDirs;
+use dioxus::logger::tracing::{error, warn};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    io,
+    path::PathBuf,
+};
+
+use crate::api;
+use crate::events::Hook;
+use crate::models::Filter;
+use crate::workspace::{self, Workspace, DEFAULT_WORKSPACE};
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Theme {
+    System,
+    Light,
+    Dark,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 3] = [Theme::System, Theme::Light, Theme::Dark];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Theme::System => "Follow system",
+            Theme::Light => "Light",
+            Theme::Dark => "Dark",
+        }
+    }
+
+    // Value of the `data-theme` attribute the stylesheet keys on
+    pub fn attr(self) -> &'static str {
+        match self {
+            Theme::System => "system",
+            Theme::Light => "light",
+            Theme::Dark => "dark",
+        }
+    }
+}
+
+// Missing fields take their defaults, so older settings files keep loading
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub theme: Theme,
+    // Filter the task list starts with
+    pub default_filter: Filter,
+    // Ask before removing a task or clearing completed ones
+    pub confirm_delete: bool,
+    // Folder the export save dialog opens in; `None` lets the OS pick
+    pub export_dir: Option<PathBuf>,
+    pub open_after_export: bool,
+    pub workspaces: Vec<Workspace>,
+    // Name of the workspace opened on start
+    pub workspace: String,
+    // Save by appending to a change journal instead of rewriting projects.json
+    pub journal: bool,
+    // Names this device's change file in sync folders; generated on first start
+    pub device_id: String,
+    // Local HTTP API for scripts and plugins (see `api`)
+    pub api_enabled: bool,
+    pub api_port: u16,
+    // Token the API requires; generated when it's first turned on
+    pub api_token: String,
+    // Webhooks and commands run on task changes (see `events`)
+    pub hooks: Vec<Hook>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            theme: Theme::System,
+            default_filter: Filter::All,
+            confirm_delete: true,
+            export_dir: None,
+            open_after_export: false,
+            workspaces: workspace::default_workspaces(),
+            workspace: DEFAULT_WORKSPACE.into(),
+            journal: false,
+            device_id: String::new(),
+            api_enabled: false,
+            api_port: api::DEFAULT_PORT,
+            api_token: String::new(),
+            hooks: Vec::new(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn find_workspace(&self, name: &str) -> Option<&Workspace> {
+        self.workspaces.iter().find(|w| w.name == name)
+    }
+
+    /// A saved workspace already keeping its data in `ws`'s folder; names differing only in
+    /// punctuation or case share a default folder.
+    pub fn workspace_sharing_dir(&self, ws: &Workspace) -> Option<&Workspace> {
+        let dir = ws.data_dir().ok()?;
+        self.workspaces.iter().find(|w| w.data_dir().is_ok_and(|d| d == dir))
+    }
+
+    /// The saved current workspace, falling back to the first one (or a fresh default).
+    pub fn current_workspace(&self) -> Workspace {
+        self.find_workspace(&self.workspace)
+            .or(self.workspaces.first())
+            .cloned()
+            .unwrap_or_else(|| Workspace::new(DEFAULT_WORKSPACE.into(), None))
+    }
+}
+
+fn settings_file_path() -> io::Result<PathBuf> {
+    let proj = ProjectDirs::from("com", "dx", "dx_todo_app")
+        .ok_or_else(|| io::Error::other("unable to get project dirs"))?;
+    let dir = proj.config_dir().to_path_buf();
+    fs::create_dir_all(&dir)?;
+    Ok(dir.join("settings.json"))
+}
+
+// A missing file means defaults; a broken one is logged and replaced on the next save
+pub fn load() -> Settings {
+    let Ok(path) = settings_file_path() else { return Settings::default() };
+    match fs::read_to_string(&path) {
+        Ok(s) => serde_json::from_str(&s).unwrap_or_else(|e| {
+            warn!("[Settings] Failed to parse {}: {}", path.display(), e);
+            Settings::default()
+        }),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Settings::default(),
+        Err(e) => {
+            warn!("[Settings] Failed to read {}: {}", path.display(), e);
+            Settings::default()
+        }
+    }
+}
+
+pub fn save(settings: &Settings) {
+    let res = settings_file_path().and_then(|path| {
+        let json = serde_json::to_string_pretty(settings).map_err(io::Error::other)?;
+        fs::write(path, json)
+    });
+    if let Err(e) = res {
+        error!("[Settings] Failed to save settings: {}", e);
+    }
+}
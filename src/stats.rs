@@ -0,0 +1,94 @@
+use chrono::{DateTime, Duration, Local, NaiveDate, Utc};
+
+use crate::id::Id;
+use crate::models::{Project, Subtask};
+
+#[derive(Clone, PartialEq)]
+pub struct ProjectStats {
+    pub id: Id,
+    pub name: String,
+    pub open_todos: usize,
+    pub completed_todos: usize,
+    pub open_subtasks: usize,
+    pub completed_subtasks: usize,
+}
+
+impl ProjectStats {
+    pub fn todo_total(&self) -> usize { self.open_todos + self.completed_todos }
+    pub fn subtask_total(&self) -> usize { self.open_subtasks + self.completed_subtasks }
+
+    // Todo completion in whole percent; 0 for an empty project
+    pub fn percent(&self) -> usize {
+        (self.completed_todos * 100).checked_div(self.todo_total()).unwrap_or(0)
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub struct OpenTask {
+    pub project_id: Id,
+    pub project: String,
+    pub todo_id: Id,
+    pub title: String,
+    pub created: NaiveDate,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct Stats {
+    pub projects: Vec<ProjectStats>,
+    // Oldest first, one entry per day ending today
+    pub completed_per_day: Vec<(NaiveDate, usize)>,
+    pub oldest_open: Vec<OpenTask>,
+    // Consecutive days with at least one completion, ending today (or yesterday if nothing yet today)
+    pub streak: usize,
+}
+
+pub const CHART_DAYS: i64 = 30;
+const OLDEST_LIMIT: usize = 5;
+
+fn local_date(ts: DateTime<Utc>) -> NaiveDate {
+    ts.with_timezone(&Local).date_naive()
+}
+
+pub fn compute(projects: &[Project], today: NaiveDate) -> Stats {
+    let mut per_project = Vec::new();
+    let mut completion_days: Vec<NaiveDate> = Vec::new();
+    let mut open: Vec<OpenTask> = Vec::new();
+
+    for p in projects {
+        let mut ps = ProjectStats { id: p.id, name: p.name.clone(), open_todos: 0, completed_todos: 0, open_subtasks: 0, completed_subtasks: 0 };
+        for t in &p.todos {
+            if t.completed { ps.completed_todos += 1 } else { ps.open_todos += 1 }
+            completion_days.extend(t.completed_at.map(local_date));
+            if !t.completed && let Some(created) = t.created_at {
+                open.push(OpenTask { project_id: p.id, project: p.name.clone(), todo_id: t.id, title: t.title.clone(), created: local_date(created) });
+            }
+            Subtask::walk(&t.subtasks, 0, &mut |s, _| {
+                if s.completed { ps.completed_subtasks += 1 } else { ps.open_subtasks += 1 }
+                completion_days.extend(s.completed_at.map(local_date));
+            });
+        }
+        per_project.push(ps);
+    }
+
+    let start = today - Duration::days(CHART_DAYS - 1);
+    let completed_per_day = (0..CHART_DAYS)
+        .map(|i| {
+            let day = start + Duration::days(i);
+            (day, completion_days.iter().filter(|d| **d == day).count())
+        })
+        .collect();
+
+    // Walk back from today; a quiet today doesn't break yesterday's streak
+    let active = |d: NaiveDate| completion_days.contains(&d);
+    let mut day = if active(today) { today } else { today - Duration::days(1) };
+    let mut streak = 0;
+    while active(day) {
+        streak += 1;
+        day -= Duration::days(1);
+    }
+
+    open.sort_by_key(|o| o.created);
+    open.truncate(OLDEST_LIMIT);
+
+    Stats { projects: per_project, completed_per_day, oldest_open: open, streak }
+}
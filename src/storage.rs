@@ -1,62 +1,639 @@
-use crate::models::{Project, Todo};
-use directories::ProjectDirs;
+use crate::crdt;
+use crate::events;
+use crate::git::{self, Repo};
+use crate::id::Id;
+use crate::journal;
+use crate::models::{upgrade_ids, Project, Todo};
+use crate::workspace;
+use dioxus::logger::tracing::{error, info, warn};
 use std::{
-    fs::{self, File},
-    io::{self, Read, Write},
-    path::PathBuf,
+    fs::{self, File, OpenOptions, TryLockError},
+    io::{self, Read},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    sync::{Arc, Mutex, OnceLock},
+    thread,
+    time::{Duration, Instant},
 };
+use tokio::sync::watch;
 
-fn todos_file_path() -> io::Result<PathBuf> {
-    let proj = ProjectDirs::from("com", "dx", "dx_todo_app")
-        .ok_or_else(|| io::Error::other("unable to get project dirs"))?;
-    let dir = proj.data_dir().to_path_buf();
-    fs::create_dir_all(&dir)?;
-    Ok(dir.join("todos.json"))
+// Saves are coalesced: written once edits pause for SAVE_DELAY, and at least every SAVE_MAX_DELAY
+const SAVE_DELAY: Duration = Duration::from_millis(500);
+const SAVE_MAX_DELAY: Duration = Duration::from_secs(3);
+// In journal mode, the log is folded into a new snapshot after this many entries
+const COMPACT_AFTER: usize = 200;
+
+// Folder of the open workspace; `None` until one is opened, meaning the app data dir
+static DATA_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+// The projects as last read or written by this instance, serialized; anything else on disk
+// (projects.json with its journal, or the project files in git mode) is an external change
+static SYNCED: Mutex<Option<String>> = Mutex::new(None);
+// Held open while this instance owns the data dir; the OS drops the lock if the process dies
+static LOCK: Mutex<Option<File>> = Mutex::new(None);
+static READ_ONLY: AtomicBool = AtomicBool::new(false);
+// Append each change to projects.log instead of rewriting projects.json (see `journal`)
+static JOURNALED: AtomicBool = AtomicBool::new(false);
+// Save as per-project files in a git repository, a commit per save (see `git`)
+static GIT_MODE: AtomicBool = AtomicBool::new(false);
+// Journal entries replayed by the last load; they count toward the next compaction
+static REPLAYED: AtomicUsize = AtomicUsize::new(0);
+// Replaces the generated message of the next commit in git mode
+static COMMIT_MESSAGE: Mutex<Option<String>> = Mutex::new(None);
+
+/// Point all loads and saves at another folder (switching workspaces).
+pub fn set_data_dir(dir: PathBuf) {
+    flush();
+    info!("[Storage] Using data dir {}", dir.display());
+    *LOCK.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    *SYNCED.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    events::reset();
+    *DATA_DIR.lock().unwrap_or_else(|e| e.into_inner()) = Some(dir);
+}
+
+/// Take the data dir's lock file so no other instance writes there. Returns `false` when
+/// another instance holds it; saves are then skipped until a different folder is opened.
+pub fn lock_data_dir() -> io::Result<bool> {
+    let path = data_dir()?.join(".lock");
+    let file = OpenOptions::new().create(true).truncate(false).write(true).open(&path)?;
+    let locked = match file.try_lock() {
+        Ok(()) => true,
+        Err(TryLockError::WouldBlock) => false,
+        Err(TryLockError::Error(e)) => return Err(e),
+    };
+    READ_ONLY.store(!locked, Ordering::Relaxed);
+    if locked {
+        *LOCK.lock().unwrap_or_else(|e| e.into_inner()) = Some(file);
+    } else {
+        warn!("[Storage] {} is locked by another instance; opening read-only", path.display());
+    }
+    Ok(locked)
+}
+
+pub fn set_journaled(on: bool) {
+    if JOURNALED.swap(on, Ordering::Relaxed) != on {
+        info!("[Storage] Journal mode {}", if on { "on" } else { "off" });
+    }
+}
+
+pub fn set_git_mode(on: bool) {
+    if GIT_MODE.swap(on, Ordering::Relaxed) != on {
+        info!("[Storage] Git mode {}", if on { "on" } else { "off" });
+    }
+}
+
+pub fn is_git_mode() -> bool {
+    GIT_MODE.load(Ordering::Relaxed)
+}
+
+/// Use `message` for the next git commit instead of describing the changes.
+pub fn set_commit_message(message: String) {
+    *COMMIT_MESSAGE.lock().unwrap_or_else(|e| e.into_inner()) = Some(message);
+}
+
+fn journal_path(projects_path: &Path) -> PathBuf {
+    projects_path.with_extension("log")
+}
+
+// Bring a snapshot up to date with the changes logged since it was written
+fn replay_journal(mut projects: Vec<Project>, projects_path: &Path) -> Vec<Project> {
+    let path = journal_path(projects_path);
+    let replayed = journal::replay(&path, &mut projects);
+    REPLAYED.store(replayed.as_ref().map_or(0, |n| *n), Ordering::Relaxed);
+    match replayed {
+        Ok(0) => {}
+        Ok(n) => info!("[Storage] Replayed {} journal entr{} from {}", n, if n == 1 { "y" } else { "ies" }, path.display()),
+        Err(e) => error!("[Storage] Failed to read {}: {}", path.display(), e),
+    }
+    projects
+}
+
+pub fn is_read_only() -> bool {
+    READ_ONLY.load(Ordering::Relaxed)
+}
+
+fn set_synced(projects: &[Project]) {
+    *SYNCED.lock().unwrap_or_else(|e| e.into_inner()) = serde_json::to_string(projects).ok();
+}
+
+fn synced_projects() -> Vec<Project> {
+    let mut projects: Vec<Project> = SYNCED.lock().unwrap_or_else(|e| e.into_inner()).as_deref()
+        .and_then(|s| serde_json::from_str(s).ok())
+        .unwrap_or_default();
+    upgrade_ids(&mut projects);
+    projects
 }
 
-fn projects_file_path() -> io::Result<PathBuf> {
-    let proj = ProjectDirs::from("com", "dx", "dx_todo_app")
-        .ok_or_else(|| io::Error::other("unable to get project dirs"))?;
-    let dir = proj.data_dir().to_path_buf();
+pub fn data_dir() -> io::Result<PathBuf> {
+    let dir = match DATA_DIR.lock().unwrap_or_else(|e| e.into_inner()).clone() {
+        // No folder could be worked out for the workspace; never fall through to the working dir
+        Some(dir) if dir.as_os_str().is_empty() => return Err(io::Error::other("no data folder is available")),
+        Some(dir) => dir,
+        None => workspace::app_data_dir()?,
+    };
     fs::create_dir_all(&dir)?;
-    Ok(dir.join("projects.json"))
+    Ok(dir)
+}
+
+fn todos_file_path() -> io::Result<PathBuf> {
+    Ok(data_dir()?.join("todos.json"))
+}
+
+pub fn projects_file_path() -> io::Result<PathBuf> {
+    Ok(data_dir()?.join("projects.json"))
 }
 
 pub fn load_or_migrate_projects() -> Vec<Project> {
+    let mut projects = read_projects();
+    // Numeric ids from before UUIDs are replaced, and the upgraded data written back right away
+    // so the journal and project files only ever hold one kind
+    let upgraded = upgrade_ids(&mut projects);
+    // Data saved before merge bookkeeping gets its first stamps and ranks
+    crdt::stamp(&[], &mut projects);
+    if upgraded {
+        info!("[Storage] Upgraded the ids of {} project(s) to UUIDs", projects.len());
+        if is_git_mode() {
+            set_commit_message("Give projects and tasks unique ids".into());
+        }
+        save_projects(&projects);
+    }
+    projects
+}
+
+fn read_projects() -> Vec<Project> {
+    // Git mode keeps a file per project; until the first save there, fall through to projects.json
+    if is_git_mode()
+        && let Ok(dir) = data_dir()
+        && git::has_tree(&dir)
+    {
+        match git::read_tree(&dir) {
+            Ok(list) => return list,
+            Err(e) => error!("[Storage] Failed to read the project files in {}: {}", dir.display(), e),
+        }
+    }
+
     // Preferred: projects.json
-    if let Ok(path) = projects_file_path() {
-        if let Ok(mut f) = File::open(&path) {
-            let mut s = String::new();
-            if f.read_to_string(&mut s).is_ok() {
-                if let Ok(list) = serde_json::from_str::<Vec<Project>>(&s) {
-                    return list;
-                }
-            }
+    if let Ok(path) = projects_file_path()
+        && let Ok(mut f) = File::open(&path)
+    {
+        let mut s = String::new();
+        match f.read_to_string(&mut s).map_err(|e| e.to_string()).and_then(|_| serde_json::from_str::<Vec<Project>>(&s).map_err(|e| e.to_string())) {
+            Ok(list) => return replay_journal(list, &path),
+            Err(e) => warn!("[Storage] Failed to read {}: {}", path.display(), e),
         }
     }
 
     // Migration: wrap old todos.json into a Default Project
-    if let Ok(tpath) = todos_file_path() {
-        if let Ok(mut f) = File::open(&tpath) {
-            let mut s = String::new();
-            if f.read_to_string(&mut s).is_ok() {
-                if let Ok(todos) = serde_json::from_str::<Vec<Todo>>(&s) {
-                    let projects = vec![Project { id: 1, name: "Default Project".into(), todos }];
-                    save_projects(&projects);
-                    return projects;
-                }
-            }
+    if let Ok(tpath) = todos_file_path()
+        && let Ok(mut f) = File::open(&tpath)
+    {
+        let mut s = String::new();
+        if f.read_to_string(&mut s).is_ok()
+            && let Ok(todos) = serde_json::from_str::<Vec<Todo>>(&s)
+        {
+            info!("[Storage] Migrating {} todo(s) from {}", todos.len(), tpath.display());
+            let projects = vec![Project { todos, ..Project::new(Id::new(), "Default Project".into()) }];
+            save_projects(&projects);
+            return projects;
         }
     }
-    Vec::new()
+    projects_file_path().map(|path| replay_journal(Vec::new(), &path)).unwrap_or_default()
+}
+
+#[derive(Clone, PartialEq)]
+pub enum SaveStatus {
+    Saved,
+    // Changes are queued or being written
+    Pending,
+    Failed(String),
+}
+
+enum Job {
+    Save(PathBuf, Vec<Project>, Origin),
+    // The state to compare the next save's events with, when that isn't a save (see `events`)
+    Observe(Vec<Project>),
+    // Write queued changes now; the sender, if any, is told when that's done
+    Flush(Option<Sender<()>>),
+    // The projects as currently on disk (just loaded or pulled), to diff the next save against,
+    // and how many journal entries are on top of the snapshot
+    Baseline(PathBuf, Vec<Project>, usize),
+}
+
+// Where a saved state comes from: edits here send events, changes merged in from elsewhere
+// (other devices, another instance, history) sent theirs where they were made
+#[derive(Clone, Copy, PartialEq)]
+enum Origin {
+    Edit,
+    Merge,
+}
+
+// Background writer: serialization and IO happen on its own thread, not the UI's
+struct Saver {
+    jobs: Sender<Job>,
+    status: Arc<watch::Sender<SaveStatus>>,
+}
+
+static SAVER: OnceLock<Saver> = OnceLock::new();
+
+fn saver() -> &'static Saver {
+    SAVER.get_or_init(|| {
+        let (jobs, rx) = mpsc::channel();
+        let status = Arc::new(watch::Sender::new(SaveStatus::Saved));
+        let thread_status = status.clone();
+        thread::Builder::new()
+            .name("save".into())
+            .spawn(move || run_saver(rx, thread_status))
+            .expect("failed to start the save thread");
+        Saver { jobs, status }
+    })
 }
 
+fn send_job(job: Job) {
+    let _ = saver().jobs.send(job);
+}
+
+/// Queue the projects to be written in the background; later calls replace earlier ones.
 pub fn save_projects(projects: &[Project]) {
+    queue_save(projects, Origin::Edit);
+}
+
+/// Like `save_projects`, for projects that took in changes made elsewhere (another device or
+/// instance, a pull); those aren't sent as events again.
+pub fn save_merged(projects: &[Project]) {
+    queue_save(projects, Origin::Merge);
+}
+
+fn queue_save(projects: &[Project], origin: Origin) {
+    if is_read_only() {
+        warn!("[Storage] Not saving: the data dir is locked by another instance");
+        return;
+    }
+    match projects_file_path() {
+        Ok(path) => {
+            saver().status.send_replace(SaveStatus::Pending);
+            send_job(Job::Save(path, projects.to_vec(), origin));
+        }
+        Err(e) => error!("[Storage] Failed to save projects: {}", e),
+    }
+}
+
+/// Compare the next save's events with `projects` (merged in, but already on disk).
+pub fn observe(projects: &[Project]) {
+    send_job(Job::Observe(projects.to_vec()));
+}
+
+/// Tell the writer what's on disk now, e.g. after loading.
+pub fn set_baseline(projects: &[Project]) {
+    set_synced(projects);
     if let Ok(path) = projects_file_path() {
-        if let Ok(mut f) = File::create(path) {
-            if let Ok(s) = serde_json::to_string_pretty(projects) {
-                let _ = f.write_all(s.as_bytes());
+        send_job(Job::Baseline(path, projects.to_vec(), REPLAYED.swap(0, Ordering::Relaxed)));
+    }
+}
+
+/// Write queued changes now, without waiting for them (e.g. on navigation).
+pub fn request_flush() {
+    send_job(Job::Flush(None));
+}
+
+/// Write queued changes and wait until they're on disk (closing, switching folders).
+pub fn flush() {
+    if SAVER.get().is_none() {
+        return;
+    }
+    let (done, wait) = mpsc::channel();
+    send_job(Job::Flush(Some(done)));
+    let _ = wait.recv();
+}
+
+/// Follow the background writer's progress, for display.
+pub fn save_status() -> watch::Receiver<SaveStatus> {
+    saver().status.subscribe()
+}
+
+// What the writer knows is on disk, to journal the next save as a diff
+#[derive(Default)]
+struct Written {
+    state: Option<(PathBuf, Vec<Project>)>,
+    // Journal entries since the last snapshot
+    entries: usize,
+}
+
+fn run_saver(jobs: Receiver<Job>, status: Arc<watch::Sender<SaveStatus>>) {
+    let mut queued: Option<(PathBuf, Vec<Project>, Instant)> = None;
+    let mut written = Written::default();
+    loop {
+        let job = match &queued {
+            Some((_, _, since)) => {
+                let wait = SAVE_DELAY.min(SAVE_MAX_DELAY.saturating_sub(since.elapsed()));
+                match jobs.recv_timeout(wait) {
+                    Ok(job) => Some(job),
+                    Err(RecvTimeoutError::Timeout) => None,
+                    Err(RecvTimeoutError::Disconnected) => Some(Job::Flush(None)),
+                }
+            }
+            None => match jobs.recv() {
+                Ok(job) => Some(job),
+                Err(_) => return,
+            },
+        };
+        match job {
+            Some(Job::Baseline(path, projects, entries)) => {
+                events::observe(&projects);
+                written = Written { state: Some((path, projects)), entries };
+                continue;
+            }
+            Some(Job::Observe(projects)) => {
+                events::observe(&projects);
+                continue;
+            }
+            Some(Job::Save(path, projects, origin)) => {
+                // Events come from each save, in order, before saves are coalesced for writing
+                match origin {
+                    Origin::Edit => events::saved(&projects),
+                    Origin::Merge => events::observe(&projects),
+                }
+                status.send_replace(SaveStatus::Pending);
+                let since = queued.take().map_or_else(Instant::now, |(_, _, since)| since);
+                queued = Some((path, projects, since));
+                if since.elapsed() < SAVE_MAX_DELAY {
+                    continue;
+                }
+            }
+            Some(Job::Flush(done)) => {
+                if let Some((path, projects, _)) = queued.take() {
+                    store_projects(path, projects, &mut written, &status);
+                }
+                if let Some(done) = done {
+                    let _ = done.send(());
+                }
+                continue;
             }
+            None => {}
         }
+        if let Some((path, projects, _)) = queued.take() {
+            store_projects(path, projects, &mut written, &status);
+        }
+    }
+}
+
+// Write the latest queued state. In journal mode that's one entry diffing it against the last
+// write, so a burst of edits to the same item is appended as a single op
+fn store_projects(path: PathBuf, projects: Vec<Project>, written: &mut Written, status: &watch::Sender<SaveStatus>) {
+    if JOURNALED.load(Ordering::Relaxed) && !is_git_mode() {
+        append_projects(path, projects, written, status);
+    } else {
+        write_projects(path, projects, written, status);
+    }
+}
+
+// Append the changes since the last write to the journal; a full snapshot when there's
+// nothing to diff against yet or the journal has grown long
+fn append_projects(path: PathBuf, projects: Vec<Project>, written: &mut Written, status: &watch::Sender<SaveStatus>) {
+    let base = match &written.state {
+        Some((base_path, base)) if *base_path == path && written.entries < COMPACT_AFTER => base,
+        _ => return write_projects(path, projects, written, status),
+    };
+    let mut projects = projects;
+    crdt::stamp(base, &mut projects);
+    let ops = journal::diff(base, &projects);
+    if !ops.is_empty() {
+        if let Err(e) = journal::append(&journal_path(&path), ops) {
+            error!("[Storage] Failed to append to the journal for {}: {}", path.display(), e);
+            status.send_replace(SaveStatus::Failed(e.to_string()));
+            return;
+        }
+        written.entries += 1;
+    }
+    set_synced(&projects);
+    written.state = Some((path, projects));
+    status.send_replace(SaveStatus::Saved);
+}
+
+// Write a full snapshot; it includes everything journaled so far, so the journal is dropped.
+// Written through a temp file so readers (the watcher, sync tools) never see a half-written file
+fn write_projects(path: PathBuf, mut projects: Vec<Project>, written: &mut Written, status: &watch::Sender<SaveStatus>) {
+    match &written.state {
+        Some((base_path, base)) if *base_path == path => crdt::stamp(base, &mut projects),
+        _ => crdt::stamp(&[], &mut projects),
+    }
+    if is_git_mode() {
+        return commit_projects(path, projects, written, status);
+    }
+    let res = serde_json::to_string_pretty(&projects).map_err(io::Error::other).and_then(|s| {
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, &s)?;
+        fs::rename(&tmp, &path)?;
+        match fs::remove_file(journal_path(&path)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+        Ok(())
+    });
+    match res {
+        Ok(()) => {
+            set_synced(&projects);
+            *written = Written { state: Some((path, projects)), entries: 0 };
+            status.send_replace(SaveStatus::Saved);
+        }
+        Err(e) => {
+            error!("[Storage] Failed to save projects to {}: {}", path.display(), e);
+            status.send_replace(SaveStatus::Failed(e.to_string()));
+        }
+    }
+}
+
+/// The data files as changed by someone else since this instance last read or wrote them.
+pub struct ExternalChange {
+    // The projects as this instance last saw them, to stamp both sides' edits against
+    pub base: Vec<Project>,
+    pub theirs: Vec<Project>,
+}
+
+impl ExternalChange {
+    /// Record the changed files as seen, so they aren't reported again.
+    pub fn accept(&self) {
+        set_synced(&self.theirs);
+    }
+}
+
+/// The files loads read from: projects.json and its journal, and in git mode the project files.
+pub fn source_paths() -> io::Result<Vec<PathBuf>> {
+    let path = projects_file_path()?;
+    let mut paths = vec![journal_path(&path), path];
+    if is_git_mode() {
+        paths.extend(git::tree_paths(&data_dir()?));
+    }
+    Ok(paths)
+}
+
+/// Compare the data files, read as a load would, with what this instance last read or wrote.
+/// Unreadable or half-written files are skipped; the next change event tries again.
+pub fn external_change() -> Option<ExternalChange> {
+    let mut theirs = match read_sources() {
+        Ok(projects) => projects,
+        Err(e) => {
+            warn!("[Storage] Ignoring unreadable change to the data files: {}", e);
+            return None;
+        }
+    };
+    // Written by an older version, perhaps; compared with ours by upgraded id
+    upgrade_ids(&mut theirs);
+    let text = serde_json::to_string(&theirs).ok()?;
+    if SYNCED.lock().unwrap_or_else(|e| e.into_inner()).as_deref() == Some(text.as_str()) {
+        return None;
+    }
+    Some(ExternalChange { base: synced_projects(), theirs })
+}
+
+// The projects on disk in the current mode, like `read_projects` without its fallbacks
+fn read_sources() -> io::Result<Vec<Project>> {
+    let dir = data_dir()?;
+    if is_git_mode() && git::has_tree(&dir) {
+        return git::read_tree(&dir);
+    }
+    let path = projects_file_path()?;
+    let mut projects: Vec<Project> = serde_json::from_str(&fs::read_to_string(&path)?).map_err(io::Error::other)?;
+    journal::replay(&journal_path(&path), &mut projects)?;
+    Ok(projects)
+}
+
+// Git mode: write the project files and commit them with a message describing the change
+fn commit_projects(path: PathBuf, projects: Vec<Project>, written: &mut Written, status: &watch::Sender<SaveStatus>) {
+    let Some(dir) = path.parent().map(Path::to_path_buf) else { return };
+    let message = COMMIT_MESSAGE.lock().unwrap_or_else(|e| e.into_inner()).take().unwrap_or_else(|| match &written.state {
+        Some((base_path, base)) if *base_path == path => git::describe(base, &projects),
+        _ => "Save workspace".into(),
+    });
+    let repo = Repo::new(dir.clone());
+    let res = git::exclusive(|| {
+        let mut projects = projects;
+        // The files moved since our last write (a pull, most likely): this state was queued before
+        // it and doesn't include it, so it's merged into the files rather than written over them
+        if let Some((base_path, base)) = &written.state
+            && *base_path == path
+            && git::has_tree(&dir)
+            && let Ok(disk) = git::read_tree(&dir)
+            && disk != *base
+        {
+            info!("[Storage] Project files changed since the last save; merging");
+            projects = crdt::merge(&disk, &projects);
+        }
+        repo.init().and_then(|_| git::write_tree(&dir, &projects)).and_then(|_| repo.commit_all(&message)).map(|c| (c, projects))
+    });
+    match res {
+        Ok((committed, projects)) => {
+            if committed {
+                info!("[Storage] Committed: {}", message);
+            }
+            set_synced(&projects);
+            *written = Written { state: Some((path, projects)), entries: 0 };
+            status.send_replace(SaveStatus::Saved);
+        }
+        Err(e) => {
+            error!("[Storage] Failed to commit projects in {}: {}", dir.display(), e);
+            status.send_replace(SaveStatus::Failed(e.to_string()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The tests share the storage statics
+    static SERIAL: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn journal_from_earlier_sessions_is_compacted_on_the_next_save() {
+        let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+        let dir = std::env::temp_dir().join(format!("dx-todo-journal-{}", Id::new()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("projects.json");
+        let mut projects = vec![Project::new(Id::new(), "Work".into())];
+        crdt::stamp(&[], &mut projects);
+        fs::write(&path, serde_json::to_string(&projects).unwrap()).unwrap();
+        // Earlier sessions, each a few edits: none reached COMPACT_AFTER on its own
+        for n in 0..COMPACT_AFTER {
+            let mut next = projects.clone();
+            next[0].todos.push(Todo::new(Id::new(), format!("Task {n}")));
+            crdt::stamp(&projects, &mut next);
+            journal::append(&journal_path(&path), journal::diff(&projects, &next)).unwrap();
+            projects = next;
+        }
+
+        // Reopen: load, then the baseline the app sets after loading
+        let snapshot: Vec<Project> = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        let loaded = replay_journal(snapshot, &path);
+        assert!(loaded == projects);
+        let mut written = Written { state: Some((path.clone(), loaded.clone())), entries: REPLAYED.swap(0, Ordering::Relaxed) };
+        assert_eq!(written.entries, COMPACT_AFTER);
+
+        let mut next = loaded;
+        next[0].name = "Job".into();
+        append_projects(path.clone(), next, &mut written, &watch::Sender::new(SaveStatus::Saved));
+        assert!(!journal_path(&path).exists());
+        assert_eq!(written.entries, 0);
+        let saved: Vec<Project> = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved[0].name, "Job");
+        assert_eq!(saved[0].todos.len(), COMPACT_AFTER);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn own_saves_arent_external_changes_in_any_mode() {
+        let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+        let dir = std::env::temp_dir().join(format!("dx-todo-external-{}", Id::new()));
+        *DATA_DIR.lock().unwrap() = Some(dir.clone());
+        let path = projects_file_path().unwrap();
+        let status = watch::Sender::new(SaveStatus::Saved);
+        let mut projects = vec![Project::new(Id::new(), "Work".into())];
+        projects[0].todos = vec![Todo::new(Id::new(), "Write report".into()), Todo::new(Id::new(), "Call Sam".into())];
+        let edit = |projects: &mut Vec<Project>, n: usize| {
+            projects[0].todos[0].title = format!("Write report, take {n}");
+            projects[0].todos[0].add_subtask(None, format!("Draft {n}"));
+            projects[0].todos[1].completed = n.is_multiple_of(2);
+        };
+
+        // Journal mode: the snapshot and each appended entry
+        JOURNALED.store(true, Ordering::Relaxed);
+        let mut written = Written::default();
+        write_projects(path.clone(), projects.clone(), &mut written, &status);
+        assert!(external_change().is_none());
+        for n in 0..3 {
+            edit(&mut projects, n);
+            append_projects(path.clone(), projects.clone(), &mut written, &status);
+            assert!(journal_path(&path).exists());
+            assert!(external_change().is_none());
+        }
+        // ... but not an entry another instance appended
+        let ours = written.state.clone().unwrap().1;
+        let mut theirs = ours.clone();
+        theirs[0].todos[1].title = "Email Sam".into();
+        crdt::stamp(&ours, &mut theirs);
+        journal::append(&journal_path(&path), journal::diff(&ours, &theirs)).unwrap();
+        let change = external_change().expect("the appended entry");
+        assert_eq!(change.theirs[0].todos[1].title, "Email Sam");
+        change.accept();
+        assert!(external_change().is_none());
+        JOURNALED.store(false, Ordering::Relaxed);
+
+        // Git mode: each commit, but not project files changed by a pull
+        GIT_MODE.store(true, Ordering::Relaxed);
+        let mut written = Written::default();
+        for n in 3..5 {
+            edit(&mut projects, n);
+            write_projects(path.clone(), projects.clone(), &mut written, &status);
+            assert!(*status.borrow() == SaveStatus::Saved);
+            assert!(external_change().is_none());
+        }
+        let mut pulled = written.state.clone().unwrap().1;
+        pulled[0].name = "Job".into();
+        git::write_tree(&dir, &pulled).unwrap();
+        assert_eq!(external_change().expect("the changed project file").theirs[0].name, "Job");
+        GIT_MODE.store(false, Ordering::Relaxed);
+
+        *DATA_DIR.lock().unwrap() = None;
+        *SYNCED.lock().unwrap() = None;
+        let _ = fs::remove_dir_all(dir);
     }
 }
@@ -0,0 +1,94 @@
+// Smart views: task lists gathered from every project rather than the active one.
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{Duration, Local, NaiveDate};
+
+use crate::id::Id;
+use crate::models::{Project, Todo};
+
+// How far ahead Upcoming looks and how far back Completed recently looks
+const UPCOMING_DAYS: i64 = 7;
+const RECENT_DAYS: i64 = 7;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SmartView {
+    Inbox,
+    Today,
+    Upcoming,
+    Recent,
+}
+
+impl SmartView {
+    pub const ALL: [SmartView; 4] = [SmartView::Inbox, SmartView::Today, SmartView::Upcoming, SmartView::Recent];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SmartView::Inbox => "Inbox",
+            SmartView::Today => "Today",
+            SmartView::Upcoming => "Upcoming",
+            SmartView::Recent => "Completed recently",
+        }
+    }
+
+    pub fn empty_text(self) -> &'static str {
+        match self {
+            SmartView::Inbox => "Inbox is empty. Capture a task above and sort it into a project later.",
+            SmartView::Today => "Nothing due today or overdue.",
+            SmartView::Upcoming => "Nothing due in the next 7 days.",
+            SmartView::Recent => "Nothing completed in the last 7 days.",
+        }
+    }
+
+    fn matches(self, project: &Project, todo: &Todo, today: NaiveDate) -> bool {
+        match self {
+            SmartView::Inbox => project.inbox,
+            SmartView::Today => !todo.completed && todo.due.is_some_and(|d| d <= today),
+            SmartView::Upcoming => !todo.completed && todo.due.is_some_and(|d| d > today && d <= today + Duration::days(UPCOMING_DAYS)),
+            SmartView::Recent => todo.completed && todo.completed_at.is_some_and(|ts| ts.with_timezone(&Local).date_naive() > today - Duration::days(RECENT_DAYS)),
+        }
+    }
+
+    /// Matching tasks with their owning project (id, name). Dated views sort by due date,
+    /// Completed recently puts the latest first, Inbox keeps its own order.
+    pub fn collect(self, projects: &[Project], today: NaiveDate) -> Vec<(Id, String, Todo)> {
+        let mut out: Vec<(Id, String, Todo)> = projects.iter()
+            .flat_map(|p| p.todos.iter().filter(move |t| self.matches(p, t, today)).map(move |t| (p.id, p.name.clone(), t.clone())))
+            .collect();
+        match self {
+            SmartView::Today | SmartView::Upcoming => out.sort_by_key(|(_, _, t)| t.due),
+            SmartView::Recent => out.sort_by_key(|(_, _, t)| std::cmp::Reverse(t.completed_at)),
+            SmartView::Inbox => {}
+        }
+        out
+    }
+
+    /// Number shown next to the view's name: open tasks, or completions for Completed recently.
+    pub fn badge_count(self, projects: &[Project], today: NaiveDate) -> usize {
+        match self {
+            SmartView::Recent => self.collect(projects, today).len(),
+            _ => self.collect(projects, today).iter().filter(|(_, _, t)| !t.completed).count(),
+        }
+    }
+}
+
+// Route segment, e.g. `/view/today`
+impl fmt::Display for SmartView {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let slug = match self {
+            SmartView::Inbox => "inbox",
+            SmartView::Today => "today",
+            SmartView::Upcoming => "upcoming",
+            SmartView::Recent => "recent",
+        };
+        f.write_str(slug)
+    }
+}
+
+impl FromStr for SmartView {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SmartView::ALL.into_iter().find(|v| v.to_string() == s).ok_or_else(|| format!("unknown view: {s}"))
+    }
+}
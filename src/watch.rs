@@ -0,0 +1,30 @@
+use dioxus::logger::tracing::warn;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+
+/// Watch files for changes; a folder among them stands for the files directly in it. Their
+/// folders are watched rather than the files themselves, since editors, sync tools and git
+/// often replace files instead of writing them in place.
+/// Events stop when the returned watcher is dropped.
+pub fn watch_files(paths: &[PathBuf]) -> notify::Result<(RecommendedWatcher, UnboundedReceiver<()>)> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let wanted = paths.to_vec();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<Event>| match res {
+        Ok(event) => {
+            let ours = event.paths.iter().any(|p| wanted.iter().any(|w| p == w || p.parent() == Some(w.as_path())));
+            if ours && !matches!(event.kind, EventKind::Access(_)) {
+                let _ = tx.send(());
+            }
+        }
+        Err(e) => warn!("[Watch] {}", e),
+    })?;
+    let mut dirs: Vec<&Path> = paths.iter().filter_map(|p| p.parent()).collect();
+    dirs.extend(paths.iter().filter(|p| p.is_dir()).map(PathBuf::as_path));
+    dirs.sort();
+    dirs.dedup();
+    for dir in dirs {
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    }
+    Ok((watcher, rx))
+}
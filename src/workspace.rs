@@ -0,0 +1,87 @@
+// Workspaces: named task collections, each with its own data folder.
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use std::{io, path::PathBuf};
+
+use crate::cli::Args;
+use crate::settings::Settings;
+
+pub const DEFAULT_WORKSPACE: &str = "Default";
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Workspace {
+    pub name: String,
+    // A chosen folder (e.g. a synced one); `None` keeps the data under the app data dir
+    #[serde(default)]
+    pub dir: Option<PathBuf>,
+    // Shared folder to sync this workspace with other devices through; see `folder_sync`
+    #[serde(default)]
+    pub sync_dir: Option<PathBuf>,
+    // Keep the data as a git repository with a commit per save; see `git`
+    #[serde(default)]
+    pub git: bool,
+}
+
+impl Workspace {
+    pub fn new(name: String, dir: Option<PathBuf>) -> Self {
+        Self { name, dir, sync_dir: None, git: false }
+    }
+
+    /// Folder holding this workspace's `projects.json`. The default workspace uses the app data
+    /// dir itself, so data from before workspaces existed stays where it was.
+    pub fn data_dir(&self) -> io::Result<PathBuf> {
+        if let Some(dir) = &self.dir {
+            return Ok(dir.clone());
+        }
+        let root = app_data_dir()?;
+        if self.name == DEFAULT_WORKSPACE {
+            Ok(root)
+        } else {
+            Ok(root.join("workspaces").join(slug(&self.name)))
+        }
+    }
+}
+
+pub fn app_data_dir() -> io::Result<PathBuf> {
+    let proj = ProjectDirs::from("com", "dx", "dx_todo_app")
+        .ok_or_else(|| io::Error::other("unable to get project dirs"))?;
+    Ok(proj.data_dir().to_path_buf())
+}
+
+pub fn default_workspaces() -> Vec<Workspace> {
+    vec![Workspace::new(DEFAULT_WORKSPACE.into(), None)]
+}
+
+// Folder-safe version of a workspace name
+fn slug(name: &str) -> String {
+    let s: String = name
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+        .collect();
+    let s = s.trim_matches('-').to_string();
+    if s.is_empty() { "workspace".into() } else { s }
+}
+
+/// Workspace for this run: `--data-dir` wins, then `--workspace` (saved to the list if new),
+/// then the saved choice. Returns the label to show and the data folder.
+pub fn resolve_start(args: &Args, settings: &mut Settings) -> io::Result<(String, PathBuf)> {
+    if let Some(dir) = &args.data_dir {
+        return Ok((dir.display().to_string(), dir.clone()));
+    }
+    let ws = match &args.workspace {
+        Some(name) => match settings.find_workspace(name) {
+            Some(ws) => ws.clone(),
+            None => {
+                let ws = Workspace::new(name.clone(), None);
+                if let Some(other) = settings.workspace_sharing_dir(&ws) {
+                    return Err(io::Error::other(format!("\"{name}\" would share its folder with \"{}\"", other.name)));
+                }
+                settings.workspaces.push(ws.clone());
+                crate::settings::save(settings);
+                ws
+            }
+        },
+        None => settings.current_workspace(),
+    };
+    Ok((ws.name.clone(), ws.data_dir()?))
+}